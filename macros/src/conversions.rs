@@ -0,0 +1,71 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::ext::IdentExt;
+use syn::{Data, DataStruct, DeriveInput, Fields, Ident};
+
+// Gets the fields of `input`, along with the name of the attribute each one corresponds to.
+fn named_fields(input: &DeriveInput, derive: &str) -> Vec<(Ident, String)> {
+	match &input.data {
+		Data::Struct(DataStruct { fields: Fields::Named(fields), .. }) => fields
+			.named
+			.iter()
+			.map(|field| {
+				let ident = field.ident.clone().expect("named fields always have an ident");
+				let name = ident.unraw().to_string();
+				(ident, name)
+			})
+			.collect(),
+		_ => panic!("`{derive}` only works on structs with named fields"),
+	}
+}
+
+pub fn expand_to_value(input: DeriveInput) -> TokenStream {
+	let (idents, names): (Vec<_>, Vec<_>) = named_fields(&input, "ToValue").into_iter().unzip();
+	let name = &input.ident;
+	let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+	quote! {
+		#[automatically_derived]
+		impl #impl_generics ::quest::ToValue for #name #ty_generics #where_clause {
+			fn to_value(self) -> ::quest::Value {
+				use ::quest::value::AttributedMut;
+
+				let mut object = ::quest::ToValue::to_value(::quest::value::ty::Wrap::with_parent(
+					(),
+					::quest::value::ty::Object::instance(),
+				));
+
+				#(
+					object
+						.set_attr(::quest::ToValue::to_value(#names), ::quest::ToValue::to_value(self.#idents))
+						.expect("setting attributes on a new object can't fail");
+				)*
+
+				object
+			}
+		}
+	}
+}
+
+pub fn expand_from_value(input: DeriveInput) -> TokenStream {
+	let (idents, names): (Vec<_>, Vec<_>) = named_fields(&input, "FromValue").into_iter().unzip();
+	let name = &input.ident;
+	let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+	quote! {
+		#[automatically_derived]
+		impl #impl_generics ::quest::FromValue for #name #ty_generics #where_clause {
+			fn from_value(value: ::quest::Value) -> ::quest::Result<Self> {
+				use ::quest::value::TryAttributed;
+
+				Ok(Self {
+					#(
+						#idents: ::quest::FromValue::from_value(
+							value.try_get_unbound_attr(::quest::ToValue::to_value(#names))?
+						)?,
+					)*
+				})
+			}
+		}
+	}
+}
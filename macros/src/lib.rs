@@ -1,5 +1,6 @@
 use syn::{parse_macro_input, AttributeArgs, DeriveInput, ItemFn};
 mod allocated;
+mod conversions;
 mod named_type;
 mod quest_fn;
 
@@ -17,6 +18,20 @@ pub fn allocated(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
 	allocated::expand_allocated(input).into()
 }
 
+#[proc_macro_derive(ToValue)]
+pub fn to_value(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+	let input = parse_macro_input!(input as DeriveInput);
+
+	conversions::expand_to_value(input).into()
+}
+
+#[proc_macro_derive(FromValue)]
+pub fn from_value(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+	let input = parse_macro_input!(input as DeriveInput);
+
+	conversions::expand_from_value(input).into()
+}
+
 #[proc_macro_attribute]
 pub fn quest_fn(
 	attr: proc_macro::TokenStream,
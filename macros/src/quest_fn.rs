@@ -1,87 +1,280 @@
-#![allow(unused)]
 use proc_macro2::TokenStream;
 use quote::{format_ident, quote, ToTokens};
-use syn::ItemFn;
-use syn::MetaNameValue;
-use syn::NestedMeta;
+use syn::ext::IdentExt;
+use syn::spanned::Spanned;
+use syn::{
+	AttributeArgs, Error, FnArg, ItemFn, Lit, Meta, MetaNameValue, NestedMeta, Pat, PatType,
+	ReturnType, Type,
+};
 
-fn get_qs_name(args: &[NestedMeta]) -> Option<String> {
+// What kind of argument a parameter is bound to.
+enum Kind {
+	Required,
+	Optional,
+	Keyword { is_optional: bool },
+	Rest,
+}
+
+struct Param {
+	name: String,
+	kind: Kind,
+}
+
+fn get_qs_name(args: &[NestedMeta]) -> syn::Result<Option<String>> {
 	for arg in args {
 		match arg {
-			NestedMeta::Meta(syn::Meta::NameValue(MetaNameValue {
-				path: syn::Path { leading_colon: None, segments },
-				lit: syn::Lit::Str(litstr),
-				..
-			})) if segments.len() == 1 && segments[0].ident == "name" => {
-				let mut s = litstr.token().to_string();
-				s.pop();
-				s.remove(0);
-				return Some(s);
+			NestedMeta::Meta(Meta::NameValue(MetaNameValue { path, lit: Lit::Str(name), .. }))
+				if path.is_ident("name") =>
+			{
+				return Ok(Some(name.value()));
 			}
-			_ => {}
+			// For backwards compatibility, `#[quest_fn(name)]` is the same as `#[quest_fn]`.
+			NestedMeta::Meta(Meta::Path(path)) if path.is_ident("name") => {}
+			_ => return Err(Error::new_spanned(arg, "expected `name = \"...\"`")),
+		}
+	}
+
+	Ok(None)
+}
+
+fn is_option(ty: &Type) -> bool {
+	match ty {
+		Type::Path(path) => path.path.segments.last().is_some_and(|seg| seg.ident == "Option"),
+		_ => false,
+	}
+}
+
+fn returns_result(output: &ReturnType) -> bool {
+	match output {
+		ReturnType::Type(_, ty) => match &**ty {
+			Type::Path(path) => path.path.segments.last().is_some_and(|seg| seg.ident == "Result"),
+			_ => false,
+		},
+		ReturnType::Default => false,
+	}
+}
+
+// Removes the `#[this]`, `#[keyword]`, and `#[rest]` attributes from `param`, returning which
+// were present.
+fn take_param_attrs(param: &mut PatType) -> (bool, bool, bool) {
+	let (mut is_this, mut is_keyword, mut is_rest) = (false, false, false);
+
+	param.attrs.retain(|attr| {
+		if attr.path.is_ident("this") {
+			is_this = true;
+		} else if attr.path.is_ident("keyword") {
+			is_keyword = true;
+		} else if attr.path.is_ident("rest") {
+			is_rest = true;
+		} else {
+			return true;
+		}
+
+		false
+	});
+
+	(is_this, is_keyword, is_rest)
+}
+
+fn doc_of(item: &ItemFn) -> String {
+	let lines = item.attrs.iter().filter(|attr| attr.path.is_ident("doc")).filter_map(|attr| {
+		match attr.parse_meta() {
+			Ok(Meta::NameValue(MetaNameValue { lit: Lit::Str(doc), .. })) => Some(doc.value()),
+			_ => None,
+		}
+	});
+
+	lines.map(|line| line.trim().to_string()).collect::<Vec<_>>().join("\n").trim().to_string()
+}
+
+pub fn expand_quest_fn(args: AttributeArgs, mut item: ItemFn) -> TokenStream {
+	match expand(&args, &mut item) {
+		Ok(rustfn) => quote! { #item #rustfn },
+		Err(err) => {
+			let err = err.to_compile_error();
+			quote! { #item #err }
 		}
 	}
-	None
 }
 
-pub fn expand_quest_fn(args: syn::AttributeArgs, item: ItemFn) -> TokenStream {
+fn expand(args: &[NestedMeta], item: &mut ItemFn) -> syn::Result<TokenStream> {
 	let vis = &item.vis;
 	let name = &item.sig.ident;
-	let qs_name = format_ident!("qs_{}", get_qs_name(&args).unwrap_or_else(|| name.to_string()));
+	let qs_ident = format_ident!("qs_{}", name.unraw());
+	let qs_name = get_qs_name(args)?.unwrap_or_else(|| name.unraw().to_string());
+	let doc = doc_of(item);
 
-	let positional_len = item.sig.inputs.len() - 1;
+	let mut has_receiver = false;
+	let mut has_this = false;
+	let mut params = Vec::new();
 
-	let attrs = item.sig.inputs.iter().skip(1).enumerate().map(|(i, x)| {
-		let kind = match x {
-			syn::FnArg::Typed(syn::PatType { ref ty, .. }) => ty,
-			_ => unreachable!(),
+	for (index, input) in item.sig.inputs.iter_mut().enumerate() {
+		let param = match input {
+			FnArg::Receiver(receiver) if receiver.reference.is_none() => {
+				has_receiver = true;
+				has_this = true;
+				continue;
+			}
+			FnArg::Receiver(receiver) => {
+				return Err(Error::new_spanned(
+					receiver,
+					"`#[quest_fn]` methods must take `self` by value",
+				))
+			}
+			FnArg::Typed(param) => param,
 		};
-		quote! {
-			args[#i].try_downcast::<#kind>()?
+
+		let (is_this, is_keyword, is_rest) = take_param_attrs(param);
+		let span = param.span();
+		let param_name = match &*param.pat {
+			Pat::Ident(ident) => ident.ident.unraw().to_string(),
+			_ => format!("arg{index}"),
+		};
+
+		if is_this {
+			if index != 0 {
+				return Err(Error::new(span, "only the first parameter can be `#[this]`"));
+			}
+
+			has_this = true;
+			continue;
 		}
-	});
 
-	// let (returns_result, to_value) = match item.sig.output {
-	// 	syn::ReturnType::Type(_, ref foo)
-	// 		if matches!(&**foo,
-	// 		syn::Type::Path(syn::TypePath {
-	// 			qself: None,
-	// 			path: syn::Path { leading_colon: None, segments },
-	// 		}) if segments.len() == 1 && segments[0].ident == "Result") =>
-	// 	{
-	// 		false
-	// 	}
-	// 	_ => true,
-	// };
-
-	// let ret = if to_value {
-	// 	quote! {
-	// 		Ok(result)
-	// 	}
-	// } else {
-	// 	quote! {
-	// 		result
-	// 	}
-	// }
-
-	let qs_fn = quote! {
-		#vis fn #qs_name (
-			object: ::quest::Value,
-			args: ::quest::vm::Args<'_>
-		) -> ::quest::Result<::quest::Value>
+		let kind = if is_rest {
+			Kind::Rest
+		} else if is_keyword {
+			Kind::Keyword { is_optional: is_option(&param.ty) }
+		} else if is_option(&param.ty) {
+			Kind::Optional
+		} else {
+			Kind::Required
+		};
+
+		if let Some(last) =
+			params.iter().rev().find(|param: &&Param| !matches!(param.kind, Kind::Keyword { .. }))
 		{
-			args.assert_no_keyword()?;
-			args.assert_positional_len(#positional_len)?;
+			match (&last.kind, &kind) {
+				(Kind::Rest, Kind::Required | Kind::Optional | Kind::Rest) => {
+					return Err(Error::new(span, "`#[rest]` must be the last positional parameter"))
+				}
+				(Kind::Optional, Kind::Required) => {
+					return Err(Error::new(span, "required parameters must come before optional ones"))
+				}
+				_ => {}
+			}
+		}
+
+		params.push(Param { name: param_name, kind });
+	}
+
+	let names_of = |filter: fn(&Kind) -> bool| {
+		params.iter().filter(move |param| filter(&param.kind)).map(|param| &param.name)
+	};
+	let required = names_of(|kind| matches!(kind, Kind::Required));
+	let optional = names_of(|kind| matches!(kind, Kind::Optional));
+	let required_keyword = names_of(|kind| matches!(kind, Kind::Keyword { is_optional: false }));
+	let optional_keyword = names_of(|kind| matches!(kind, Kind::Keyword { is_optional: true }));
+	let rest = match params.iter().find(|param| matches!(param.kind, Kind::Rest)) {
+		Some(param) => {
+			let name = &param.name;
+			quote!(Some(#name))
+		}
+		None => quote!(None),
+	};
 
-			let this = object.try_downcast::<Self>()?;
-			let result = this.#name(#(#attrs,)*);
-			result
+	let signature = quote! {
+		::quest::value::ty::rustfn::Signature {
+			this: #has_this,
+			required: &[#(#required),*],
+			optional: &[#(#optional),*],
+			required_keyword: &[#(#required_keyword),*],
+			optional_keyword: &[#(#optional_keyword),*],
+			rest: #rest,
+			doc: #doc,
 		}
 	};
 
-	let mut ts = TokenStream::new();
+	let mut position = 0usize;
+	let mut arguments = Vec::new();
+
+	if has_this {
+		arguments.push(quote!(::quest::FromValue::from_value(this)?));
+	}
+
+	for param in &params {
+		arguments.push(match param.kind {
+			Kind::Required => {
+				position += 1;
+				let index = position - 1;
+				quote!(::quest::FromValue::from_value(positional[#index])?)
+			}
+			Kind::Optional => {
+				position += 1;
+				let index = position - 1;
+				quote! {
+					match positional.get(#index) {
+						Some(&value) => ::quest::FromValue::from_value(value)?,
+						None => None,
+					}
+				}
+			}
+			Kind::Keyword { is_optional: false } => {
+				let name = &param.name;
+				quote! {
+					::quest::FromValue::from_value(
+						args.get(#name).expect("required keywords are checked by the signature")
+					)?
+				}
+			}
+			Kind::Keyword { is_optional: true } => {
+				let name = &param.name;
+				quote! {
+					match args.get(#name) {
+						Some(value) => ::quest::FromValue::from_value(value)?,
+						None => None,
+					}
+				}
+			}
+			Kind::Rest => quote! {
+				positional[#position..]
+					.iter()
+					.map(|&value| ::quest::FromValue::from_value(value))
+					.collect::<::quest::Result<_>>()?
+			},
+		});
+	}
+
+	let split_this =
+		if has_this { quote!(let (this, args) = args.split_first()?;) } else { quote!() };
+
+	let callee = if has_receiver { quote!(Self::#name) } else { name.to_token_stream() };
+
+	let call = quote!(#callee(#(#arguments),*));
+	let body = match &item.sig.output {
+		ReturnType::Default => quote! {
+			#call;
+			Ok(::quest::Value::default())
+		},
+		output if returns_result(output) => quote!(#call.map(::quest::ToValue::to_value)),
+		_ => quote!(Ok(::quest::ToValue::to_value(#call))),
+	};
+
+	Ok(quote! {
+		#[allow(non_upper_case_globals)]
+		#[doc = concat!("The Quest version of [`", stringify!(#name), "`], generated by `#[quest_fn]`.")]
+		#vis const #qs_ident: ::quest::value::ty::RustFn =
+			::quest::value::ty::RustFn::new(&::quest::value::ty::rustfn::Inner {
+				name: #qs_name,
+				func: |args: ::quest::vm::Args<'_>| -> ::quest::Result<::quest::Value> {
+					#split_this
+					#signature.check(args)?;
+
+					#[allow(unused_variables)]
+					let positional = args.positional();
 
-	ts.extend(item.into_token_stream());
-	ts.extend(qs_fn.into_token_stream());
-	ts
+					#body
+				},
+				signature: Some(&#signature),
+			});
+	})
 }
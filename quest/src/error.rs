@@ -5,7 +5,7 @@ mod stacktrace;
 pub use stacktrace::Stacktrace;
 
 /// An error type that contains both a [`Stacktrace`] and an [`ErrorKind`].
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 #[must_use]
 pub struct Error {
 	pub stacktrace: Stacktrace,
@@ -16,7 +16,7 @@ pub struct Error {
 pub type Result<T> = std::result::Result<T, Error>;
 
 /// Possible errors that can be thrown during execution within Quest.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 #[must_use]
 #[non_exhaustive]
 pub enum ErrorKind {
@@ -24,9 +24,16 @@ pub enum ErrorKind {
 	///
 	/// This means either [`Gc::as_ref`](crate::value::Gc::as_ref) was called while the value is
 	/// mutably borrowed, or [`Gc::as_mut`](crate::value::Gc::as_mut) was called when the value was
-	/// either mutably or immutably borrowed.
+	/// either mutably or immutably borrowed, by either the current thread or by another thread for
+	/// longer than the [contention strategy](crate::value::gc::Contention) allows.
 	AlreadyLocked(Value),
 
+	/// Waiting for the value's rwlock would deadlock, as it's held by a thread that's (indirectly)
+	/// waiting on the current one.
+	///
+	/// See the [contention docs](crate::value::gc::Contention) for more details.
+	Deadlock(Value),
+
 	/// Mutable access on a [frozen value](crate::Value::freeze) was attempted.
 	ValueFrozen(Value),
 
@@ -36,6 +43,13 @@ pub enum ErrorKind {
 		attribute: Value,
 	},
 
+	/// A value's parents would cause it to (indirectly) inherit from itself.
+	CyclicParents(Value),
+
+	/// The given list of parents has no consistent [method resolution order](crate::Value::mro),
+	/// e.g. because a parent is listed before one of its own ancestors.
+	InconsistentParents(Value),
+
 	/// An `expected` type was required but a `given` was given.
 	InvalidTypeGiven {
 		expected: crate::value::Typename,
@@ -64,6 +78,10 @@ pub enum ErrorKind {
 	/// Indicates that iteration should be stopped.
 	StopIteration,
 
+	/// `Kernel::exit` was called with the given status. Like returns, this has no stacktrace, and
+	/// it's up to the host to decide what to do with it.
+	Exit(i32),
+
 	/// A function expected no keyword arguments but they were given.
 	KeywordsGivenWhenNotExpected,
 
@@ -79,6 +97,18 @@ pub enum ErrorKind {
 	/// Too many stackframes encountered
 	StackOverflow,
 
+	/// The [fuel limit](crate::vm::Limits::fuel) was exhausted.
+	OutOfFuel,
+
+	/// The [timeout](crate::vm::Limits::timeout) elapsed.
+	DeadlineExceeded,
+
+	/// More memory than the [heap size limit](crate::vm::Limits::max_heap_size) was allocated.
+	HeapLimitExceeded {
+		size: usize,
+		limit: usize,
+	},
+
 	/// An assertion failed, with an optional message
 	AssertionFailed(Option<crate::value::Gc<crate::value::ty::Text>>),
 
@@ -102,7 +132,12 @@ impl Display for ErrorKind {
 			Self::UnknownAttribute { object, attribute } => {
 				write!(f, "unknown attribute {attribute:?} for {object:?}")
 			}
+			Self::CyclicParents(value) => write!(f, "value {value:?} would be its own ancestor"),
+			Self::InconsistentParents(parents) => {
+				write!(f, "parents {parents:?} have no consistent method resolution order")
+			}
 			Self::AlreadyLocked(value) => write!(f, "value {value:?} is already locked"),
+			Self::Deadlock(value) => write!(f, "waiting for value {value:?} would deadlock"),
 			Self::ValueFrozen(value) => write!(f, "value {value:?} is frozen"),
 			Self::ConversionFailed { object, into } => {
 				write!(f, "conversion {object:?} failed for {into:?}")
@@ -115,6 +150,7 @@ impl Display for ErrorKind {
 				write!(f, "returning value {value:?} from frame {from_frame:?}")
 			}
 			Self::StopIteration => write!(f, "Iteration should be stopped"),
+			Self::Exit(status) => write!(f, "exiting with status {status}"),
 			Self::KeywordsGivenWhenNotExpected => {
 				write!(f, "keyword arguments given when none expected")
 			}
@@ -125,6 +161,11 @@ impl Display for ErrorKind {
 				write!(f, "frame {frame:?} is currently executing")
 			}
 			Self::StackOverflow => write!(f, "too many stackframes are running"),
+			Self::OutOfFuel => write!(f, "ran out of fuel"),
+			Self::DeadlineExceeded => write!(f, "the deadline was exceeded"),
+			Self::HeapLimitExceeded { size, limit } => {
+				write!(f, "heap size limit exceeded ({size} bytes allocated, limit is {limit})")
+			}
 			Self::AssertionFailed(None) => write!(f, "an assertion failed"),
 			Self::AssertionFailed(Some(err)) => write!(f, "an assertion failed: {err:?}"),
 			Self::DivisionByZero(kind) => write!(f, "{kind} by zero"),
@@ -146,7 +187,10 @@ impl From<String> for Error {
 
 impl From<ErrorKind> for Error {
 	fn from(kind: ErrorKind) -> Self {
-		let stacktrace = if matches!(kind, ErrorKind::Return { .. } | ErrorKind::StopIteration) {
+		let stacktrace = if matches!(
+			kind,
+			ErrorKind::Return { .. } | ErrorKind::StopIteration | ErrorKind::Exit(_)
+		) {
 			Stacktrace::empty()
 		} else {
 			Stacktrace::current()
@@ -3,7 +3,7 @@ use crate::vm::{frame, Block};
 use std::fmt::{self, Display, Formatter};
 
 /// A Stacktrace in Quest, representing the callstack at a point in time during execution.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 #[must_use]
 pub struct Stacktrace(Vec<Gc<Block>>);
 
@@ -112,46 +112,69 @@ define_interned! {
 	__parents__ __id__ __name__
 	__get_attr__ __get_unbound_attr__ __set_attr__
 	__del_attr__ __has_attr__ __call_attr__
+	__attrs__ __all_attrs__ __ancestors__ __mro__ __super__
 
 	// Constants
 	r#true "true" r#false "false" null
 
 	// Classes
 	Boolean BoundFn Callable Class Float Integer Kernel List
-	Null Object Pristine RustFn Scope Text
-	Frame Block
+	Null Object Pristine RustFn Scope Text Map Set Range
+	Frame Block Thread Channel Mutex Condvar Task
 
 	// Operators
 	op_add "+" op_sub "-" op_mul "*" op_div "/" op_mod "%" op_pow "**"
 	op_eql "==" op_neq "!=" op_lth "<" op_leq "<=" op_gth ">" op_geq ">=" op_cmp "<=>"
 	op_not "!" op_neg "-@" op_index "[]" op_index_assign "[]=" op_call "()" op_assign "="
 	op_shl "<<" op_shr ">>" op_bitand "&" op_bitor "|" op_bitxor "^" op_bitneg
+	op_range ".." op_range_incl "..="
 
 	// Conversions
-	dbg to_text to_num to_bool to_list to_int to_float
+	dbg to_text to_num to_bool to_list to_int to_float to_set
 
 	// `Object` functions
 	hash clone itself
 	tap pipe then and_then r#else "else" or_else or and
-	display freeze dup
+	display freeze deep_freeze is_frozen dup
+	is_a responds_to typename
 
 	// Kernel functions
 	if_cascade ifl r#if "if"
-	r#while "while" r#return "return"
-	exit abort assert object print rand
-	spawn dump // both are temporary
+	r#while "while" r#return "return" r#yield "yield"
+	exit abort assert object print rand breakpoint
+	spawn dump // `dump` is temporary
+	r#await "await" sleep
 
 	// Frame and Block Functions
-	resume restart create_frame __block__ __args__
+	resume restart create_frame generator __block__ __args__
+
+	// Class functions
+	new_ "new" init name
+
+	// RustFn functions
+	help
 
 	// String functions
 	join concat len
 
 	// List functions
 	push pop shift unshift product shuffle is_empty
+	sort sort_in_place sort_by sort_by_in_place reverse index_of insert remove_at
+	slice flatten uniq zip chunks windows min max binary_search fill rotate
+
+	// Map functions
+	get includes delete merge keys values entries default set_default
+
+	// Set functions
+	add union intersection difference symmetric_difference is_subset is_superset
+
+	// Range functions
+	step start end
 
 	// Enumerator functions
 	map filter reduce each next sum iter tap_each count is_any are_all
+	take skip take_while skip_while enumerate chain flat_map step_by peekable peek cycle
+	group_by first last find min_by max_by partition
 
 	// Integer functions
 	upto downto times chr
@@ -159,6 +182,13 @@ define_interned! {
 
 	// Float functions
 	is_whole
+
+	// Thread, Channel, Mutex and Condvar functions
+	is_finished send recv try_recv select close is_closed
+	lock update set is_locked wait notify_one notify_all
+
+	// Task functions
+	after every all race is_done cancel
 }
 
 // Note that this has to be implemented like this because we manually implement `Hash`.
@@ -195,16 +225,24 @@ impl Intern {
 			return Ok(intern);
 		}
 
+		// Hold the lock while creating the intern, so that two threads interning the same string
+		// at once get the same intern, and so each intern's index matches its text's position.
+		let mut intern_to_text = intern_to_text().write().unwrap();
+
+		if let Ok(intern) = Self::try_from(&*textref) {
+			return Ok(intern);
+		}
+
 		textref.freeze();
 		text.do_not_free();
 
 		// SAFETY: Since `text` will never be freed, and is frozen, we know that it's contents will
 		// always be the same value for the remainder of the program. Thus, we can extend its lifetime.
 		let string = unsafe { std::mem::transmute::<&str, &'static str>(textref.as_str()) };
-		let intern = Self(offset((BUILTIN_LENGTH + text_to_intern().len()) as u64));
+		let intern = Self(offset((BUILTIN_LENGTH + intern_to_text.len()) as u64));
 
+		intern_to_text.push(text);
 		text_to_intern().insert(string, intern);
-		intern_to_text().write().unwrap().push(text);
 
 		Ok(intern)
 	}
@@ -225,15 +263,6 @@ impl Intern {
 		(self.bits() >> 7) as usize
 	}
 
-	pub(crate) unsafe fn try_from_repr(bits: u64) -> Option<Self> {
-		if bits & 0b111_1111 == TAG {
-			debug_assert!((bits as usize >> 7) < (BUILTIN_LENGTH + text_to_intern().len()));
-			Some(Self::from_bits_unchecked(bits))
-		} else {
-			None
-		}
-	}
-
 	/// Converts `self` to its `Text` representation.
 	#[must_use]
 	pub fn as_text(self) -> Gc<Text> {
@@ -0,0 +1,276 @@
+//! A high-level interface for embedding Quest.
+
+use crate::parse::ast::{Compile, Group};
+use crate::parse::{Parser, Syntaxes};
+use crate::value::ty::kernel::Capabilities;
+use crate::value::ty::{Kernel, Text, Wrap};
+use crate::value::{Attributed, AttributedMut, Callable, ToValue, TryAttributed};
+use crate::vm::block::{Builder as BlockBuilder, Local};
+use crate::vm::{frame, limits, Args, Frame, Limits};
+use crate::{ErrorKind, Intern, Result, Value};
+use std::path::Path;
+
+/// A Quest interpreter.
+///
+/// Unlike running blocks directly, everything that's assigned at the top level of [`eval`](
+/// Self::eval)ed code is kept around as a _global_, as are any `$syntax`es that are defined. So,
+/// successive calls to `eval` behave like lines in a REPL.
+///
+/// # Examples
+/// ```
+/// # use quest::{Interpreter, ToValue, value::ty::Integer};
+/// let mut interpreter = Interpreter::new();
+///
+/// interpreter.eval("square = n -> { n * n };").unwrap();
+/// interpreter.set_global("x", 4.to_value()).unwrap();
+///
+/// let result = interpreter.eval("square(x) + 1").unwrap();
+/// assert_eq!(result.downcast::<Integer>().unwrap().get(), 17);
+///
+/// let result = interpreter.call("square", &[3.to_value()]).unwrap();
+/// assert_eq!(result.downcast::<Integer>().unwrap().get(), 9);
+/// ```
+#[derive(Debug)]
+pub struct Interpreter {
+	globals: Value,
+	// These borrow from `sources`, so they must be declared (and thus dropped) before them.
+	syntaxes: Option<Syntaxes<'static>>,
+	sources: Vec<Source>,
+	limits: Limits,
+}
+
+// The source code of an `eval`, which is kept around as the syntaxes it defines borrow from it.
+#[derive(Debug)]
+struct Source {
+	code: Box<str>,
+	file: Option<Box<Path>>,
+}
+
+/// A builder for [`Interpreter`]s with non-default configurations.
+#[derive(Debug, Clone, Default)]
+#[must_use]
+pub struct Builder {
+	capabilities: Capabilities,
+	root: Option<Value>,
+	limits: Limits,
+}
+
+impl Builder {
+	/// Sets what the scripts are allowed to do; defaults to [`Capabilities::ALL`].
+	///
+	/// This is ignored if a [`root`](Self::root) is given.
+	pub fn capabilities(mut self, capabilities: Capabilities) -> Self {
+		self.capabilities = capabilities;
+		self
+	}
+
+	/// Uses `root` instead of a [`Kernel`] for looking up any variables that aren't globals.
+	pub fn root(mut self, root: Value) -> Self {
+		self.root = Some(root);
+		self
+	}
+
+	/// Sets the limits that each call to [`Interpreter::eval`] (and friends) runs with.
+	pub fn limits(mut self, limits: Limits) -> Self {
+		self.limits = limits;
+		self
+	}
+
+	/// Creates the [`Interpreter`].
+	#[must_use]
+	pub fn build(self) -> Interpreter {
+		let root = self.root.unwrap_or_else(|| {
+			if self.capabilities == Capabilities::ALL {
+				Kernel::instance()
+			} else {
+				Kernel::with_capabilities(self.capabilities)
+			}
+		});
+
+		Interpreter {
+			globals: Wrap::with_parent((), root).to_value(),
+			syntaxes: Some(Syntaxes::default()),
+			sources: Vec::new(),
+			limits: self.limits,
+		}
+	}
+}
+
+impl Default for Interpreter {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+fn global_name(name: &str) -> Result<Intern> {
+	Intern::new(Text::from_str(name))
+}
+
+impl Interpreter {
+	/// Creates a new [`Interpreter`] with the default configuration.
+	#[must_use]
+	pub fn new() -> Self {
+		Self::builder().build()
+	}
+
+	/// Creates a [`Builder`] to configure a new interpreter.
+	pub fn builder() -> Builder {
+		Builder::default()
+	}
+
+	/// Executes `source`, returning the value of its last expression.
+	///
+	/// Since the syntaxes that `source` defines are kept around, a copy of `source` is kept until
+	/// the interpreter is dropped.
+	///
+	/// # Errors
+	/// Returns an error if `source` can't be parsed, or if executing it fails.
+	pub fn eval(&mut self, source: &str) -> Result<Value> {
+		self.eval_source(Source { code: source.into(), file: None })
+	}
+
+	/// Reads the file at `path`, and then [`eval`](Self::eval)s it.
+	///
+	/// # Errors
+	/// Returns an error if the file can't be read, or any errors from [`eval`](Self::eval).
+	pub fn eval_file<P: AsRef<Path>>(&mut self, path: P) -> Result<Value> {
+		let path = path.as_ref();
+		let source = std::fs::read_to_string(path)
+			.map_err(|err| ErrorKind::Message(format!("unable to read {}: {err}", path.display())))?;
+
+		self.eval_source(Source { code: source.into_boxed_str(), file: Some(path.into()) })
+	}
+
+	fn eval_source(&mut self, source: Source) -> Result<Value> {
+		// SAFETY: The code and file are boxed, so they don't move even if `sources` reallocates.
+		// They're only dropped along with `self`, after `syntaxes` (the only thing that keeps
+		// borrowing from them once this function returns).
+		let code: *const str = &*source.code;
+		let file = source.file.as_deref().map(|file| file as *const Path);
+		let (code, file) = unsafe { (&*code, file.map(|file| &*file)) };
+		self.sources.push(source);
+
+		let mut parser = Parser::with_syntaxes(code, file, self.syntaxes.take().unwrap_or_default());
+		let group = Group::parse_all(&mut parser);
+		let location = parser.location();
+		self.syntaxes = Some(parser.into_syntaxes());
+
+		let group = group.map_err(|err| {
+			ErrorKind::Message(format!("unable to parse: {:?} at {}", err.kind, err.location))
+		})?;
+
+		let mut builder = BlockBuilder::new(0, location.into());
+		group.compile(&mut builder, Local::Scratch);
+		let frame = Frame::new(builder.build(), Args::default())?;
+
+		let result = self.enter(|| frame.run());
+
+		for (name, value) in frame.as_ref()?.named_locals()? {
+			if name != Intern::__block__ && name != Intern::__args__ {
+				self.globals.set_attr(name, value)?;
+			}
+		}
+
+		result
+	}
+
+	/// Calls the global `name` with `args`.
+	///
+	/// # Errors
+	/// Returns an error if there's no global `name`, or if calling it fails.
+	pub fn call(&mut self, name: &str, args: &[Value]) -> Result<Value> {
+		let function = self.globals.try_get_unbound_attr(global_name(name)?)?;
+
+		self.enter(|| function.call(Args::new(args, &[])))
+	}
+
+	/// Gets the global `name`, if it exists.
+	///
+	/// Like within scripts, this also looks up values in the root (eg `print`).
+	///
+	/// # Errors
+	/// Returns any errors that looking up the attribute causes.
+	pub fn get_global(&self, name: &str) -> Result<Option<Value>> {
+		self.globals.get_unbound_attr(global_name(name)?)
+	}
+
+	/// Sets the global `name` to `value`, so it's visible in all subsequently-[`eval`](
+	/// Self::eval)ed code.
+	///
+	/// # Errors
+	/// Returns any errors that setting the attribute causes.
+	pub fn set_global(&mut self, name: &str, value: Value) -> Result<()> {
+		self.globals.set_attr(global_name(name)?, value)
+	}
+
+	/// Runs `func` with the globals as the root and with the configured limits.
+	fn enter<T>(&self, func: impl FnOnce() -> Result<T>) -> Result<T> {
+		let (old_root, old_limits) = (frame::root(), limits::limits());
+
+		frame::set_root(self.globals);
+		limits::set_limits(self.limits);
+
+		let result = func();
+
+		frame::set_root(old_root);
+		limits::set_limits(old_limits);
+
+		result
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::value::ty::Integer;
+
+	fn int(value: Result<Value>) -> i64 {
+		value.unwrap().downcast::<Integer>().unwrap().get()
+	}
+
+	#[test]
+	fn globals_persist() {
+		let mut interpreter = Interpreter::new();
+
+		interpreter.eval("x = 3; inc = n -> { n + 1 };").unwrap();
+		assert_eq!(int(interpreter.eval("x = inc(x); x * 2")), 8);
+		assert_eq!(int(interpreter.get_global("x").map(Option::unwrap)), 4);
+		assert!(interpreter.get_global("y").unwrap().is_none());
+
+		interpreter.set_global("y", 10.to_value()).unwrap();
+		assert_eq!(int(interpreter.eval("x + y")), 14);
+		assert_eq!(int(interpreter.call("inc", &[41.to_value()])), 42);
+		assert!(interpreter.call("nope", &[]).is_err());
+	}
+
+	#[test]
+	fn syntaxes_persist() {
+		let mut interpreter = Interpreter::new();
+
+		interpreter.eval("$syntax { double $x:token } = { ($x * 2) };").unwrap();
+		assert_eq!(int(interpreter.eval("double 21")), 42);
+	}
+
+	#[test]
+	fn configuration() {
+		let mut interpreter = Interpreter::builder()
+			.capabilities(Capabilities::NONE)
+			.limits(Limits { fuel: Some(1000), ..Limits::default() })
+			.build();
+
+		assert!(interpreter.eval("print(1)").is_err());
+		assert_matches!(
+			interpreter.eval("while({ true }, {})").unwrap_err().kind,
+			ErrorKind::OutOfFuel
+		);
+		assert_eq!(int(interpreter.eval("1 + 2")), 3);
+	}
+
+	#[test]
+	fn parse_errors() {
+		let mut interpreter = Interpreter::new();
+
+		assert!(interpreter.eval("(").is_err());
+		assert_eq!(int(interpreter.eval("1")), 1);
+	}
+}
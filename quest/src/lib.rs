@@ -57,12 +57,29 @@ fn run_code(code: &str) -> Result<Value> {
 pub mod value;
 pub mod error;
 mod intern;
+pub mod interpreter;
 pub mod parse;
 pub mod vm;
 
 pub use error::{Error, ErrorKind, Result};
 pub use intern::Intern;
-pub use value::{ToValue, Value};
+pub use interpreter::Interpreter;
+pub use value::{FromValue, ToValue, Value};
+
+/// Generates a [`RustFn`](value::ty::RustFn) for a function with a typed signature.
+///
+/// For a function `foo`, this creates a `qs_foo` constant which converts its arguments via
+/// [`FromValue`] and its return value via [`ToValue`]. Parameters can be:
+/// - `#[this]` (or a `self` receiver): the `this` of the call, or the first positional argument.
+/// - `T`: a required positional argument.
+/// - `Option<T>`: an optional positional argument, which must come after required ones.
+/// - `#[keyword] T` or `#[keyword] Option<T>`: a required or optional keyword argument.
+/// - `#[rest] Vec<T>`: all remaining positional arguments.
+///
+/// The name used within Quest can be changed with `#[quest_fn(name = "...")]`. The function's
+/// parameters and documentation are available via [`RustFn::signature`](value::ty::RustFn::signature)
+/// and [`RustFn::help`](value::ty::RustFn::help).
+pub use qvm_macros::quest_fn;
 
 #[cfg(miri)]
 extern "Rust" {
@@ -81,6 +98,8 @@ pub unsafe fn alloc<T>(layout: std::alloc::Layout) -> std::ptr::NonNull<T> {
 		std::alloc::handle_alloc_error(layout);
 	}
 
+	vm::limits::record_allocation(layout.size() as isize);
+
 	#[cfg(miri)]
 	miri_static_root(ptr.cast()); // TODO: garbage collection
 
@@ -121,6 +140,8 @@ pub unsafe fn alloc_zeroed<T>(layout: std::alloc::Layout) -> std::ptr::NonNull<T
 		std::alloc::handle_alloc_error(layout);
 	}
 
+	vm::limits::record_allocation(layout.size() as isize);
+
 	#[cfg(miri)]
 	miri_static_root(ptr.cast()); // TODO: garbage collection
 
@@ -144,8 +165,22 @@ pub unsafe fn realloc<T>(
 		std::alloc::handle_alloc_error(layout);
 	}
 
+	vm::limits::record_allocation(new_size as isize - layout.size() as isize);
+
 	#[cfg(miri)]
 	miri_static_root(ptr.cast()); // TODO: garbage collection
 
 	std::ptr::NonNull::new_unchecked(ptr)
 }
+
+/// Frees memory that was allocated via [`alloc`], [`alloc_zeroed`], or [`realloc`].
+///
+/// # Safety
+/// `ptr` must have been returned by one of those functions, and not freed since. `layout` must be
+/// the layout it was allocated with; for memory returned by [`realloc`], that's the original
+/// layout with its size replaced by `new_size`.
+pub unsafe fn dealloc(ptr: *mut u8, layout: std::alloc::Layout) {
+	std::alloc::dealloc(ptr, layout);
+
+	vm::limits::record_allocation(-(layout.size() as isize));
+}
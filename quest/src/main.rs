@@ -11,14 +11,8 @@ use quest::vm::*;
 use quest::Result;
 use std::path::Path;
 
-fn run_code(code: &str, filename: Option<&Path>) -> Result<Value> {
-	let mut parser = Parser::new(code, filename);
-	let mut builder = quest::vm::block::Builder::default();
-	let scratch = quest::vm::block::Local::Scratch;
-
-	ast::Group::parse_all(&mut parser).expect("bad parse").compile(&mut builder, scratch);
-
-	builder.build().run(Default::default())
+fn run_code(code: &str) -> Result<Value> {
+	quest::Interpreter::new().eval(code)
 }
 
 fn setup_tracing() {
@@ -49,26 +43,67 @@ fn main() {
 		run_code(
 			r#"fib=n->{(n<=1).then(n.return);fib(n-1)+fib(n-2)};print(fib(30))"#,
 			// r#"i=0; while({ i < 5_000_000 }, { :1.i = i + 1 })"#,
-			None,
 		)
 		.unwrap();
 
 		return;
 	}
 
-	const USAGE: &str = "usage: -e <expr> | -f <file>";
+	const USAGE: &str = "usage: [--debug] [--profile <folded-file>] [--coverage <lcov-file>] \
+		(-e <expr> | -f <file>)";
+	const PROFILE_REPORT_LEN: usize = 25;
 
-	let mut args = std::env::args().skip(1);
-	let (contents, filename) = match &*args.next().expect(USAGE) {
-		"-f" => {
-			let name = args.next().expect(USAGE);
-			(std::fs::read_to_string(&name).expect("cant open file"), Some(name))
+	let mut args = std::env::args().skip(1).peekable();
+	let mut profile_file = None;
+	let mut coverage_file = None;
+
+	while let Some(flag) = args.next_if(|arg| arg.starts_with("--")) {
+		match &*flag {
+			"--debug" => {
+				debugger::attach(Box::new(debugger::Console::stdio()));
+				debugger::pause_at_next_line();
+			}
+			"--profile" => {
+				profile_file = Some(args.next().expect(USAGE));
+				profiler::start();
+			}
+			"--coverage" => {
+				coverage_file = Some(args.next().expect(USAGE));
+				coverage::start();
+			}
+			_ => panic!("{USAGE}"),
 		}
-		"-e" => (args.next().expect(USAGE), None),
+	}
+
+	let mut interpreter = quest::Interpreter::new();
+	let result = match &*args.next().expect(USAGE) {
+		"-f" => interpreter.eval_file(args.next().expect(USAGE)),
+		"-e" => interpreter.eval(&args.next().expect(USAGE)),
 		_ => panic!("{USAGE}"),
 	};
 
-	match run_code(&contents, filename.as_deref().map(Path::new)) {
+	// Let any tasks the script spawned, but never awaited, finish.
+	if result.is_ok() {
+		event_loop::run_until_idle();
+	}
+
+	if let Some(profile_file) = profile_file {
+		let profile = profiler::stop().expect("profiling was started");
+		profile.write_report(std::io::stderr(), PROFILE_REPORT_LEN).expect("cant write profile");
+
+		let file = std::fs::File::create(&profile_file).expect("cant create profile file");
+		profile.write_folded(std::io::BufWriter::new(file)).expect("cant write profile");
+		eprintln!("folded stacks written to {profile_file}");
+	}
+
+	if let Some(coverage_file) = coverage_file {
+		let coverage = coverage::stop().expect("coverage was started");
+		let file = std::fs::File::create(coverage_file).expect("cant create coverage file");
+		coverage.write_lcov(std::io::BufWriter::new(file)).expect("cant write coverage");
+	}
+
+	match result {
+		Err(quest::Error { kind: quest::ErrorKind::Exit(status), .. }) => std::process::exit(status),
 		Err(err) => {
 			eprintln!("error: {err:#}");
 			std::process::exit(0)
@@ -4,6 +4,7 @@ use crate::parse::{Parser, Result};
 mod assignment;
 mod atom;
 mod block;
+mod class;
 mod expression;
 mod fnargs;
 mod group;
@@ -11,6 +12,7 @@ mod primary;
 pub use assignment::Assignment;
 pub use atom::Atom;
 pub use block::Block;
+pub use class::Class;
 pub use expression::Expression;
 pub use fnargs::FnArgs;
 pub use group::Group;
@@ -0,0 +1,83 @@
+use super::{Block, Compile, FnArgs};
+use crate::parse::token::{ParenType, Token, TokenContents};
+use crate::parse::{ErrorKind, Parser, Result};
+use crate::value::ty::class::ClassClass;
+use crate::value::ty::Singleton;
+use crate::vm::block::{Builder, Local};
+use crate::Intern;
+
+/// A class definition, `class Name(Parent, ...) { body }`, which is the same as
+/// `Name = Class.new("Name", [Parent, ...], { body })`.
+///
+/// `class` is only treated as a keyword when it's directly followed by an identifier, so it can
+/// still be used as a normal variable name.
+#[derive(Debug)]
+pub struct Class<'a> {
+	name: &'a str,
+	parents: Option<FnArgs<'a>>,
+	body: Block<'a>,
+}
+
+impl<'a> Class<'a> {
+	pub fn parse(parser: &mut Parser<'a>) -> Result<'a, Option<Self>> {
+		let keyword =
+			if let Some(token) = parser.take_if_contents(TokenContents::Identifier("class"))? {
+				token
+			} else {
+				return Ok(None);
+			};
+
+		let name =
+			match parser.take_if(|token| matches!(token.contents, TokenContents::Identifier(_)))? {
+				Some(Token { contents: TokenContents::Identifier(name), .. }) => name,
+				_ => {
+					parser.untake(keyword);
+					return Ok(None);
+				}
+			};
+
+		let parents =
+			if parser.take_if_contents(TokenContents::LeftParen(ParenType::Round))?.is_some() {
+				Some(FnArgs::parse(parser, ParenType::Round)?)
+			} else {
+				None
+			};
+
+		let body = Block::parse(parser)?.ok_or_else(|| {
+			parser.error(ErrorKind::Message(format!("expected a body for class `{name}`")))
+		})?;
+
+		Ok(Some(Self { name, parents, body }))
+	}
+}
+
+impl Compile for Class<'_> {
+	fn compile(&self, builder: &mut Builder, dst: Local) {
+		let class_local = builder.unnamed_local();
+		builder.constant(ClassClass::instance(), class_local);
+
+		let name_local = builder.unnamed_local();
+		builder.str_constant(self.name, name_local);
+
+		let mut parent_locals = Vec::new();
+		for parent in self.parents.iter().flat_map(|parents| &parents.arguments) {
+			let local = builder.unnamed_local();
+			parent.compile(builder, local);
+			parent_locals.push(local);
+		}
+		let parents_local = builder.unnamed_local();
+		builder.create_list(&parent_locals, parents_local);
+
+		let body_local = builder.unnamed_local();
+		self.body.compile(builder, body_local);
+
+		let local = builder.named_local(self.name);
+		builder.call_attr_simple_intern(
+			class_local,
+			Intern::new_,
+			&[name_local, parents_local, body_local],
+			local,
+		);
+		builder.mov(local, dst);
+	}
+}
@@ -1,4 +1,4 @@
-use super::{Assignment, Compile, Primary};
+use super::{Assignment, Class, Compile, Primary};
 use crate::parse::token::TokenContents;
 use crate::parse::{Parser, Result};
 use crate::vm::block::{Builder, Local};
@@ -8,11 +8,16 @@ use crate::vm::Opcode;
 pub enum Expression<'a> {
 	Primary(Primary<'a>),
 	Assignment(Box<Assignment<'a>>),
+	Class(Box<Class<'a>>),
 	BinaryOperator(Box<Expression<'a>>, &'a str, Box<Expression<'a>>),
 }
 
 impl<'a> Expression<'a> {
 	pub fn parse(parser: &mut Parser<'a>) -> Result<'a, Option<Self>> {
+		if let Some(class) = Class::parse(parser)? {
+			return Ok(Some(Self::Class(Box::new(class))));
+		}
+
 		let primary = if let Some(primary) = Primary::parse(parser)? {
 			primary
 		} else {
@@ -55,6 +60,7 @@ impl Compile for Expression<'_> {
 		match self {
 			Self::Primary(primary) => primary.compile(builder, dst),
 			Self::Assignment(assign) => assign.compile(builder, dst),
+			Self::Class(class) => class.compile(builder, dst),
 			Self::BinaryOperator(lhs, op, rhs) => {
 				let lhs_local = builder.unnamed_local();
 				lhs.compile(builder, lhs_local);
@@ -11,13 +11,27 @@ pub struct Group<'a> {
 }
 
 #[derive(Debug)]
-enum Statement<'a> {
+struct Statement<'a> {
+	start: SourceLocation<'a>,
+	kind: StatementKind<'a>,
+}
+
+#[derive(Debug)]
+enum StatementKind<'a> {
 	Single(Expression<'a>),
 	Many(Vec<Expression<'a>>),
 }
 
 impl<'a> Statement<'a> {
 	pub fn parse(parser: &mut Parser<'a>) -> Result<'a, Option<Self>> {
+		let start = parser.peek()?.map_or_else(|| parser.location(), |token| token.span.start);
+
+		Ok(StatementKind::parse(parser)?.map(|kind| Self { start, kind }))
+	}
+}
+
+impl<'a> StatementKind<'a> {
+	fn parse(parser: &mut Parser<'a>) -> Result<'a, Option<Self>> {
 		let first = if let Some(expr) = Expression::parse(parser)? {
 			expr
 		} else {
@@ -135,9 +149,11 @@ impl<'a> Group<'a> {
 
 impl Compile for Statement<'_> {
 	fn compile(&self, builder: &mut Builder, dst: Local) {
-		match self {
-			Self::Single(expr) => expr.compile(builder, dst),
-			Self::Many(many) => {
+		builder.line(self.start.line);
+
+		match &self.kind {
+			StatementKind::Single(expr) => expr.compile(builder, dst),
+			StatementKind::Many(many) => {
 				let mut locals = Vec::with_capacity(many.len());
 
 				for expr in many {
@@ -1,4 +1,4 @@
-use super::{Atom, AttrAccessKind, Block, Compile, FnArgs};
+use super::{Atom, AttrAccessKind, Block, Compile, Expression, FnArgs};
 use crate::parse::token::{ParenType, TokenContents};
 use crate::parse::{ErrorKind, Parser, Result};
 use crate::value::ty::Text;
@@ -11,6 +11,7 @@ pub enum Primary<'a> {
 	Atom(Atom<'a>),
 	Block(Block<'a>),
 	List(FnArgs<'a>),
+	Map(Vec<(Expression<'a>, Expression<'a>)>),
 	UnaryOp(&'a str, Box<Primary<'a>>),
 	// TODO: attribute call.
 	FnCall(Box<Primary<'a>>, FnArgs<'a>),
@@ -28,7 +29,7 @@ impl<'a> Primary<'a> {
 		} else if let Some(atom) = Atom::parse(parser)? {
 			Self::Atom(atom)
 		} else if parser.take_if_contents(TokenContents::LeftParen(ParenType::Square))?.is_some() {
-			Self::List(FnArgs::parse(parser, ParenType::Square)?)
+			Self::parse_list_or_map(parser)?
 		} else if let Some(token) =
 			parser.take_if(|token| matches!(token.contents, TokenContents::Symbol(_)))?
 		{
@@ -90,6 +91,43 @@ impl<'a> Primary<'a> {
 	}
 }
 
+impl<'a> Primary<'a> {
+	// Map literals are lists whose elements are all `key => value` pairs; the empty map is `[=>]`.
+	fn parse_list_or_map(parser: &mut Parser<'a>) -> Result<'a, Self> {
+		if let Some(arrow) = parser.take_if_contents(TokenContents::Symbol("=>"))? {
+			if parser.take_if_contents(TokenContents::RightParen(ParenType::Square))?.is_some() {
+				return Ok(Self::Map(Vec::new()));
+			}
+
+			parser.untake(arrow);
+		}
+
+		let elements = FnArgs::parse(parser, ParenType::Square)?;
+
+		let is_pair = |expr: &Expression<'_>| matches!(expr, Expression::BinaryOperator(_, "=>", _));
+		if !elements.arguments.iter().any(is_pair) {
+			return Ok(Self::List(elements));
+		}
+
+		if !elements.arguments.iter().all(is_pair) {
+			return Err(parser.error(ErrorKind::Message(
+				"map literals must only contain `key => value` pairs".to_string(),
+			)));
+		}
+
+		let pairs = elements
+			.arguments
+			.into_iter()
+			.map(|expr| match expr {
+				Expression::BinaryOperator(key, _, value) => (*key, *value),
+				_ => unreachable!(),
+			})
+			.collect();
+
+		Ok(Self::Map(pairs))
+	}
+}
+
 impl Compile for Primary<'_> {
 	fn compile(&self, builder: &mut Builder, dst: Local) {
 		match self {
@@ -105,6 +143,17 @@ impl Compile for Primary<'_> {
 				}
 				builder.create_list(&element_locals, dst);
 			}
+			Self::Map(pairs) => {
+				let mut pair_locals = Vec::with_capacity(pairs.len());
+				for (key, value) in pairs {
+					let key_local = builder.unnamed_local();
+					key.compile(builder, key_local);
+					let value_local = builder.unnamed_local();
+					value.compile(builder, value_local);
+					pair_locals.push((key_local, value_local));
+				}
+				builder.create_map(&pair_locals, dst);
+			}
 			Self::UnaryOp(op, primary) => {
 				if let Some(opcode) = Opcode::unary_from_symbol(op) {
 					primary.compile(builder, dst);
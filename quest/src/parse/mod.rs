@@ -7,7 +7,7 @@ pub mod syntax;
 pub mod token;
 
 pub use error::{Error, ErrorKind, Result};
-pub use parser::Parser;
+pub use parser::{Parser, Syntaxes};
 pub use source_location::SourceLocation;
 pub use stream::Stream;
 pub use syntax::Syntax;
@@ -13,19 +13,44 @@ pub struct Parser<'a> {
 	peeked_tokens: Vec<Token<'a>>,
 }
 
+/// The `$syntax`es defined by a [`Parser`], so they can be used by another [`Parser`].
+#[derive(Debug)]
+pub struct Syntaxes<'a> {
+	syntaxes: [Vec<Rc<Syntax<'a>>>; MIN_PRIORITY + 1],
+	groups: HashMap<&'a str, Vec<Rc<Syntax<'a>>>>,
+}
+
+impl Default for Syntaxes<'_> {
+	fn default() -> Self {
+		const EMPTY_VEC: Vec<Rc<Syntax<'static>>> = Vec::new();
+
+		Self { syntaxes: [EMPTY_VEC; MIN_PRIORITY + 1], groups: HashMap::default() }
+	}
+}
+
 impl<'a> Parser<'a> {
 	#[must_use]
 	pub fn new(src: &'a str, filename: Option<&'a Path>) -> Self {
-		const EMPTY_VEC: Vec<Rc<Syntax<'static>>> = Vec::new();
+		Self::with_syntaxes(src, filename, Syntaxes::default())
+	}
 
+	/// Creates a new [`Parser`] which starts out with all of `syntaxes` already defined.
+	#[must_use]
+	pub fn with_syntaxes(src: &'a str, filename: Option<&'a Path>, syntaxes: Syntaxes<'a>) -> Self {
 		Self {
-			syntaxes: [EMPTY_VEC; MIN_PRIORITY + 1],
-			groups: HashMap::default(),
+			syntaxes: syntaxes.syntaxes,
+			groups: syntaxes.groups,
 			stream: Stream::new(src, filename),
 			peeked_tokens: Vec::new(),
 		}
 	}
 
+	/// Consumes `self`, returning all the syntaxes that were defined.
+	#[must_use]
+	pub fn into_syntaxes(self) -> Syntaxes<'a> {
+		Syntaxes { syntaxes: self.syntaxes, groups: self.groups }
+	}
+
 	pub fn error(&self, kind: ErrorKind) -> Error<'a> {
 		self.stream.error(kind)
 	}
@@ -103,10 +103,14 @@ impl<'a> Syntax<'a> {
 	}
 
 	pub fn replace(&self, parser: &mut Parser<'a>) -> Result<'a, bool> {
+		// The replacement's tokens are attributed to where the syntax is used, so that errors (and
+		// line numbers) point to the original source, not the syntax definition.
+		let used_at = parser.peek_bypass_syntax()?.map(|token| token.span);
+
 		let mut matched_tokens = Vec::new();
 		let mut matches = Matcher::new(&mut matched_tokens);
 		if self.does_match(&mut matches, parser)? {
-			self.replacement.replace(matches.finish(), parser)?;
+			self.replacement.replace(matches.finish(), used_at, parser)?;
 			Ok(true)
 		} else {
 			Ok(false)
@@ -1,6 +1,6 @@
 use super::Matches;
 use crate::parse::token::{ParenType, Token, TokenContents};
-use crate::parse::{Parser, Result};
+use crate::parse::{Parser, Result, Span};
 
 /*
 replacements := '{' replacement-body '}'
@@ -193,15 +193,25 @@ impl<'a> Replacement<'a> {
 }
 
 impl<'a> Replacement<'a> {
-	pub fn replace(&self, matches: Matches<'a>, parser: &mut Parser<'a>) -> Result<'a, ()> {
-		self.0.replace(&matches, parser)
+	pub fn replace(
+		&self,
+		matches: Matches<'a>,
+		used_at: Option<Span<'a>>,
+		parser: &mut Parser<'a>,
+	) -> Result<'a, ()> {
+		self.0.replace(&matches, used_at, parser)
 	}
 }
 
 impl<'a> ReplacementBody<'a> {
-	fn replace(&self, matches: &Matches<'a>, parser: &mut Parser<'a>) -> Result<'a, ()> {
+	fn replace(
+		&self,
+		matches: &Matches<'a>,
+		used_at: Option<Span<'a>>,
+		parser: &mut Parser<'a>,
+	) -> Result<'a, ()> {
 		for atom in self.0.iter().rev() {
-			atom.replace(matches, parser)?;
+			atom.replace(matches, used_at, parser)?;
 		}
 
 		Ok(())
@@ -209,12 +219,17 @@ impl<'a> ReplacementBody<'a> {
 }
 
 impl<'a> ReplacementAtom<'a> {
-	fn replace(&self, matches: &Matches<'a>, parser: &mut Parser<'a>) -> Result<'a, ()> {
+	fn replace(
+		&self,
+		matches: &Matches<'a>,
+		used_at: Option<Span<'a>>,
+		parser: &mut Parser<'a>,
+	) -> Result<'a, ()> {
 		// TODO: remove 1 from every syntax token here.
 
 		match self {
 			Self::Token(token) => {
-				parser.untake(*token);
+				parser.untake(Token { span: used_at.unwrap_or(token.span), ..*token });
 				Ok(())
 			},
 			Self::Capture(name) => {
@@ -260,7 +275,7 @@ impl<'a> ReplacementAtom<'a> {
 
 					for submatch in submatches {
 						for caps in submatch.iter().rev() {
-							body.replace(caps, parser)?;
+							body.replace(caps, used_at, parser)?;
 						}
 					}
 
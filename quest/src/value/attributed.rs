@@ -40,23 +40,11 @@ pub trait TryAttributed: Attributed + Copy + ToValue {
 	/// For attributes which have [`Intern::op_call`] defined on them, this will create a new
 	/// [`BoundFn`]. For all other types, it just returns the attribute itself.
 	fn get_attr<A: Attribute>(self, attr: A) -> Result<Option<Value>> {
-		let value = if let Some(value) = self.get_unbound_attr(attr)? {
-			value
+		if let Some(value) = self.get_unbound_attr(attr)? {
+			bind(self.to_value(), value).map(Some)
 		} else {
-			return Ok(None);
-		};
-
-		let is_callable = value.is_a::<ty::RustFn>()
-			|| value.is_a::<Gc<crate::vm::Block>>()
-			|| value.is_a::<Gc<ty::BoundFn>>()
-			|| value.has_attr(Intern::op_call)?;
-
-		// If the value is callable, wrap it in a bound fn. Short circuit for common ones.
-		if is_callable {
-			return Ok(Some(ty::BoundFn::new(self.to_value(), value).to_value()));
+			Ok(None)
 		}
-
-		Ok(Some(value))
 	}
 
 	/// Attempts to get the attribute `attr`, returning `Err` if it doesn't exist.
@@ -69,6 +57,24 @@ pub trait TryAttributed: Attributed + Copy + ToValue {
 
 impl<T: Attributed + Copy + ToValue> TryAttributed for T {}
 
+/// Binds the attribute `value` to `this`, if it's callable.
+///
+/// For attributes which have [`Intern::op_call`] defined on them, this will create a new
+/// [`BoundFn`](ty::BoundFn). For all other types, it just returns the attribute itself.
+pub(crate) fn bind(this: Value, value: Value) -> Result<Value> {
+	let is_callable = value.is_a::<ty::RustFn>()
+		|| value.is_a::<Gc<crate::vm::Block>>()
+		|| value.is_a::<Gc<ty::BoundFn>>()
+		|| value.has_attr(Intern::op_call)?;
+
+	// If the value is callable, wrap it in a bound fn. Short circuit for common ones.
+	if is_callable {
+		Ok(ty::BoundFn::new(this, value).to_value())
+	} else {
+		Ok(value)
+	}
+}
+
 pub trait Callable {
 	/// Calls `self` with the given `args`.
 	fn call(self, args: Args<'_>) -> Result<Value>;
@@ -11,7 +11,7 @@ mod builder;
 mod flags;
 mod parents;
 
-pub use attributes::{Attribute, AttributesMut, AttributesRef};
+pub use attributes::{Attribute, AttributesMut, AttributesRef, Shape};
 pub use builder::Builder;
 pub use flags::{Flags, HasTypeFlag, TypeFlag};
 pub use parents::{IntoParent, NoParents, ParentsMut, ParentsRef};
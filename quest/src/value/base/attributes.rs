@@ -4,15 +4,20 @@ use crate::{Intern, Result, Value};
 use std::fmt::{self, Debug, Formatter};
 use std::mem::ManuallyDrop;
 
-mod list;
 mod map;
-use list::ListMap;
+mod shape;
 use map::Map;
+pub use shape::Shape;
+use shape::Shaped;
 
+/// The attributes of a [`Header`](crate::value::base::Header).
+///
+/// Attributes start out [shaped](Shape), and switch to a map (signified by [`Flags::ATTR_MAP`]) if
+/// they can't be stored in a shape anymore.
 #[repr(C)]
 pub(super) union Attributes {
 	none: u64,
-	list: ManuallyDrop<Box<ListMap>>,
+	shaped: ManuallyDrop<Box<Shaped>>,
 	map: ManuallyDrop<Box<Map>>,
 }
 
@@ -59,7 +64,7 @@ impl Debug for AttributesRef<'_> {
 		if self.is_none() {
 			f.debug_map().finish()
 		} else if self.isnt_map() {
-			Debug::fmt(unsafe { &self.attributes.list }, f)
+			Debug::fmt(unsafe { &self.attributes.shaped }, f)
 		} else {
 			Debug::fmt(unsafe { &self.attributes.map }, f)
 		}
@@ -86,7 +91,7 @@ impl<'a> AttributesRef<'a> {
 		AttributesIter(if self.is_none() {
 			AttributesIterInner::None
 		} else if self.isnt_map() {
-			AttributesIterInner::List(unsafe { &self.attributes.list }.iter())
+			AttributesIterInner::Shaped(unsafe { &self.attributes.shaped }.iter())
 		} else {
 			AttributesIterInner::Map(unsafe { &self.attributes.map }.iter())
 		})
@@ -97,7 +102,7 @@ impl<'a> AttributesRef<'a> {
 		if self.is_none() {
 			0
 		} else if self.isnt_map() {
-			unsafe { &self.attributes.list }.len()
+			unsafe { &self.attributes.shaped }.len()
 		} else {
 			unsafe { &self.attributes.map }.len()
 		}
@@ -105,10 +110,30 @@ impl<'a> AttributesRef<'a> {
 
 	/// Whether any attributes are defined.
 	pub fn is_empty(&self) -> bool {
-		// NOTE: WE can't just check for `.is_none()`, as list/map attributes could have been deleted.
+		// NOTE: WE can't just check for `.is_none()`, as shaped/map attributes could have been deleted.
 		self.len() == 0
 	}
 
+	/// Gets the shape of `self`, or `None` if the attributes are stored in a map instead.
+	pub fn shape(&self) -> Option<&'static Shape> {
+		if self.is_none() {
+			Some(Shape::root())
+		} else if self.isnt_map() {
+			Some(unsafe { &self.attributes.shaped }.shape())
+		} else {
+			None
+		}
+	}
+
+	/// Gets the value in the slot `index` of `self`'s [shape](Self::shape).
+	pub fn slot(&self, index: usize) -> Option<Value> {
+		if self.is_none() || !self.isnt_map() {
+			None
+		} else {
+			unsafe { &self.attributes.shaped }.slot(index)
+		}
+	}
+
 	/// Gets an unbound attribute `attr`.
 	///
 	/// Note that `attr` should not be a [special attribute](Attribute::is_special).
@@ -118,7 +143,7 @@ impl<'a> AttributesRef<'a> {
 		if self.is_none() {
 			Ok(None)
 		} else if self.isnt_map() {
-			unsafe { &self.attributes.list }.get_unbound_attr(attr)
+			unsafe { &self.attributes.shaped }.get_unbound_attr(attr)
 		} else {
 			unsafe { &self.attributes.map }.get_unbound_attr(attr)
 		}
@@ -131,8 +156,8 @@ impl<'a> AttributesMut<'a> {
 
 		if capacity == 0 {
 			self.attributes.none = 0;
-		} else if capacity <= list::MAX_LISTMAP_LEN {
-			self.attributes.list = ManuallyDrop::new(ListMap::new());
+		} else if capacity <= shape::MAX_SHAPE_LEN {
+			self.attributes.shaped = ManuallyDrop::new(Shaped::with_capacity(capacity));
 		} else {
 			assert!(
 				capacity <= isize::MAX as usize,
@@ -149,6 +174,7 @@ impl<'a> AttributesMut<'a> {
 	/// Note that `attr` should not be a [special attribute](Attribute::is_special).
 	pub fn get_unbound_attr_mut<A: Attribute>(mut self, attr: A) -> Result<&'a mut Value> {
 		debug_assert!(!attr.is_special());
+		crate::vm::inline_cache::on_modify(self.flags);
 
 		// TODO: don't fetch the attr beforehand
 		if self.get_unbound_attr(attr)?.is_none() {
@@ -158,7 +184,7 @@ impl<'a> AttributesMut<'a> {
 		debug_assert!(!self.is_none());
 
 		if self.isnt_map() {
-			unsafe { &mut self.attributes.list }.get_unbound_attr_mut(attr)
+			unsafe { &mut self.attributes.shaped }.get_unbound_attr_mut(attr)
 		} else {
 			unsafe { &mut self.attributes.map }.get_unbound_attr_mut(attr)
 		}
@@ -169,48 +195,66 @@ impl<'a> AttributesMut<'a> {
 	/// Note that `attr` should not be a [special attribute](Attribute::is_special).
 	pub fn set_attr<A: Attribute>(&mut self, attr: A, value: Value) -> Result<()> {
 		debug_assert!(!attr.is_special());
+		crate::vm::inline_cache::on_modify(self.flags);
 
 		if self.is_none() {
 			debug_assert!(self.isnt_map());
 
-			self.attributes.list = ManuallyDrop::new(ListMap::new());
-			return unsafe { &mut self.attributes.list }.set_attr(attr, value);
+			self.attributes.shaped = ManuallyDrop::new(Shaped::with_capacity(1));
 		}
 
 		if self.isnt_map() {
-			let list = unsafe { &mut self.attributes.list };
-			if !list.is_full() {
-				return list.set_attr(attr, value);
+			if unsafe { &mut self.attributes.shaped }.set_attr(attr, value)? {
+				return Ok(());
 			}
 
-			let list = unsafe { ManuallyDrop::take(list) };
-			self.attributes.map = ManuallyDrop::new(Map::from_iter(list.iter())?);
-			self.flags.insert_internal(Flags::ATTR_MAP);
+			self.convert_to_map()?;
 		}
 
 		unsafe { &mut self.attributes.map }.set_attr(attr, value)
 	}
 
+	// Moves shaped attributes over to a map.
+	fn convert_to_map(&mut self) -> Result<()> {
+		debug_assert!(self.isnt_map() && !self.is_none());
+
+		let map = Map::from_iter(unsafe { &self.attributes.shaped }.iter())?;
+		unsafe { ManuallyDrop::drop(&mut self.attributes.shaped) };
+		self.attributes.map = ManuallyDrop::new(map);
+		self.flags.insert_internal(Flags::ATTR_MAP);
+
+		Ok(())
+	}
+
 	/// Deletes an attribute `attr`, returning `None` if it didnt exist.
 	///
 	/// Note that `attr` should not be a [special attribute](Attribute::is_special).
 	pub fn del_attr<A: Attribute>(&mut self, attr: A) -> Result<Option<Value>> {
 		debug_assert!(!attr.is_special());
+		crate::vm::inline_cache::on_modify(self.flags);
 
 		if self.is_none() {
-			Ok(None)
-		} else if self.isnt_map() {
-			unsafe { &mut self.attributes.list }.del_attr(attr)
-		} else {
-			unsafe { &mut self.attributes.map }.del_attr(attr)
+			return Ok(None);
+		}
+
+		if self.isnt_map() {
+			if unsafe { &self.attributes.shaped }.get_unbound_attr(attr)?.is_none() {
+				return Ok(None);
+			}
+
+			// Shapes without the attribute would have to be rebuilt from the root, and objects that
+			// have attributes deleted rarely share a layout anyways.
+			self.convert_to_map()?;
 		}
+
+		unsafe { &mut self.attributes.map }.del_attr(attr)
 	}
 
 	pub(crate) unsafe fn drop_internal(&mut self) {
 		if self.is_none() {
 			// we do nothing when dropping empty attributes
 		} else if self.isnt_map() {
-			ManuallyDrop::drop(&mut self.attributes.list)
+			ManuallyDrop::drop(&mut self.attributes.shaped)
 		} else {
 			ManuallyDrop::drop(&mut self.attributes.map)
 		}
@@ -223,7 +267,7 @@ pub struct AttributesIter<'a>(AttributesIterInner<'a>);
 // we need an inner enum so people cant access the internals whilst the iter is public.
 enum AttributesIterInner<'a> {
 	None,
-	List(list::ListMapIter<'a>),
+	Shaped(shape::ShapedIter<'a>),
 	Map(map::MapIter<'a>),
 }
 
@@ -233,7 +277,7 @@ impl Iterator for AttributesIter<'_> {
 	fn next(&mut self) -> Option<Self::Item> {
 		match &mut self.0 {
 			AttributesIterInner::None => None,
-			AttributesIterInner::List(list_iter) => list_iter.next(),
+			AttributesIterInner::Shaped(shaped_iter) => shaped_iter.next(),
 			AttributesIterInner::Map(map_iter) => map_iter.next(),
 		}
 	}
@@ -342,7 +386,24 @@ impl Attribute for Value {
 mod tests {
 	use super::*;
 	use crate::value::ty::{Integer, Text};
-	use crate::value::{Attributed, AttributedMut, Value};
+	use crate::value::{Attributed, AttributedMut, HasAttributes, Value};
+
+	#[test]
+	fn deleting_transitions_over_to_map() {
+		let text = Text::from_static_str("g'day");
+		let mut textmut = text.as_mut().unwrap();
+
+		textmut.set_attr(Intern::start, Value::ONE.to_value()).unwrap();
+		textmut.set_attr(Intern::end, Value::TRUE.to_value()).unwrap();
+		assert!(textmut.attributes().shape().is_some());
+
+		assert!(textmut.del_attr(Intern::start).unwrap().is_some());
+		assert!(textmut.attributes().shape().is_none());
+		assert_eq!(
+			textmut.get_unbound_attr(Intern::end).unwrap().unwrap().bits(),
+			Value::TRUE.bits()
+		);
+	}
 
 	#[test]
 	fn it_transitions_over_to_full_map() {
@@ -351,7 +412,7 @@ mod tests {
 		{
 			let mut textmut = text.as_mut().unwrap();
 
-			for i in 0..=list::MAX_LISTMAP_LEN * 2 {
+			for i in 0..=shape::MAX_SHAPE_LEN * 2 {
 				let value = Integer::new(i as i64).unwrap().to_value();
 				textmut.set_attr(value, value).unwrap();
 
@@ -370,7 +431,7 @@ mod tests {
 			.unwrap());
 
 		// now it should be a full `map`, let's go over all of them again.
-		for i in 0..=list::MAX_LISTMAP_LEN * 2 {
+		for i in 0..=shape::MAX_SHAPE_LEN * 2 {
 			let value = Integer::new(i as i64).unwrap().to_value();
 			assert!(textref
 				.get_unbound_attr_checked(value, &mut vec![])
@@ -35,7 +35,7 @@ impl Map {
 	}
 
 	pub fn from_iter(iter: impl IntoIterator<Item = (Value, Value)>) -> Result<Box<Self>> {
-		let mut map = Self::with_capacity(super::list::MAX_LISTMAP_LEN);
+		let mut map = Self::with_capacity(super::shape::MAX_SHAPE_LEN);
 
 		for (attr, value) in iter {
 			map.set_attr(attr, value)?;
@@ -74,7 +74,16 @@ impl Map {
 		debug_assert!(!attr.is_special());
 
 		if let Some(intern) = attr.as_intern()? {
-			Ok(self.interned.get(&intern).copied())
+			if let Some(&value) = self.interned.get(&intern) {
+				return Ok(Some(value));
+			}
+
+			// It may have been set as a `Text` before it was interned.
+			if self.any.is_empty() {
+				Ok(None)
+			} else {
+				self.get_unbound_any_attr(attr)
+			}
 		} else {
 			self.get_unbound_any_attr(attr)
 		}
@@ -145,10 +154,17 @@ impl Map {
 	pub fn del_attr<A: Attribute>(&mut self, attr: A) -> Result<Option<Value>> {
 		debug_assert!(!attr.is_special());
 
-		if let Some(_intern) = attr.as_intern()? {
-			// You cannot remove interned values
-			// TODO: is this actually the semantics we want?
-			Ok(None)
+		if let Some(intern) = attr.as_intern()? {
+			if let Some(value) = self.interned.remove(&intern) {
+				return Ok(Some(value));
+			}
+
+			// It may have been set as a `Text` before it was interned.
+			if self.any.is_empty() {
+				Ok(None)
+			} else {
+				self.del_any_attr(attr)
+			}
 		} else {
 			self.del_any_attr(attr)
 		}
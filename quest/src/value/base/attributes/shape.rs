@@ -0,0 +1,220 @@
+//! Shapes (also known as "hidden classes"), which describe the layout of an object's attributes.
+//!
+//! Instead of each object storing its own keys, it stores a reference to a [`Shape`] (which knows
+//! what attributes the object has, and in what order they were added) and a dense array of values,
+//! one slot per attribute. Shapes form a global transition tree rooted at the empty shape: adding
+//! an attribute moves an object to the child shape for that attribute, creating it if it doesn't
+//! exist yet. As such, objects whose attributes were added in the same order share the same shape,
+//! which makes comparing layouts (eg for inline caches) a pointer comparison.
+//!
+//! Only interned attributes are stored this way. Objects that use any other attribute, that have
+//! more than [`MAX_SHAPE_LEN`] attributes, or that have any deleted, switch over to a
+//! [`Map`](super::map::Map) instead. Since shapes are never freed, there are at most
+//! [`MAX_SHAPES`] of them; once that many exist, objects needing new ones use maps too.
+
+use super::Attribute;
+use crate::{Intern, Result, ToValue, Value};
+use hashbrown::hash_map::{Entry, HashMap};
+use once_cell::sync::OnceCell;
+use std::fmt::{self, Debug, Formatter};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::RwLock;
+
+/// The maximum amount of attributes an object can have before it switches to a map.
+pub const MAX_SHAPE_LEN: usize = 32;
+
+/// The maximum amount of shapes that are created, including the root.
+pub const MAX_SHAPES: usize = 1 << 16;
+
+static SHAPE_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+/// The layout of an object's attributes.
+pub struct Shape {
+	keys: Box<[Intern]>,
+	transitions: RwLock<HashMap<Intern, &'static Shape>>,
+}
+
+impl Debug for Shape {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		f.debug_tuple("Shape").field(&self.keys).finish()
+	}
+}
+
+impl Shape {
+	fn new(keys: Box<[Intern]>) -> &'static Self {
+		// Shapes are shared between every object that has them, and so are never freed.
+		SHAPE_COUNT.fetch_add(1, Ordering::Relaxed);
+		Box::leak(Box::new(Self { keys, transitions: RwLock::default() }))
+	}
+
+	/// Gets the shape of objects without attributes, which is the root of the transition tree.
+	pub fn root() -> &'static Self {
+		static ROOT: OnceCell<&'static Shape> = OnceCell::new();
+
+		ROOT.get_or_init(|| Self::new(Box::default()))
+	}
+
+	/// The attributes objects with this shape have, in the order they were added.
+	#[must_use]
+	pub fn keys(&self) -> &[Intern] {
+		&self.keys
+	}
+
+	/// Gets the index of the slot which `key` is stored in.
+	#[must_use]
+	pub fn index_of(&self, key: Intern) -> Option<usize> {
+		self.keys.iter().position(|&k| k == key)
+	}
+
+	/// Gets the shape that results from adding `key` to `self`, or `None` if it doesn't exist yet
+	/// and [`MAX_SHAPES`] have already been created.
+	///
+	/// `key` shouldn't already be in `self`.
+	pub fn with(&'static self, key: Intern) -> Option<&'static Self> {
+		debug_assert!(self.index_of(key).is_none());
+
+		if let Some(&shape) = self.transitions.read().unwrap().get(&key) {
+			return Some(shape);
+		}
+
+		// Another thread may have added it in the meantime, so use `entry` instead of inserting.
+		match self.transitions.write().unwrap().entry(key) {
+			Entry::Occupied(entry) => Some(*entry.get()),
+			Entry::Vacant(_) if MAX_SHAPES <= SHAPE_COUNT.load(Ordering::Relaxed) => None,
+			Entry::Vacant(entry) => {
+				let mut keys = Vec::with_capacity(self.keys.len() + 1);
+				keys.extend_from_slice(&self.keys);
+				keys.push(key);
+				Some(*entry.insert(Self::new(keys.into_boxed_slice())))
+			}
+		}
+	}
+}
+
+/// Attributes stored as a [`Shape`] and its slots.
+pub(super) struct Shaped {
+	shape: &'static Shape,
+	slots: Vec<Value>,
+}
+
+impl Debug for Shaped {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		f.debug_map().entries(self.shape.keys.iter().zip(&self.slots)).finish()
+	}
+}
+
+pub struct ShapedIter<'a>(
+	std::iter::Zip<std::slice::Iter<'a, Intern>, std::slice::Iter<'a, Value>>,
+);
+
+impl Iterator for ShapedIter<'_> {
+	type Item = (Value, Value);
+
+	fn next(&mut self) -> Option<Self::Item> {
+		self.0.next().map(|(key, &value)| (key.to_value(), value))
+	}
+}
+
+impl Shaped {
+	pub fn with_capacity(capacity: usize) -> Box<Self> {
+		Box::new(Self { shape: Shape::root(), slots: Vec::with_capacity(capacity) })
+	}
+
+	pub fn shape(&self) -> &'static Shape {
+		self.shape
+	}
+
+	pub fn slot(&self, index: usize) -> Option<Value> {
+		self.slots.get(index).copied()
+	}
+
+	pub fn iter(&self) -> ShapedIter<'_> {
+		ShapedIter(self.shape.keys.iter().zip(self.slots.iter()))
+	}
+
+	pub fn len(&self) -> usize {
+		self.slots.len()
+	}
+
+	pub fn get_unbound_attr<A: Attribute>(&self, attr: A) -> Result<Option<Value>> {
+		// Only interned attributes are ever stored in shapes.
+		Ok(attr.as_intern()?.and_then(|intern| self.shape.index_of(intern)).map(|i| self.slots[i]))
+	}
+
+	pub fn get_unbound_attr_mut<A: Attribute>(&mut self, attr: A) -> Result<&mut Value> {
+		let index = attr
+			.as_intern()?
+			.and_then(|intern| self.shape.index_of(intern))
+			.expect("get_unbound_attr_mut called with an unknown attribute");
+
+		Ok(&mut self.slots[index])
+	}
+
+	/// Sets `attr` to `value`, returning `false` if it can't be stored in a shape.
+	pub fn set_attr<A: Attribute>(&mut self, attr: A, value: Value) -> Result<bool> {
+		let intern = if let Some(intern) = attr.as_intern()? {
+			intern
+		} else {
+			return Ok(false);
+		};
+
+		if let Some(index) = self.shape.index_of(intern) {
+			self.slots[index] = value;
+		} else if self.slots.len() == MAX_SHAPE_LEN {
+			return Ok(false);
+		} else if let Some(shape) = self.shape.with(intern) {
+			self.shape = shape;
+			self.slots.push(value);
+		} else {
+			return Ok(false);
+		}
+
+		Ok(true)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::value::ty::Text;
+
+	#[test]
+	fn objects_built_the_same_way_share_shapes() {
+		let mut first = Shaped::with_capacity(0);
+		let mut second = Shaped::with_capacity(0);
+
+		first.set_attr(Intern::start, Value::ONE.to_value()).unwrap();
+		first.set_attr(Intern::end, Value::ONE.to_value()).unwrap();
+		second.set_attr(Intern::start, Value::TRUE.to_value()).unwrap();
+		assert!(!std::ptr::eq(first.shape(), second.shape()));
+
+		second.set_attr(Intern::end, Value::TRUE.to_value()).unwrap();
+		assert!(std::ptr::eq(first.shape(), second.shape()));
+		assert_eq!(first.shape().keys(), &[Intern::start, Intern::end]);
+
+		// Overwriting attributes doesn't change the shape...
+		second.set_attr(Intern::start, Value::ONE.to_value()).unwrap();
+		assert!(std::ptr::eq(first.shape(), second.shape()));
+
+		// ...but adding them in a different order gives a different one.
+		let mut third = Shaped::with_capacity(0);
+		third.set_attr(Intern::end, Value::ONE.to_value()).unwrap();
+		third.set_attr(Intern::start, Value::ONE.to_value()).unwrap();
+		assert!(!std::ptr::eq(first.shape(), third.shape()));
+	}
+
+	#[test]
+	fn only_stores_interned_attributes() {
+		let mut shaped = Shaped::with_capacity(0);
+
+		assert!(!shaped.set_attr(Value::ONE.to_value(), Value::ONE.to_value()).unwrap());
+
+		for i in 0..MAX_SHAPE_LEN {
+			let intern = Intern::new(Text::from_str(&format!("shape_attr_{i}"))).unwrap();
+			assert!(shaped.set_attr(intern, Value::ONE.to_value()).unwrap());
+		}
+
+		assert!(!shaped.set_attr(Intern::start, Value::ONE.to_value()).unwrap());
+		assert_eq!(shaped.len(), MAX_SHAPE_LEN);
+	}
+}
@@ -65,21 +65,32 @@ impl Flags {
 	pub(crate) const ATTR_MAP: u32 = 1 << 19;
 	/// Set if more than one parent is defined on a type.
 	pub(crate) const MULTI_PARENT: u32 = 1 << 20;
-	const _UNUSED_21: u32 = 1 << 21;
-	const _UNUSED_22: u32 = 1 << 22;
-	const _UNUSED_23: u32 = 1 << 23;
-	const _UNUSED_24: u32 = 1 << 24;
+	/// Set if the parents are lexical scopes (eg of frames and blocks), which are searched
+	/// depth-first instead of in their method resolution order.
+	pub(crate) const LEXICAL_PARENTS: u32 = 1 << 21;
+	/// Set if the value is an ancestor that an inline cache depends on, so modifying it must
+	/// invalidate them.
+	pub(crate) const INLINE_CACHED: u32 = 1 << 22;
+	/// Set if the value is a list that's used as the parents of another value, so modifying it
+	/// must invalidate any caches that depend on those parents.
+	pub(crate) const PARENTS_LIST: u32 = 1 << 23;
+	/// Set if the value is a list of lexical scopes that are used as the parents of another value,
+	/// which only have to be acyclic rather than have a consistent linearization.
+	pub(crate) const SCOPES_LIST: u32 = 1 << 24;
 	const _UNUSED_25: u32 = 1 << 25;
 	const _UNUSED_26: u32 = 1 << 26;
-	const _UNUSED_27: u32 = 1 << 27;
 
-	const TYPE_FLAG_BITSHIFT: u32 = 28;
-	const TYPE_FLAG1: u32 = 1 << 28;
-	const TYPE_FLAG2: u32 = 1 << 29;
-	const TYPE_FLAG3: u32 = 1 << 30;
-	const TYPE_FLAG4: u32 = 1 << 31;
-	const TYPE_FLAG_MASK: u32 =
-		Self::TYPE_FLAG1 | Self::TYPE_FLAG2 | Self::TYPE_FLAG3 | Self::TYPE_FLAG4;
+	const TYPE_FLAG_BITSHIFT: u32 = 27;
+	const TYPE_FLAG1: u32 = 1 << 27;
+	const TYPE_FLAG2: u32 = 1 << 28;
+	const TYPE_FLAG3: u32 = 1 << 29;
+	const TYPE_FLAG4: u32 = 1 << 30;
+	const TYPE_FLAG5: u32 = 1 << 31;
+	const TYPE_FLAG_MASK: u32 = Self::TYPE_FLAG1
+		| Self::TYPE_FLAG2
+		| Self::TYPE_FLAG3
+		| Self::TYPE_FLAG4
+		| Self::TYPE_FLAG5;
 
 	/// Creates new [`Flags`].
 	#[must_use]
@@ -194,9 +205,9 @@ impl Debug for Flags {
 		check!(
 			USER0 USER1 USER2 USER3 USER4 USER5 USER6 USER7 USER8 USER9
 			USER10 USER11 USER12 USER13 USER14 USER15
-			FROZEN NOFREE GCMARK ATTR_MAP MULTI_PARENT
-			_UNUSED_21 _UNUSED_22 _UNUSED_23 _UNUSED_24 _UNUSED_25 _UNUSED_26 _UNUSED_27
-			TYPE_FLAG1 TYPE_FLAG2 TYPE_FLAG3 TYPE_FLAG4
+			FROZEN NOFREE GCMARK ATTR_MAP MULTI_PARENT LEXICAL_PARENTS INLINE_CACHED PARENTS_LIST
+			SCOPES_LIST _UNUSED_25 _UNUSED_26
+			TYPE_FLAG1 TYPE_FLAG2 TYPE_FLAG3 TYPE_FLAG4 TYPE_FLAG5
 		);
 
 		let _ = is_first;
@@ -28,7 +28,11 @@ pub enum TypeFlag {
 	Pristine = offset(12),
 	ScopeClass = offset(13),
 	BoundFnClass = offset(14),
-	ThreadClass = offset(15),
+	Native = offset(15),
+	Map = offset(16),
+	Set = offset(17),
+	Range = offset(18),
+	Instance = offset(19),
 }
 
 impl TypeFlag {
@@ -55,6 +59,12 @@ impl TypeFlag {
 			_ if inp == Self::Iterator as u32 => true,
 			_ if inp == Self::Pristine as u32 => true,
 			_ if inp == Self::ScopeClass as u32 => true,
+			_ if inp == Self::BoundFnClass as u32 => true,
+			_ if inp == Self::Native as u32 => true,
+			_ if inp == Self::Map as u32 => true,
+			_ if inp == Self::Set as u32 => true,
+			_ if inp == Self::Range as u32 => true,
+			_ if inp == Self::Instance as u32 => true,
 			_ => false,
 		}
 	}
@@ -66,6 +76,17 @@ impl TypeFlag {
 // types.
 pub unsafe trait HasTypeFlag {
 	const TYPE_FLAG: TypeFlag;
+
+	/// Checks to see if `ptr`, which points to an allocated value with a type flag of `TYPE_FLAG`,
+	/// is actually a `Self`. This only needs to be overridden by types that share a type flag.
+	///
+	/// # Safety
+	/// `ptr` must point to a valid allocated value whose type flag is `TYPE_FLAG`.
+	#[inline]
+	unsafe fn is_instance(ptr: *const Self) -> bool {
+		let _ = ptr;
+		true
+	}
 }
 
 unsafe impl HasTypeFlag for ty::BigNum {
@@ -80,18 +101,34 @@ unsafe impl HasTypeFlag for ty::Class {
 	const TYPE_FLAG: TypeFlag = TypeFlag::Class;
 }
 
+unsafe impl HasTypeFlag for ty::Instance {
+	const TYPE_FLAG: TypeFlag = TypeFlag::Instance;
+}
+
 unsafe impl HasTypeFlag for ty::List {
 	const TYPE_FLAG: TypeFlag = TypeFlag::List;
 }
 
+unsafe impl HasTypeFlag for ty::Map {
+	const TYPE_FLAG: TypeFlag = TypeFlag::Map;
+}
+
 unsafe impl HasTypeFlag for ty::Object {
 	const TYPE_FLAG: TypeFlag = TypeFlag::Object;
 }
 
+unsafe impl HasTypeFlag for ty::Range {
+	const TYPE_FLAG: TypeFlag = TypeFlag::Range;
+}
+
 unsafe impl HasTypeFlag for ty::Scope {
 	const TYPE_FLAG: TypeFlag = TypeFlag::Scope;
 }
 
+unsafe impl HasTypeFlag for ty::Set {
+	const TYPE_FLAG: TypeFlag = TypeFlag::Set;
+}
+
 unsafe impl HasTypeFlag for ty::Text {
 	const TYPE_FLAG: TypeFlag = TypeFlag::Text;
 }
@@ -1,6 +1,6 @@
 use crate::value::base::{Attribute, Flags};
 use crate::value::ty::List;
-use crate::value::{Callable, Gc, Value};
+use crate::value::{Callable, Gc, HasFlags, Value};
 use crate::{ErrorKind, Result};
 use std::fmt::{self, Debug, Formatter};
 
@@ -64,7 +64,7 @@ pub struct NoParents;
 impl IntoParent for NoParents {
 	#[inline]
 	fn into_parent(self, parents: &mut ParentsMut<'_>) {
-		parents.flags.remove_internal(Flags::MULTI_PARENT);
+		parents.flags.remove_internal(Flags::MULTI_PARENT | Flags::LEXICAL_PARENTS);
 		parents.parents.none = 0;
 	}
 }
@@ -72,7 +72,7 @@ impl IntoParent for NoParents {
 impl IntoParent for Value {
 	#[inline]
 	fn into_parent(self, parents: &mut ParentsMut<'_>) {
-		parents.flags.remove_internal(Flags::MULTI_PARENT);
+		parents.flags.remove_internal(Flags::MULTI_PARENT | Flags::LEXICAL_PARENTS);
 		parents.parents.single = self;
 	}
 }
@@ -80,7 +80,10 @@ impl IntoParent for Value {
 impl IntoParent for Gc<List> {
 	#[inline]
 	fn into_parent(self, parents: &mut ParentsMut<'_>) {
+		parents.flags.remove_internal(Flags::LEXICAL_PARENTS);
 		parents.flags.insert_internal(Flags::MULTI_PARENT);
+		self.flags().remove_internal(Flags::SCOPES_LIST);
+		self.flags().insert_internal(Flags::PARENTS_LIST);
 		parents.parents.list = self;
 	}
 }
@@ -123,6 +126,28 @@ impl ParentsRef<'_> {
 		}
 	}
 
+	/// Gets a value that uniquely identifies the parents, so long as they aren't modified.
+	pub(crate) fn identity(&self) -> u64 {
+		unsafe { self.parents.none }
+	}
+
+	/// Whether the parents are lexical scopes, which are searched depth-first.
+	pub(crate) fn is_lexical(&self) -> bool {
+		self.flags.contains(Flags::LEXICAL_PARENTS)
+	}
+
+	/// Gets a copy of the parents, in the order they're listed in.
+	///
+	/// # Errors
+	/// Returns an error if the parents are a [`List`] that's currently mutably borrowed.
+	pub fn to_vec(&self) -> Result<Vec<Value>> {
+		match self.classify() {
+			ParentsKind::None => Ok(Vec::new()),
+			ParentsKind::Single(single) => Ok(vec![single]),
+			ParentsKind::List(list) => Ok(list.as_ref()?.as_slice().to_vec()),
+		}
+	}
+
 	/// Attempts to get the unbound attribute `attr` on `self`.
 	pub fn get_unbound_attr_checked<A: Attribute>(
 		&self,
@@ -133,8 +158,28 @@ impl ParentsRef<'_> {
 			ParentsKind::None => Ok(None),
 			ParentsKind::Single(single) => single.get_unbound_attr_checked(attr, checked),
 			ParentsKind::List(list) => {
-				for parent in list.as_ref()?.as_slice() {
-					if let Some(value) = parent.get_unbound_attr_checked(attr, checked)? {
+				let listref = list.as_ref()?;
+
+				// With only one parent, searching depth-first is the same as the linearization.
+				match listref.as_slice() {
+					[] => return Ok(None),
+					[parent] => return parent.get_unbound_attr_checked(attr, checked),
+					parents if self.is_lexical() => {
+						for parent in parents {
+							if let Some(value) = parent.get_unbound_attr_checked(attr, checked)? {
+								return Ok(Some(value));
+							}
+						}
+
+						return Ok(None);
+					}
+					_ => {}
+				}
+
+				drop(listref);
+
+				for &ancestor in crate::value::mro::linearize_parents_list(list)?.iter() {
+					if let Some(value) = ancestor.get_own_unbound_attr(attr)? {
 						return Ok(Some(value));
 					}
 				}
@@ -166,9 +211,21 @@ impl ParentsRef<'_> {
 impl ParentsMut<'_> {
 	/// Replaces `self` with `parent`.
 	pub fn set<I: IntoParent>(&mut self, parent: I) {
+		crate::vm::inline_cache::on_modify(self.flags);
 		parent.into_parent(self);
 	}
 
+	/// Replaces `self` with `parents`, which are lexical scopes that should be searched depth-first
+	/// rather than in their method resolution order.
+	pub(crate) fn set_lexical<I: IntoParent>(&mut self, parents: I) {
+		self.set(parents);
+		self.flags.insert_internal(Flags::LEXICAL_PARENTS);
+
+		if let ParentsKind::List(list) = self.classify() {
+			list.flags().insert_internal(Flags::SCOPES_LIST);
+		}
+	}
+
 	/// Converts `self` into a list of parents if it isn't already, returning the list. Modifications
 	/// to the list will modify the the parents.
 	pub fn as_list(&mut self) -> Gc<List> {
@@ -0,0 +1,242 @@
+//! The protocol for comparing and hashing values.
+//!
+//! Every value responds to three methods, which must all agree with each other:
+//! - `==` returns whether two values are equal. It's never an error to compare values of
+//!   unrelated types; they're simply not equal.
+//! - `<=>` returns `-1`, `0` or `1`, or `null` if the two values have no ordering (eg because
+//!   they're of unrelated types). It returns `0` exactly when `==` is true.
+//! - `hash` returns an `Integer`, which is the same for any two values that are `==`.
+//!
+//! `Object` defines `<`, `<=`, `>` and `>=` in terms of `<=>`, raising an error when the values
+//! have no ordering. By default, values are only `==` to themselves, and `<=>` is only defined
+//! for values that are `==`. Builtin types refine this as follows:
+//! - `Integer`, `Float` and `BigNum` compare by their numeric value regardless of which type they
+//!   are (eg `1 == 1.0`), and equal numbers hash the same. As with IEEE floats, `NaN` isn't `==`
+//!   to anything, and `<` and friends are all false for it instead of raising errors.
+//! - `Text` compares lexicographically by codepoint.
+//! - `List` compares lexicographically by its elements, with prefixes ordering first, and hashes
+//!   its elements.
+//! - `Map` and `Set` are `==` when they have equal entries (or elements), regardless of order, and
+//!   hash them in an order-independent way. They have no ordering beyond that.
+//! - `Range` is `==` when its start, end, step and inclusivity are all the same, and hashes them.
+//! - `Boolean` orders `false` before `true`.
+//!
+//! Values which contain themselves (such as a `List` that's been pushed onto itself) hash the
+//! inner occurrences as a constant, instead of recursing forever.
+//!
+//! On the Rust side, [`Value::try_eq`], [`Value::try_partial_cmp`] and [`Value::try_hash`]
+//! follow the same protocol, but skip the method calls for builtin types. (They also consider
+//! identical values to always be equal, so that a `NaN` key can still be found in a map.)
+
+use crate::value::ty::{BigNum, Float, Integer};
+use crate::value::Gc;
+use crate::{ErrorKind, Result, ToValue, Value};
+use num_bigint::BigInt;
+use num_traits::{FromPrimitive, ToPrimitive};
+use std::cmp::Ordering;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+/// A value of any of the numeric types, so they can be compared with each other.
+enum Number {
+	Integer(i64),
+	Float(Float),
+	BigNum(BigInt),
+}
+
+impl Number {
+	fn new(value: Value) -> Result<Option<Self>> {
+		if let Some(integer) = value.downcast::<Integer>() {
+			Ok(Some(Self::Integer(integer.get())))
+		} else if let Some(float) = value.downcast::<Float>() {
+			Ok(Some(Self::Float(float)))
+		} else if let Some(bignum) = value.downcast::<Gc<BigNum>>() {
+			Ok(Some(Self::BigNum(bignum.as_ref()?.as_ref().clone())))
+		} else {
+			Ok(None)
+		}
+	}
+
+	fn partial_cmp(&self, rhs: &Self) -> Option<Ordering> {
+		match (self, rhs) {
+			(Self::Integer(lhs), Self::Integer(rhs)) => Some(lhs.cmp(rhs)),
+			(Self::Float(lhs), Self::Float(rhs)) => lhs.partial_cmp(rhs),
+			(Self::BigNum(lhs), Self::BigNum(rhs)) => Some(lhs.cmp(rhs)),
+			(Self::Integer(lhs), Self::BigNum(rhs)) => Some(BigInt::from(*lhs).cmp(rhs)),
+			(Self::BigNum(lhs), Self::Integer(rhs)) => Some(lhs.cmp(&BigInt::from(*rhs))),
+			(Self::Integer(lhs), Self::Float(rhs)) => cmp_integer_float(&BigInt::from(*lhs), *rhs),
+			(Self::BigNum(lhs), Self::Float(rhs)) => cmp_integer_float(lhs, *rhs),
+			(Self::Float(_), _) => rhs.partial_cmp(self).map(Ordering::reverse),
+		}
+	}
+
+	fn hash(&self) -> u64 {
+		// Whole numbers hash as the integer they're equal to, so all three types agree.
+		match *self {
+			Self::Integer(integer) => integer as u64,
+			Self::BigNum(ref bignum) => {
+				bignum.to_i64().map_or_else(|| hash_bigint(bignum), |n| n as u64)
+			}
+			Self::Float(float) if float.fract() == 0.0 => float.to_i64().map_or_else(
+				|| hash_bigint(&BigInt::from_f64(float).expect("whole floats are finite")),
+				|n| n as u64,
+			),
+			Self::Float(float) => float.to_bits(),
+		}
+	}
+}
+
+// Compares exactly, as converting `int` to a float could lose precision.
+fn cmp_integer_float(int: &BigInt, float: Float) -> Option<Ordering> {
+	if float.is_nan() {
+		return None;
+	}
+
+	if float.is_infinite() {
+		return Some(if float.is_sign_positive() { Ordering::Less } else { Ordering::Greater });
+	}
+
+	let whole = BigInt::from_f64(float.trunc()).expect("finite floats are convertible");
+
+	// If the whole parts are equal, the fractional part of `float` decides.
+	Some(int.cmp(&whole).then_with(|| (0.0).partial_cmp(&float.fract()).unwrap()))
+}
+
+fn hash_bigint(bignum: &BigInt) -> u64 {
+	let mut hasher = DefaultHasher::new();
+	bignum.hash(&mut hasher);
+	hasher.finish()
+}
+
+/// Returns whether `value` is an `Integer`, `Float` or `BigNum`.
+pub(crate) fn is_number(value: Value) -> bool {
+	value.is_a::<Integer>() || value.is_a::<Float>() || value.is_a::<Gc<BigNum>>()
+}
+
+/// Compares two numbers, returning `None` if either isn't a number or if they're unordered.
+pub(crate) fn cmp_numbers(lhs: Value, rhs: Value) -> Result<Option<Ordering>> {
+	match (Number::new(lhs)?, Number::new(rhs)?) {
+		(Some(lhs), Some(rhs)) => Ok(lhs.partial_cmp(&rhs)),
+		_ => Ok(None),
+	}
+}
+
+/// Hashes `number`, such that numbers which are equal have the same hash regardless of type.
+///
+/// Returns `None` if `number` isn't a number.
+pub(crate) fn hash_number(number: Value) -> Result<Option<u64>> {
+	Ok(Number::new(number)?.map(|number| number.hash()))
+}
+
+/// Converts a hash into the `Integer` which `hash` methods return.
+pub(crate) fn hash_to_value(hash: u64) -> Value {
+	Integer::new_truncate(hash as i64).to_value()
+}
+
+/// Combines the hashes of `values`, in order.
+pub(crate) fn hash_all(values: impl IntoIterator<Item = Value>) -> Result<u64> {
+	let mut hasher = DefaultHasher::new();
+
+	for value in values {
+		hasher.write_u64(value.try_hash()?);
+	}
+
+	Ok(hasher.finish())
+}
+
+/// Combines `hashes` without regard to their order, for hashing unordered collections.
+pub(crate) fn hash_unordered(hashes: impl IntoIterator<Item = u64>) -> u64 {
+	let (mut sum, mut len) = (0u64, 0u64);
+
+	for hash in hashes {
+		sum = sum.wrapping_add(hash);
+		len += 1;
+	}
+
+	let mut hasher = DefaultHasher::new();
+	hasher.write_u64(sum);
+	hasher.write_u64(len);
+	hasher.finish()
+}
+
+/// Hashes `value` via `hash`, unless `value` is already being hashed on this thread (eg because
+/// it contains itself), in which case a constant is returned instead of recursing forever.
+///
+/// Elements are hashed via [`Value::try_hash`], which can run arbitrary Quest code, so the values
+/// being hashed are tracked per-thread rather than passed along.
+pub(crate) fn hash_recursive(value: Value, hash: impl FnOnce() -> Result<u64>) -> Result<u64> {
+	thread_local! {
+		static HASHING: std::cell::RefCell<Vec<Value>> = std::cell::RefCell::default();
+	}
+
+	// The hash of a value which is already being hashed, akin to `dbg` printing `[...]`.
+	const RECURSIVE_HASH: u64 = 0;
+
+	if HASHING.with(|hashing| hashing.borrow().iter().any(|&v| v.is_identical(value))) {
+		return Ok(RECURSIVE_HASH);
+	}
+
+	HASHING.with(|hashing| hashing.borrow_mut().push(value));
+	let result = hash();
+	let last = HASHING.with(|hashing| hashing.borrow_mut().pop());
+	debug_assert!(last.unwrap().is_identical(value));
+
+	result
+}
+
+/// The error for when `lhs` and `rhs` have no ordering.
+pub(crate) fn unordered(lhs: Value, rhs: Value) -> crate::Error {
+	ErrorKind::Message(format!("cannot compare {} with {}", lhs.typename(), rhs.typename())).into()
+}
+
+/// Implements `<`, `<=`, `>` and `>=`, by checking `condition` against the result of `<=>`.
+pub(crate) fn relate(lhs: Value, rhs: Value, condition: fn(Ordering) -> bool) -> Result<Value> {
+	match lhs.try_partial_cmp(rhs)? {
+		Some(ordering) => Ok(condition(ordering).to_value()),
+		None if is_number(lhs) && is_number(rhs) => Ok(false.to_value()),
+		None => Err(unordered(lhs, rhs)),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn cmp(lhs: impl ToValue, rhs: impl ToValue) -> Option<Ordering> {
+		cmp_numbers(lhs.to_value(), rhs.to_value()).unwrap()
+	}
+
+	fn hash(number: impl ToValue) -> u64 {
+		hash_number(number.to_value()).unwrap().unwrap()
+	}
+
+	#[test]
+	fn numbers_compare_exactly_across_types() {
+		let big = BigInt::from(1) << 70_u32;
+
+		assert_eq!(cmp(1, 1.0), Some(Ordering::Equal));
+		assert_eq!(cmp(1, 1.5), Some(Ordering::Less));
+		assert_eq!(cmp(-1, -1.5), Some(Ordering::Greater));
+		assert_eq!(cmp(2.5, 2), Some(Ordering::Greater));
+		assert_eq!(cmp(big.clone(), Float::INFINITY), Some(Ordering::Less));
+		assert_eq!(cmp(big.clone(), 1), Some(Ordering::Greater));
+		assert_eq!(cmp(big.clone(), big.to_f64().unwrap()), Some(Ordering::Equal));
+		assert_eq!(cmp(1, Float::NAN), None);
+		assert_eq!(cmp(1, "1"), None);
+
+		// `i64::MAX` isn't representable as a float, so it mustn't equal its rounded version.
+		let max = BigInt::from(i64::MAX);
+		assert_eq!(cmp(max.clone(), max.to_f64().unwrap()), Some(Ordering::Less));
+	}
+
+	#[test]
+	fn equal_numbers_hash_the_same() {
+		let big = BigInt::from(1) << 70_u32;
+
+		assert_eq!(hash(3), hash(3.0));
+		assert_eq!(hash(-0.0), hash(0));
+		assert_eq!(hash(BigInt::from(3)), hash(3));
+		assert_eq!(hash(big.clone()), hash(big.to_f64().unwrap()));
+		assert_eq!(hash_number("3".to_value()).unwrap(), None);
+	}
+}
@@ -0,0 +1,224 @@
+//! Deeply freezing values, and copying them (either shallowly or deeply).
+//!
+//! Both walk the same graph: a value's attributes, the contents of containers ([`List`]s,
+//! [`Map`]s and [`Set`]s) and, for freezing, its parents. Classes are never frozen or copied, as
+//! they're shared by all their instances; likewise frames are scopes, and so are only frozen once
+//! they've been converted to objects and are done running. Lexical parents (ie the scopes that
+//! blocks and frames were defined in) aren't followed either.
+
+use crate::value::base::Flags;
+use crate::value::ty::{Class, Instance, List, Map, Set, Text};
+use crate::value::{AttributedMut, Gc, HasFlags, HasParents, ToValue};
+use crate::vm::Frame;
+use crate::{ErrorKind, Result, Value};
+use std::collections::{HashMap, HashSet};
+
+/// Freezes `value` and everything reachable from it.
+pub(crate) fn deep_freeze(value: Value) -> Result<()> {
+	let mut pending = vec![value];
+	let mut frozen = HashSet::new();
+
+	while let Some(value) = pending.pop() {
+		if !value.is_allocated() || !frozen.insert(value.bits()) || !is_freezable(value)? {
+			continue;
+		}
+
+		for (attr, attr_value) in value.own_attributes()? {
+			pending.push(attr);
+			pending.push(attr_value);
+		}
+
+		if !has_lexical_parents(value) {
+			pending.extend(value.parents()?);
+		}
+
+		pending.extend(contents(value)?);
+		value.freeze()?;
+	}
+
+	Ok(())
+}
+
+fn is_freezable(value: Value) -> Result<bool> {
+	if value.is_a::<Gc<Class>>() {
+		return Ok(false);
+	}
+
+	if let Some(frame) = value.downcast::<Gc<Frame>>() {
+		let frame = frame.as_ref()?;
+		return Ok(frame.is_object() && !frame.is_running());
+	}
+
+	Ok(true)
+}
+
+fn has_lexical_parents(value: Value) -> bool {
+	unsafe { value.get_gc_any_unchecked() }.flags().contains(Flags::LEXICAL_PARENTS)
+}
+
+fn contents(value: Value) -> Result<Vec<Value>> {
+	if let Some(list) = value.downcast::<Gc<List>>() {
+		Ok(list.as_ref()?.as_slice().to_vec())
+	} else if let Some(map) = value.downcast::<Gc<Map>>() {
+		Ok(map.as_ref()?.iter().flat_map(|(key, value)| [key, value]).collect())
+	} else if let Some(set) = value.downcast::<Gc<Set>>() {
+		Ok(set.as_ref()?.iter().collect())
+	} else {
+		Ok(Vec::new())
+	}
+}
+
+/// Returns an unfrozen copy of `value`, which has the same attributes, parents and contents.
+pub(crate) fn dup(value: Value) -> Result<Value> {
+	shallow_copy(value)?.ok_or_else(|| cannot_copy(value))
+}
+
+/// Returns an unfrozen copy of `value`, whose attributes and contents are also copied.
+///
+/// Values which can't be copied (such as classes and functions) are shared with the original, and
+/// values that are reachable in multiple ways are only copied once.
+pub(crate) fn deep_dup(value: Value) -> Result<Value> {
+	if value.is_allocated() && !is_copyable(value) {
+		return Err(cannot_copy(value));
+	}
+
+	deep_copy(value, &mut HashMap::new())
+}
+
+fn cannot_copy(value: Value) -> crate::Error {
+	ErrorKind::Message(format!("values of type {} cannot be copied", value.typename())).into()
+}
+
+fn is_copyable(value: Value) -> bool {
+	value.is_a::<Gc<Text>>()
+		|| value.is_a::<Gc<List>>()
+		|| value.is_a::<Gc<Map>>()
+		|| value.is_a::<Gc<Set>>()
+		|| value.is_a::<Gc<Instance>>()
+}
+
+fn shallow_copy(value: Value) -> Result<Option<Value>> {
+	if !value.is_allocated() {
+		return Ok(Some(value)); // Unallocated values are immutable, so we don't need to copy them.
+	}
+
+	let copy = if let Some(text) = value.downcast::<Gc<Text>>() {
+		text.as_ref()?.dup().to_value()
+	} else if let Some(list) = value.downcast::<Gc<List>>() {
+		list.as_ref()?.dup().to_value()
+	} else if let Some(map) = value.downcast::<Gc<Map>>() {
+		map.as_ref()?.dup().to_value()
+	} else if let Some(set) = value.downcast::<Gc<Set>>() {
+		set.as_ref()?.dup().to_value()
+	} else if let Some(instance) = value.downcast::<Gc<Instance>>() {
+		instance.as_ref()?.dup().to_value()
+	} else {
+		return Ok(None);
+	};
+
+	let mut copy_header = unsafe { copy.get_gc_any_unchecked() }.as_mut()?;
+
+	match value.parents()?.as_slice() {
+		&[parent] => copy_header.parents_mut().set(parent),
+		parents => copy_header.parents_mut().set(List::from_slice(parents)),
+	}
+
+	for (attr, attr_value) in value.own_attributes()? {
+		copy_header.set_attr(attr, attr_value)?;
+	}
+
+	Ok(Some(copy))
+}
+
+fn deep_copy(value: Value, copies: &mut HashMap<u64, Value>) -> Result<Value> {
+	if let Some(&copy) = copies.get(&value.bits()) {
+		return Ok(copy);
+	}
+
+	let mut copy = match shallow_copy(value)? {
+		Some(copy) if value.is_allocated() => copy,
+		_ => return Ok(value),
+	};
+
+	// Insert it before copying anything else, in case `value` contains itself.
+	copies.insert(value.bits(), copy);
+
+	for (attr, attr_value) in copy.own_attributes()? {
+		let attr_copy = deep_copy(attr_value, copies)?;
+		copy.set_attr(attr, attr_copy)?;
+	}
+
+	if let Some(list) = copy.downcast::<Gc<List>>() {
+		let elements = list.as_ref()?.as_slice().to_vec();
+		let elements = elements
+			.into_iter()
+			.map(|element| deep_copy(element, copies))
+			.collect::<Result<Vec<_>>>()?;
+
+		list.as_mut()?.as_mut_slice().copy_from_slice(&elements);
+	} else if let Some(map) = copy.downcast::<Gc<Map>>() {
+		let entries = map.as_ref()?.iter().collect::<Vec<_>>();
+
+		for (key, entry) in entries {
+			let (key_copy, entry_copy) = (deep_copy(key, copies)?, deep_copy(entry, copies)?);
+			let mut map = map.as_mut()?;
+			map.remove(key)?;
+			map.insert(key_copy, entry_copy)?;
+		}
+	} else if let Some(set) = copy.downcast::<Gc<Set>>() {
+		let elements = set.as_ref()?.iter().collect::<Vec<_>>();
+
+		for element in elements {
+			let element_copy = deep_copy(element, copies)?;
+			let mut set = set.as_mut()?;
+			set.remove(element)?;
+			set.insert(element_copy)?;
+		}
+	}
+
+	Ok(copy)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::value::ty::Integer;
+	use crate::value::HasDefaultParent;
+
+	#[test]
+	fn deep_freeze_freezes_contents_but_not_classes() {
+		let inner = List::from_slice(&[Integer::new(1).unwrap().to_value()]);
+		let map = Map::new();
+		let key = Text::from_static_str("key");
+		map.as_mut().unwrap().insert(key.to_value(), inner.to_value()).unwrap();
+		let mut outer = List::from_slice(&[map.to_value()]).to_value();
+		let attr = Text::from_static_str("attr");
+		outer.set_attr(crate::Intern::len, attr.to_value()).unwrap();
+
+		deep_freeze(outer).unwrap();
+
+		assert!(outer.is_frozen());
+		assert!(map.is_frozen());
+		assert!(key.is_frozen());
+		assert!(inner.is_frozen());
+		assert!(attr.is_frozen());
+		assert!(!List::parent().is_frozen());
+	}
+
+	#[test]
+	fn deep_dup_copies_cycles_once() {
+		let list = List::new();
+		let text = Text::from_static_str("hi").to_value();
+		list.as_mut().unwrap().push(list.to_value());
+		list.as_mut().unwrap().push(text);
+		list.to_value().freeze().unwrap();
+
+		let copy = deep_dup(list.to_value()).unwrap().downcast::<Gc<List>>().unwrap();
+		assert!(!copy.is_frozen());
+		assert!(!copy.ptr_eq(list));
+
+		let copy_ref = copy.as_ref().unwrap();
+		assert!(copy_ref.as_slice()[0].is_identical(copy.to_value()));
+		assert!(!copy_ref.as_slice()[1].is_identical(text));
+	}
+}
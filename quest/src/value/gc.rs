@@ -12,6 +12,11 @@ use std::ops::{Deref, DerefMut};
 use std::ptr::NonNull;
 use std::sync::atomic::{AtomicU32, Ordering};
 
+mod contention;
+pub use contention::{
+	contention, set_contention, start_sharing, Contention, Sharing, DEFAULT_TIMEOUT,
+};
+
 /// A trait that indicates a type contains at a minimum a [`Header`].
 ///
 /// # Safety
@@ -194,11 +199,14 @@ impl<T: Allocated> Gc<T> {
 	/// Attempts to get an immutable reference to `self`'s contents, returning an error if it's
 	/// currently mutably borrowed.
 	///
-	/// This function is thread-safe.
+	/// This function is thread-safe. If another thread has mutably borrowed `self`, this follows
+	/// the current thread's [`Contention`] strategy, which by default waits for it to be released.
 	///
 	/// # Errors
-	/// If the contents are already mutably borrowed (via [`Gc::as_mut`]), this will return
-	/// an [`ErrorKind::AlreadyLocked`].
+	/// If the contents are already mutably borrowed (via [`Gc::as_mut`]) by the current thread,
+	/// or by another thread for longer than the [`Contention`] strategy allows, this will return
+	/// an [`ErrorKind::AlreadyLocked`]. If waiting would deadlock, an [`ErrorKind::Deadlock`] is
+	/// returned instead.
 	///
 	/// # Panics
 	/// This will panic if more than [`MAX_BORROWS`] borrows are currently held.
@@ -228,15 +236,27 @@ impl<T: Allocated> Gc<T> {
 	/// # quest::Result::<()>::Ok(())
 	/// ```
 	pub fn as_ref(self) -> Result<Ref<T>> {
-		self.as_ref_option().ok_or_else(|| ErrorKind::AlreadyLocked(self.to_value()).into())
+		self
+			.as_ref_option()
+			.map_or_else(|| contention::wait_for(self.to_value(), || self.as_ref_option()), Ok)
 	}
 
 	/// Tries to convert `self` to a reference, returning `None` if we can't.
+	///
+	/// Unlike [`as_ref`](Self::as_ref), this never waits for other threads.
+	///
+	/// Since [frozen](Self::is_frozen) values can never be modified, references to them aren't
+	/// tracked, which lets them be shared between threads without any contention.
 	pub fn as_ref_option(self) -> Option<Ref<T>> {
 		if cfg!(feature = "unsafe-no-locking") {
 			return Some(Ref(self));
 		}
 
+		// It can't be mutably borrowed after being frozen, but might've been from before.
+		if self.is_frozen() && self.borrows().load(Ordering::Acquire) != MUT_BORROW {
+			return Some(Ref(self));
+		}
+
 		fn updatefn(x: u32) -> Option<u32> {
 			if x == MUT_BORROW {
 				None
@@ -247,7 +267,10 @@ impl<T: Allocated> Gc<T> {
 
 		match self.borrows().fetch_update(Ordering::Acquire, Ordering::Relaxed, updatefn) {
 			Ok(x) if x == MAX_BORROWS as u32 => panic!("too many immutable borrows"),
-			Ok(_) => Some(Ref(self)),
+			Ok(_) => {
+				contention::acquired(self.addr());
+				Some(Ref(self))
+			}
 			Err(_) => None,
 		}
 	}
@@ -255,11 +278,14 @@ impl<T: Allocated> Gc<T> {
 	/// Attempts to get a mutable reference to `self`'s contents, returning an error if it's
 	/// currently immutably borrowed.
 	///
-	/// This function is thread-safe.
+	/// This function is thread-safe. If another thread has borrowed `self`, this follows the
+	/// current thread's [`Contention`] strategy, which by default waits for it to be released.
 	///
 	/// # Errors
-	/// If the contents are already immutably borrowed (via [`Gc::as_ref`]), this will
-	/// return an [`ErrorKind::AlreadyLocked`].
+	/// If the contents are already borrowed (via [`Gc::as_ref`] or [`Gc::as_mut`]) by the current
+	/// thread, or by another thread for longer than the [`Contention`] strategy allows, this will
+	/// return an [`ErrorKind::AlreadyLocked`]. If waiting would deadlock, an
+	/// [`ErrorKind::Deadlock`] is returned instead.
 	///
 	/// If the data has been [frozen](Ref::freeze), this will return a [`ErrorKind::ValueFrozen`].
 	///
@@ -300,12 +326,16 @@ impl<T: Allocated> Gc<T> {
 			return Ok(Mut(self));
 		}
 
-		if self
-			.borrows()
-			.compare_exchange(0, MUT_BORROW, Ordering::Acquire, Ordering::Relaxed)
-			.is_err()
-		{
-			return Err(ErrorKind::AlreadyLocked(self.to_value()).into());
+		let try_lock = || {
+			self
+				.borrows()
+				.compare_exchange(0, MUT_BORROW, Ordering::Acquire, Ordering::Relaxed)
+				.ok()
+				.map(|_| contention::acquired(self.addr()))
+		};
+
+		if try_lock().is_none() {
+			contention::wait_for(self.to_value(), try_lock)?;
 		}
 
 		let mutref = Mut(self);
@@ -363,6 +393,11 @@ impl<T: Allocated> Gc<T> {
 		self.0.as_ptr()
 	}
 
+	// The address of `self`, which is used to track which values each thread holds.
+	fn addr(self) -> usize {
+		self.as_ptr() as usize
+	}
+
 	/// Gets the header of `self`.
 	///
 	/// Technically this could be publicly visible, but outside the crate, you should get a reference
@@ -415,6 +450,15 @@ impl<T: Allocated> From<Gc<T>> for Value<Gc<T>> {
 	}
 }
 
+impl<T: Allocated> crate::value::FromValue for Gc<T>
+where
+	Self: crate::value::NamedType,
+{
+	fn from_value(value: Value) -> Result<Self> {
+		value.try_downcast()
+	}
+}
+
 // SAFETY: We correctly implemented `is_a` to only return true if the `Value` is a `Gc<T>`.
 // Additionally, `get` will always return a valid `Gc<T>` for any `Value<Gc<T>>`.
 unsafe impl<T: Allocated> Convertible for Gc<T> {
@@ -429,7 +473,10 @@ unsafe impl<T: Allocated> Convertible for Gc<T> {
 		// such, converting the bits to a pointer will yield a non-zero pointer. Additionally, since
 		// the pointer points to _some_ `Gc` type, we're allowed to construct a `Gc<Any>` of it, as
 		// we're not accessing the `data` at all. (We're only getting the `typeflag` from the header.)
-		unsafe { (*(value.bits() as *const () as *const Header)).flags().type_flag() == T::TYPE_FLAG }
+		unsafe {
+			(*(value.bits() as *const () as *const Header)).flags().type_flag() == T::TYPE_FLAG
+				&& T::is_instance(value.bits() as usize as *const T)
+		}
 	}
 
 	fn get(value: Value<Self>) -> Self {
@@ -443,7 +490,9 @@ unsafe impl<T: Allocated> Convertible for Gc<T> {
 
 /// A smart pointer used to release read access when dropped.
 ///
-/// This is created via the [`as_ref`](Gc::as_ref) method on [`Gc`].
+/// This is created via the [`as_ref`](Gc::as_ref) method on [`Gc`]. It should be dropped on the
+/// thread that created it: while values are being [shared](start_sharing), dropping it elsewhere
+/// leaves the creating thread thinking it still holds the value.
 #[repr(transparent)]
 pub struct Ref<T: Allocated>(Gc<T>);
 
@@ -515,6 +564,13 @@ impl<T: Allocated> Drop for Ref<T> {
 			return;
 		}
 
+		// References to frozen values aren't tracked. (References from before the value was frozen
+		// are, but since it can't be mutably borrowed anymore, we only need to forget about them.)
+		if self.0.is_frozen() {
+			contention::released(self.0.addr());
+			return;
+		}
+
 		let prev = self.0.borrows().fetch_sub(1, Ordering::Release);
 
 		// Sanity check, as it's impossible for us to have a `MUT_BORROW` after a `Ref` is created.
@@ -523,12 +579,15 @@ impl<T: Allocated> Drop for Ref<T> {
 		// Another sanity check, as this indicates something double freed (or a `Mut` was
 		// incorrectly created).
 		debug_assert_ne!(prev, 0);
+
+		contention::released(self.0.addr());
 	}
 }
 
 /// A smart pointer used to release write access when dropped.
 ///
-/// This is created via the [`as_mut`](Gc::as_mut) method on [`Gc`].
+/// This is created via the [`as_mut`](Gc::as_mut) method on [`Gc`]. Like a [`Ref`], it should be
+/// dropped on the thread that created it.
 #[repr(transparent)]
 pub struct Mut<T: Allocated>(Gc<T>);
 
@@ -557,6 +616,11 @@ impl<T: Allocated> DerefMut for Mut<T> {
 
 impl<T: Allocated> Drop for Mut<T> {
 	fn drop(&mut self) {
+		// Lists of parents can be modified in place, which changes the lookups of their values.
+		if self.0.flags().contains(Flags::PARENTS_LIST) {
+			crate::vm::inline_cache::invalidate();
+		}
+
 		if cfg!(feature = "unsafe-no-locking") {
 			return;
 		}
@@ -567,6 +631,8 @@ impl<T: Allocated> Drop for Mut<T> {
 		} else {
 			self.0.borrows().store(0, Ordering::Release);
 		}
+
+		contention::released(self.0.addr());
 	}
 }
 
@@ -618,4 +684,100 @@ mod tests {
 		assert_matches!(text.as_mut().unwrap_err().kind, ErrorKind::ValueFrozen(_));
 		assert!(text.is_frozen());
 	}
+
+	#[test]
+	fn waits_for_other_threads() {
+		let _sharing = start_sharing();
+		let text = Text::from_static_str("g'day mate");
+		let mut textmut = text.as_mut().unwrap();
+
+		let reader = std::thread::spawn(move || *text.as_ref().unwrap() == *"g'day mate!");
+		std::thread::sleep(std::time::Duration::from_millis(50));
+		textmut.push('!');
+		drop(textmut);
+
+		assert!(reader.join().unwrap());
+	}
+
+	#[test]
+	fn borrows_on_the_same_thread_dont_wait() {
+		let _sharing = start_sharing();
+		let text = Text::from_static_str("g'day mate");
+		let textref = text.as_ref().unwrap();
+
+		let writer = std::thread::spawn(move || text.as_mut().map(|mut text| text.push('!')));
+		std::thread::sleep(std::time::Duration::from_millis(50));
+
+		// The writer waiting for the value doesn't stop us from borrowing it again...
+		let nested = text.as_ref().unwrap();
+		assert_eq!(*nested, *"g'day mate");
+
+		// ...but we can't modify it while we have it borrowed, and don't wait on ourselves to find out.
+		let start = std::time::Instant::now();
+		assert_matches!(text.as_mut().unwrap_err().kind, ErrorKind::AlreadyLocked(_));
+		assert!(start.elapsed() < DEFAULT_TIMEOUT / 2);
+
+		drop((textref, nested));
+		writer.join().unwrap().unwrap();
+		assert_eq!(*text.as_ref().unwrap(), *"g'day mate!");
+	}
+
+	#[test]
+	fn guards_can_be_sent_to_other_threads() {
+		let text = Text::from_static_str("g'day mate");
+		let textmut = text.as_mut().unwrap();
+
+		std::thread::spawn(move || drop(textmut)).join().unwrap();
+		assert_matches!(text.as_mut(), Ok(_));
+	}
+
+	#[test]
+	fn respects_contention_strategy() {
+		let _sharing = start_sharing();
+		let text = Text::from_static_str("g'day mate");
+		let textmut = text.as_mut().unwrap();
+
+		let reader = std::thread::spawn(move || {
+			set_contention(Contention::Fail);
+			let fail = text.as_ref().map(drop).unwrap_err();
+
+			set_contention(Contention::Wait { timeout: std::time::Duration::from_millis(10) });
+			let timeout = text.as_ref().map(drop).unwrap_err();
+
+			(fail.kind, timeout.kind)
+		});
+
+		let (fail, timeout) = reader.join().unwrap();
+		assert_matches!(fail, ErrorKind::AlreadyLocked(_));
+		assert_matches!(timeout, ErrorKind::AlreadyLocked(_));
+		drop(textmut);
+	}
+
+	#[test]
+	fn detects_deadlocks() {
+		let _sharing = start_sharing();
+		let first = Text::from_static_str("first");
+		let second = Text::from_static_str("second");
+		let barrier = std::sync::Arc::new(std::sync::Barrier::new(2));
+
+		// Each thread locks one value, and then waits for the one the other thread has.
+		let lock_both = move |first: Gc<Text>, second: Gc<Text>, barrier: &std::sync::Barrier| {
+			let _firstmut = first.as_mut().unwrap();
+			barrier.wait();
+			second.as_mut().map(drop).map_err(|err| err.kind)
+		};
+
+		let other = std::thread::spawn({
+			let barrier = barrier.clone();
+			move || lock_both(second, first, &barrier)
+		});
+
+		let results = [lock_both(first, second, &barrier), other.join().unwrap()];
+
+		// Whichever thread noticed the deadlock gave up, which lets the other one continue.
+		assert_matches!(
+			results,
+			[Ok(()), Err(ErrorKind::Deadlock(_))] | [Err(ErrorKind::Deadlock(_)), Ok(())]
+		);
+	}
 }
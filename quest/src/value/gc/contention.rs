@@ -0,0 +1,339 @@
+//! What happens when a [`Gc`](super::Gc) is locked by someone else.
+//!
+//! While values are being [shared](start_sharing) between threads, each thread keeps track of
+//! which values it currently has borrowed. When [`Gc::as_ref`] or [`Gc::as_mut`] find that a value
+//! is already locked, what happens depends on who holds it:
+//! - If the current thread holds a conflicting borrow, an [`ErrorKind::AlreadyLocked`] is returned
+//!   straight away, as waiting for ourselves would never finish.
+//! - Otherwise, another thread has it, and the thread's [`Contention`] strategy is used. By default,
+//!   this spins for a bit, and then parks the thread until the value's released.
+//!
+//! Before parking, the thread checks whether the value's holder is (indirectly) waiting on a value
+//! the current thread holds. If it is, neither could ever continue, so an [`ErrorKind::Deadlock`]
+//! is returned instead.
+//!
+//! Keeping track of borrows isn't free, so it's only done while a [`Sharing`] exists (which every
+//! running [`Thread`](crate::value::ty::thread::Thread) has). Otherwise, the only thread which could hold
+//! a value is the current one, so conflicting borrows always fail straight away. Borrows made
+//! before sharing started aren't tracked: waiting on them can't detect deadlocks, and can only
+//! give up once the timeout passes.
+//!
+//! # Reentrancy
+//! Borrows are only reentrant in the same way as a `RefCell`'s: any amount of [`Ref`]s can be
+//! nested, regardless of whether other threads are waiting to modify the value, but a thread can't
+//! get a [`Mut`] to a value it's already borrowed (or a [`Ref`] to one it's mutably borrowed), as
+//! that would let it alias the value's contents.
+//!
+//! [`Gc::as_ref`]: super::Gc::as_ref
+//! [`Gc::as_mut`]: super::Gc::as_mut
+//! [`Ref`]: super::Ref
+//! [`Mut`]: super::Mut
+
+use crate::{ErrorKind, Result, Value};
+use std::cell::{Cell, RefCell};
+use std::sync::atomic::{self, AtomicUsize, Ordering};
+use std::sync::{Condvar, Mutex};
+use std::thread::{self, ThreadId};
+use std::time::{Duration, Instant};
+
+/// How long [`Contention::default`] waits for a value before giving up.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// What to do when a value is locked by another thread.
+///
+/// This is set per-thread via [`set_contention`], and is only used while values are being
+/// [shared](start_sharing).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Contention {
+	/// Return an [`ErrorKind::AlreadyLocked`] immediately.
+	Fail,
+
+	/// Spin briefly, and then park the thread until the value's released. If that takes longer than
+	/// `timeout`, an [`ErrorKind::AlreadyLocked`] is returned.
+	Wait { timeout: Duration },
+}
+
+impl Default for Contention {
+	fn default() -> Self {
+		Self::Wait { timeout: DEFAULT_TIMEOUT }
+	}
+}
+
+// How many `Sharing`s exist. Borrows are only tracked while there are any.
+static SHARING: AtomicUsize = AtomicUsize::new(0);
+
+// Incremented each time sharing starts, so that borrows from a previous time (which may have been
+// released while nothing was being tracked) aren't thought to still be held.
+static EPOCH: AtomicUsize = AtomicUsize::new(0);
+
+fn is_sharing() -> bool {
+	SHARING.load(Ordering::Relaxed) != 0
+}
+
+/// Indicates that values may be shared between threads, for as long as it's alive.
+///
+/// This is what lets threads wait for each other: without one, conflicting borrows fail straight
+/// away. [`Thread`](crate::value::ty::thread::Thread)s have one for as long as they're running, so this is
+/// only needed when sharing values between threads spawned some other way.
+#[must_use]
+#[derive(Debug)]
+pub struct Sharing(());
+
+/// Starts sharing values between threads, until the returned [`Sharing`] is dropped.
+pub fn start_sharing() -> Sharing {
+	if SHARING.fetch_add(1, Ordering::SeqCst) == 0 {
+		EPOCH.fetch_add(1, Ordering::SeqCst);
+	}
+
+	Sharing(())
+}
+
+impl Drop for Sharing {
+	fn drop(&mut self) {
+		SHARING.fetch_sub(1, Ordering::SeqCst);
+	}
+}
+
+// The addresses of every value a thread has borrowed, with duplicates for nested borrows.
+struct Held {
+	epoch: usize,
+	addrs: Vec<usize>,
+}
+
+impl Held {
+	fn current(&mut self) -> &mut Vec<usize> {
+		let epoch = EPOCH.load(Ordering::Relaxed);
+
+		if self.epoch != epoch {
+			self.epoch = epoch;
+			self.addrs.clear();
+		}
+
+		&mut self.addrs
+	}
+}
+
+thread_local! {
+	static CONTENTION: Cell<Contention> = Cell::new(Contention::default());
+	static HELD: RefCell<Held> = const { RefCell::new(Held { epoch: 0, addrs: Vec::new() }) };
+}
+
+/// Sets the contention strategy for the current thread.
+pub fn set_contention(contention: Contention) {
+	CONTENTION.with(|cell| cell.set(contention));
+}
+
+/// Gets the contention strategy for the current thread.
+#[must_use]
+pub fn contention() -> Contention {
+	CONTENTION.with(Cell::get)
+}
+
+/// Records that the current thread borrowed the value at `addr`.
+pub(super) fn acquired(addr: usize) {
+	if !is_sharing() {
+		return;
+	}
+
+	// If the thread's being torn down there's nothing left to conflict with, so this can't fail.
+	let _ = HELD.try_with(|held| held.borrow_mut().current().push(addr));
+}
+
+/// Records that the current thread released a borrow of the value at `addr`, and wakes up any
+/// threads that were waiting on it.
+///
+/// This must be called after the borrow itself has been released.
+pub(super) fn released(addr: usize) {
+	// Nothing can be waiting unless values are being shared, as there's no one else to wait on.
+	if !is_sharing() {
+		return;
+	}
+
+	let _ = HELD.try_with(|held| {
+		let mut held = held.borrow_mut();
+		let held = held.current();
+
+		// Borrows are usually released in the opposite order they were acquired.
+		if let Some(index) = held.iter().rposition(|&held| held == addr) {
+			held.swap_remove(index);
+		}
+	});
+
+	let bucket = Bucket::of(addr);
+
+	// Pairs with the fence in `Bucket::park`, so either we see its waiter or it sees our release.
+	atomic::fence(Ordering::SeqCst);
+	if bucket.waiters.load(Ordering::Relaxed) != 0 {
+		let _guard = bucket.lock.lock().unwrap();
+		bucket.wakeup.notify_all();
+	}
+}
+
+fn is_held(addr: usize) -> bool {
+	HELD.try_with(|held| held.borrow_mut().current().contains(&addr)).unwrap_or(false)
+}
+
+/// Waits until `try_lock` succeeds, following the current thread's [`Contention`] strategy.
+///
+/// `value` is the value being locked, and is only used for errors.
+pub(super) fn wait_for<R>(value: Value, mut try_lock: impl FnMut() -> Option<R>) -> Result<R> {
+	let addr = value.bits() as usize;
+
+	let timeout = match contention() {
+		Contention::Wait { timeout } if is_sharing() && !is_held(addr) => timeout,
+		_ => return Err(ErrorKind::AlreadyLocked(value).into()),
+	};
+
+	let deadline = Instant::now() + timeout;
+
+	// Most borrows are short, so spinning for a bit is cheaper than parking.
+	for spin in 0..SPIN_LIMIT {
+		if spin < 6 {
+			(0..1 << spin).for_each(|_| std::hint::spin_loop());
+		} else {
+			thread::yield_now();
+		}
+
+		if let Some(locked) = try_lock() {
+			return Ok(locked);
+		}
+	}
+
+	let _waiting = Waiting::register(addr).ok_or(ErrorKind::Deadlock(value))?;
+	let bucket = Bucket::of(addr);
+
+	loop {
+		if let Some(locked) = bucket.park(deadline, &mut try_lock) {
+			return Ok(locked);
+		}
+
+		if Instant::now() >= deadline {
+			return Err(ErrorKind::AlreadyLocked(value).into());
+		}
+	}
+}
+
+const SPIN_LIMIT: u32 = 10;
+
+// Waiting threads are split up by address, so releasing a value only wakes up threads which were
+// (probably) waiting on it.
+const BUCKET_COUNT: usize = 64;
+
+struct Bucket {
+	waiters: AtomicUsize,
+	lock: Mutex<()>,
+	wakeup: Condvar,
+}
+
+static BUCKETS: [Bucket; BUCKET_COUNT] = [const {
+	Bucket { waiters: AtomicUsize::new(0), lock: Mutex::new(()), wakeup: Condvar::new() }
+}; BUCKET_COUNT];
+
+impl Bucket {
+	fn of(addr: usize) -> &'static Self {
+		// The bottom four bits of allocated values are always zero.
+		&BUCKETS[(addr >> 4) % BUCKET_COUNT]
+	}
+
+	/// Parks the current thread until the bucket's woken up or `deadline` passes, trying to lock
+	/// the value beforehand.
+	fn park<R>(&self, deadline: Instant, try_lock: &mut impl FnMut() -> Option<R>) -> Option<R> {
+		self.waiters.fetch_add(1, Ordering::SeqCst);
+		atomic::fence(Ordering::SeqCst);
+
+		let guard = self.lock.lock().unwrap();
+		let locked = try_lock().or_else(|| {
+			let timeout = deadline.saturating_duration_since(Instant::now());
+			drop(self.wakeup.wait_timeout(guard, timeout).unwrap());
+			try_lock()
+		});
+
+		self.waiters.fetch_sub(1, Ordering::SeqCst);
+		locked
+	}
+}
+
+// A thread that's waiting on a value, along with all the values it holds.
+struct Waiter {
+	thread: ThreadId,
+	waiting_on: usize,
+	held: Vec<usize>,
+}
+
+static WAITERS: Mutex<Vec<Waiter>> = Mutex::new(Vec::new());
+
+/// Marks the current thread as waiting until it's dropped.
+struct Waiting;
+
+impl Waiting {
+	/// Registers the current thread as waiting on `addr`, returning `None` if that would deadlock.
+	fn register(addr: usize) -> Option<Self> {
+		let waiter = Waiter {
+			thread: thread::current().id(),
+			waiting_on: addr,
+			held: HELD.try_with(|held| held.borrow_mut().current().clone()).unwrap_or_default(),
+		};
+
+		// Since registering and checking happen atomically, the last thread to complete a cycle
+		// always notices it.
+		let mut waiters = WAITERS.lock().unwrap();
+
+		if would_deadlock(&waiters, &waiter) {
+			return None;
+		}
+
+		waiters.push(waiter);
+		Some(Self)
+	}
+}
+
+impl Drop for Waiting {
+	fn drop(&mut self) {
+		let current = thread::current().id();
+		WAITERS.lock().unwrap().retain(|waiter| waiter.thread != current);
+	}
+}
+
+// Whether `waiter` is waiting on a value whose holder is (indirectly) waiting on `waiter`.
+fn would_deadlock(waiters: &[Waiter], waiter: &Waiter) -> bool {
+	let mut visited = vec![false; waiters.len()];
+	let mut targets = vec![waiter.waiting_on];
+
+	while let Some(target) = targets.pop() {
+		for (index, holder) in waiters.iter().enumerate() {
+			if visited[index] || !holder.held.contains(&target) {
+				continue;
+			}
+
+			if waiter.held.contains(&holder.waiting_on) {
+				return true;
+			}
+
+			visited[index] = true;
+			targets.push(holder.waiting_on);
+		}
+	}
+
+	false
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn waiter(waiting_on: usize, held: &[usize]) -> Waiter {
+		Waiter { thread: thread::current().id(), waiting_on, held: held.to_vec() }
+	}
+
+	#[test]
+	fn detects_cycles_between_waiters() {
+		// One thread holds 1 and waits on 2, which another holds while waiting on 3.
+		let waiters = [waiter(2, &[1]), waiter(3, &[2]), waiter(99, &[4])];
+
+		assert!(would_deadlock(&waiters, &waiter(1, &[3])));
+		assert!(would_deadlock(&waiters, &waiter(2, &[3])));
+		assert!(!would_deadlock(&waiters, &waiter(1, &[5])));
+		assert!(!would_deadlock(&waiters, &waiter(4, &[3])));
+		assert!(!would_deadlock(&waiters, &waiter(5, &[1, 2, 3])));
+	}
+}
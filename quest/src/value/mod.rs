@@ -5,12 +5,16 @@ pub mod ty;
 
 mod attributed;
 pub mod base;
+pub(crate) mod compare;
 pub mod gc;
+mod freeze;
+pub(crate) mod mro;
 mod value;
 
 pub use attributed::{
 	Attributed, AttributedMut, Callable, HasAttributes, HasFlags, HasParents, TryAttributed,
 };
+pub(crate) use attributed::bind;
 pub use gc::Gc;
 pub use value::Value;
 
@@ -86,3 +90,35 @@ impl ToValue for std::cmp::Ordering {
 		}
 	}
 }
+
+/// A trait that indicates a type can be created from a [`Value`].
+///
+/// This is the counterpart to [`ToValue`]. Quest types (eg [`Integer`](ty::Integer) and
+/// [`Gc<List>`](ty::List)) must be given exactly that type, whereas Rust types may perform
+/// conversions (eg [`i64`] uses [`Value::to_integer`]). Conversions can also fail in other ways,
+/// such as when a [`List`](ty::List) of the wrong length is converted into a tuple.
+///
+/// Structs with named fields can `#[derive(FromValue)]`, in which case each field is taken from the
+/// attribute of the same name.
+///
+/// Borrowed types such as `&str` are intentionally not supported: a [`Text`](ty::Text) can be
+/// modified after it's converted, so the only way to hand out a `&str` would be to leak a copy of
+/// it. Instead, convert to a [`String`] to get a copy, or to a [`Gc<Text>`](ty::Text) and use
+/// [`Gc::as_ref`] to borrow its contents.
+pub trait FromValue: Sized {
+	/// Perform the conversion from `value`.
+	fn from_value(value: Value) -> crate::Result<Self>;
+}
+
+impl FromValue for Value {
+	fn from_value(value: Value) -> crate::Result<Self> {
+		Ok(value)
+	}
+}
+
+/// Derives [`FromValue`] for structs with named fields, taking each field from an attribute.
+pub use qvm_macros::FromValue;
+
+/// Derives [`ToValue`] for structs with named fields, creating an [`Object`](ty::Object) with an
+/// attribute for each field.
+pub use qvm_macros::ToValue;
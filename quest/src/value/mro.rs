@@ -0,0 +1,293 @@
+//! The method resolution order (MRO) of values.
+//!
+//! When a value has multiple parents, the order its ancestors are searched in is determined by
+//! [C3 linearization](https://en.wikipedia.org/wiki/C3_linearization), the same algorithm Python
+//! uses. Its linearization is the value itself followed by a merge of its parents' linearizations,
+//! which guarantees that:
+//! - a value is always searched before its parents,
+//! - parents are searched in the order they're listed in, and
+//! - an ancestor that's shared by multiple parents (such as `Object`) is only searched once, after
+//!   every value that inherits from it.
+//!
+//! For values with (at most) a single parent, this is identical to searching parents depth-first,
+//! which is what lookups do in that case. The parents of frames and blocks are their lexical
+//! scopes, which are also searched depth-first (skipping ancestors that were already searched),
+//! as they'd otherwise rarely have a consistent linearization.
+
+use crate::value::ty::List;
+use crate::value::{Gc, ToValue};
+use crate::vm::inline_cache;
+use crate::{ErrorKind, Result, Value};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+// Linearizations aren't worth caching beyond this; it's cleared when it gets bigger.
+const MAX_CACHED_LINEARIZATIONS: usize = 1024;
+
+// Linearizations of lists of parents, keyed by the lists' addresses. Every value in them is flagged
+// for the inline caches, so they're valid until the inline cache version changes.
+#[derive(Default)]
+struct Linearizations {
+	version: u64,
+	lists: HashMap<usize, Rc<[Value]>>,
+}
+
+thread_local! {
+	static LINEARIZATIONS: RefCell<Linearizations> = RefCell::default();
+}
+
+/// Gets the linearization of `value`, i.e. `value` followed by its ancestors in lookup order.
+pub(crate) fn linearize(value: Value) -> Result<Vec<Value>> {
+	linearize_checked(value, &mut Vec::new())
+}
+
+/// Gets the linearization of `parents` (without the value they belong to).
+pub(crate) fn linearize_parents(parents: &[Value]) -> Result<Vec<Value>> {
+	linearize_parents_checked(parents, &mut Vec::new())
+}
+
+/// Gets the ancestors of the lexical scopes `scopes`, in the order they're searched in.
+pub(crate) fn linearize_scopes(scopes: &[Value]) -> Result<Vec<Value>> {
+	linearize_scopes_checked(scopes, &mut Vec::new())
+}
+
+/// Gets the linearization of `list`, which are the parents of some value. Unlike
+/// [`linearize_parents`], this is cached until an ancestor (or `list` itself) is modified.
+pub(crate) fn linearize_parents_list(list: Gc<List>) -> Result<Rc<[Value]>> {
+	let address = list.as_ptr() as usize;
+	let version = inline_cache::version();
+
+	let cached = LINEARIZATIONS.with(|cache| {
+		let mut cache = cache.borrow_mut();
+
+		if cache.version != version {
+			cache.version = version;
+			cache.lists.clear();
+		}
+
+		cache.lists.get(&address).cloned()
+	});
+
+	if let Some(linearization) = cached {
+		return Ok(linearization);
+	}
+
+	// The version's read before the ancestors are flagged, so if any of them are modified afterwards,
+	// the linearization will just be out of date.
+	let parents = list.as_ref()?.as_slice().to_vec();
+	let linearization = Rc::<[Value]>::from(linearize_parents(&parents)?);
+
+	for &ancestor in linearization.iter() {
+		inline_cache::depend_on(ancestor);
+	}
+
+	LINEARIZATIONS.with(|cache| {
+		let mut cache = cache.borrow_mut();
+
+		if cache.version == version {
+			if MAX_CACHED_LINEARIZATIONS <= cache.lists.len() {
+				cache.lists.clear();
+			}
+
+			cache.lists.insert(address, linearization.clone());
+		}
+	});
+
+	Ok(linearization)
+}
+
+/// Checks that `parents` could be used as the parents of `value`, i.e. that doing so wouldn't
+/// introduce a cycle, and that they have a consistent linearization.
+pub(crate) fn check_parents(value: Value, parents: &[Value]) -> Result<()> {
+	linearize_parents_checked(parents, &mut vec![value]).and(Ok(()))
+}
+
+// `visiting` contains the values whose linearizations are currently being calculated, so if we
+// encounter one of them again, it's its own ancestor.
+fn linearize_checked(value: Value, visiting: &mut Vec<Value>) -> Result<Vec<Value>> {
+	if visiting.iter().any(|visited| visited.is_identical(value)) {
+		return Err(ErrorKind::CyclicParents(value).into());
+	}
+
+	visiting.push(value);
+	let ancestors = value.parents().and_then(|parents| {
+		if value.has_lexical_parents()? {
+			linearize_scopes_checked(&parents, visiting)
+		} else {
+			linearize_parents_checked(&parents, visiting)
+		}
+	});
+	visiting.pop();
+
+	let mut linearization = vec![value];
+	linearization.extend(ancestors?);
+	Ok(linearization)
+}
+
+fn linearize_parents_checked(parents: &[Value], visiting: &mut Vec<Value>) -> Result<Vec<Value>> {
+	match parents {
+		[] => Ok(Vec::new()),
+		[parent] => linearize_checked(*parent, visiting),
+		_ => {
+			let mut sequences = parents
+				.iter()
+				.map(|&parent| linearize_checked(parent, visiting))
+				.collect::<Result<Vec<_>>>()?;
+			sequences.push(parents.to_vec());
+
+			merge(sequences).ok_or_else(|| {
+				ErrorKind::InconsistentParents(List::from_slice(parents).to_value()).into()
+			})
+		}
+	}
+}
+
+fn linearize_scopes_checked(scopes: &[Value], visiting: &mut Vec<Value>) -> Result<Vec<Value>> {
+	let mut linearization = Vec::<Value>::new();
+
+	for &scope in scopes {
+		for ancestor in linearize_checked(scope, visiting)? {
+			if !linearization.iter().any(|existing| existing.is_identical(ancestor)) {
+				linearization.push(ancestor);
+			}
+		}
+	}
+
+	Ok(linearization)
+}
+
+// Repeatedly takes the first head of a sequence which isn't in the tail of any other sequence.
+// If there's no such head before all the sequences are exhausted, there's no valid linearization.
+fn merge(mut sequences: Vec<Vec<Value>>) -> Option<Vec<Value>> {
+	let mut merged = Vec::new();
+
+	loop {
+		sequences.retain(|sequence| !sequence.is_empty());
+
+		if sequences.is_empty() {
+			return Some(merged);
+		}
+
+		let head = sequences.iter().map(|sequence| sequence[0]).find(|&head| {
+			!sequences.iter().any(|sequence| sequence[1..].iter().any(|v| v.is_identical(head)))
+		})?;
+
+		merged.push(head);
+
+		for sequence in &mut sequences {
+			if sequence[0].is_identical(head) {
+				sequence.remove(0);
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::value::ty::{Object, Wrap};
+	use crate::value::{Attributed, AttributedMut};
+
+	fn object(parents: &[Value]) -> Value {
+		Wrap::with_parent((), List::from_slice(parents)).to_value()
+	}
+
+	fn assert_linearization(value: Value, expected: &[Value]) {
+		let linearization = linearize(value).unwrap();
+
+		assert_eq!(linearization.len(), expected.len(), "{linearization:?} vs {expected:?}");
+		for (actual, expected) in linearization.iter().zip(expected) {
+			assert!(actual.is_identical(*expected), "{linearization:?}");
+		}
+	}
+
+	#[test]
+	fn diamond() {
+		let base = object(&[]);
+		let left = object(&[base]);
+		let right = object(&[base]);
+		let bottom = object(&[left, right]);
+
+		// Depth-first would search `base` before `right`.
+		assert_linearization(bottom, &[bottom, left, right, base]);
+	}
+
+	#[test]
+	fn shared_ancestors() {
+		let o = Object::instance();
+		let a = object(&[o]);
+		let b = object(&[o]);
+		let c = object(&[o]);
+		let k1 = object(&[a, b]);
+		let k2 = object(&[b, c]);
+		let z = object(&[k1, k2]);
+
+		let mut expected = vec![z, k1, a, k2, b, c];
+		expected.extend(linearize(o).unwrap());
+		assert_linearization(z, &expected);
+	}
+
+	#[test]
+	fn inconsistent() {
+		let a = object(&[]);
+		let b = object(&[a]);
+
+		// `a` must come before `b` as it's listed first, but `b` inherits from `a`.
+		assert_matches!(
+			linearize(object(&[a, b])).unwrap_err().kind,
+			ErrorKind::InconsistentParents(_)
+		);
+		assert!(linearize(object(&[b, a])).is_ok());
+	}
+
+	#[test]
+	fn cycles() {
+		let mut a = object(&[]);
+		let b = object(&[a]);
+
+		assert_matches!(check_parents(a, &[b]).unwrap_err().kind, ErrorKind::CyclicParents(_));
+		assert!(check_parents(a, &[Object::instance()]).is_ok());
+
+		// Assigning `__parents__` checks for cycles, and leaves the parents untouched if one's found.
+		let err = a.set_attr(crate::Intern::__parents__, List::from_slice(&[b]).to_value());
+		assert_matches!(err.unwrap_err().kind, ErrorKind::CyclicParents(_));
+		assert!(a.parents().unwrap().is_empty());
+	}
+
+	#[test]
+	fn modifying_parents_in_place() {
+		use crate::vm::Args;
+		use crate::Intern;
+
+		let mut base = object(&[]);
+		base.set_attr(Intern::len, 1_i64.to_value()).unwrap();
+		let mut other = object(&[]);
+		other.set_attr(Intern::len, 2_i64.to_value()).unwrap();
+		let left = object(&[base]);
+		let parents = List::from_slice(&[left]);
+		let bottom = Wrap::with_parent((), parents).to_value();
+		let call = |attr, arg| parents.to_value().call_attr(attr, Args::new(&[arg], &[]));
+
+		assert_eq!(
+			bottom.get_unbound_attr(Intern::len).unwrap().unwrap().bits(),
+			1_i64.to_value().bits()
+		);
+
+		// Invalid modifications are undone.
+		assert_matches!(call(Intern::push, bottom).unwrap_err().kind, ErrorKind::CyclicParents(_));
+		assert_matches!(
+			call(Intern::unshift, base).unwrap_err().kind,
+			ErrorKind::InconsistentParents(_)
+		);
+		assert_linearization(bottom, &[bottom, left, base]);
+
+		// Lookups see valid ones.
+		call(Intern::unshift, other).unwrap();
+		assert_linearization(bottom, &[bottom, other, left, base]);
+		assert_eq!(
+			bottom.get_unbound_attr(Intern::len).unwrap().unwrap().bits(),
+			2_i64.to_value().bits()
+		);
+	}
+}
@@ -63,6 +63,8 @@ impl AsRef<BigInt> for BigNum {
 
 pub mod funcs {
 	use super::*;
+	use crate::value::compare;
+	use std::cmp::Ordering;
 
 	pub fn to_text(int: Gc<BigNum>, args: Args<'_>) -> Result<Value> {
 		args.assert_no_arguments()?;
@@ -70,6 +72,27 @@ pub mod funcs {
 		Ok(int.as_ref()?.to_string().to_value())
 		// Ok((int + args[0].try_downcast::<Integer>()?).to_value())
 	}
+
+	pub fn op_eql(int: Gc<BigNum>, args: Args<'_>) -> Result<Value> {
+		args.assert_no_keyword()?;
+		args.assert_positional_len(1)?;
+
+		let cmp = compare::cmp_numbers(int.to_value(), args[0])?;
+		Ok((cmp == Some(Ordering::Equal)).to_value())
+	}
+
+	pub fn op_cmp(int: Gc<BigNum>, args: Args<'_>) -> Result<Value> {
+		args.assert_no_keyword()?;
+		args.assert_positional_len(1)?;
+
+		Ok(compare::cmp_numbers(int.to_value(), args[0])?.to_value())
+	}
+
+	pub fn hash(int: Gc<BigNum>, args: Args<'_>) -> Result<Value> {
+		args.assert_no_arguments()?;
+
+		Ok(compare::hash_to_value(int.to_value().try_hash()?))
+	}
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
@@ -83,7 +106,10 @@ impl Singleton for BigNumClass {
 
 		*INSTANCE.get_or_init(|| {
 			create_class! { "BigNum", parent Object::instance();
-				Intern::to_text => method funcs::to_text
+				Intern::to_text => method funcs::to_text,
+				Intern::op_eql => method funcs::op_eql,
+				Intern::op_cmp => method funcs::op_cmp,
+				Intern::hash => method funcs::hash,
 			}
 		})
 	}
@@ -9,6 +9,12 @@ impl super::AttrConversionDefined for Boolean {
 	const ATTR_NAME: crate::Intern = crate::Intern::to_bool;
 }
 
+impl crate::value::FromValue for Boolean {
+	fn from_value(value: Value) -> Result<Self> {
+		value.to_boolean()
+	}
+}
+
 impl crate::value::NamedType for Boolean {
 	const TYPENAME: crate::value::Typename = "Boolean";
 }
@@ -166,6 +172,13 @@ pub mod funcs {
 
 		Ok((boolean ^ args[0].try_downcast::<Boolean>()?).to_value())
 	}
+
+	pub fn op_cmp(boolean: bool, args: Args<'_>) -> Result<Value> {
+		args.assert_no_keyword()?;
+		args.assert_positional_len(1)?;
+
+		Ok(args[0].downcast::<Boolean>().map(|rhs| boolean.cmp(&rhs)).to_value())
+	}
 }
 
 impl HasDefaultParent for Boolean {
@@ -180,6 +193,7 @@ impl HasDefaultParent for Boolean {
 				Intern::op_bitand => method funcs::op_bitand,
 				Intern::op_bitor => method funcs::op_bitor,
 				Intern::op_bitxor => method funcs::op_bitxor,
+				Intern::op_cmp => method funcs::op_cmp,
 
 				Intern::and => method funcs::and,
 				Intern::then => method funcs::then,
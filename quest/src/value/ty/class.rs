@@ -1,7 +1,35 @@
-use crate::value::base::{Attribute, Base, Builder as BaseBuilder};
-use crate::value::ty::Pristine;
-use crate::value::{AttributedMut, Gc, HasParents};
-use crate::{Result, Value};
+//! Classes, and the `Class` class that's used to define them from within Quest.
+//!
+//! Classes defined within Quest (either via `Class.new(name, parents, body)` or the equivalent
+//! `class Name(Parent) { body }` syntax) have [`ClassClass`] as an ancestor, which provides their
+//! constructor, `new`. This creates an [`Instance`] whose parent is the class, and then calls its
+//! `init` method (if it has one) with the arguments that were passed to `new`. If there's no
+//! `init`, `new` doesn't accept any arguments.
+//!
+//! # Examples
+//! ```
+//! use quest::value::ty::Text;
+//! use quest::value::Gc;
+//! use quest::{Interpreter, FromValue};
+//!
+//! let result = Interpreter::new().eval(r#"
+//!     class Point {
+//!         init = (self, x, y) -> { self.x = x; self.y = y };
+//!         to_text = self -> { "(" + self.x.to_text() + ", " + self.y.to_text() + ")" };
+//!     };
+//!
+//!     Point.new(1, 2).to_text()
+//! "#)?;
+//!
+//! assert_eq!(Gc::<Text>::from_value(result)?.as_ref()?.as_str(), "(1, 2)");
+//! # quest::Result::Ok(())
+//! ```
+
+use crate::value::base::{Attribute, Base, Builder as BaseBuilder, IntoParent};
+use crate::value::ty::{Instance, List, Pristine, Singleton, Text};
+use crate::value::{Attributed, AttributedMut, Gc, HasParents, NamedType, ToValue};
+use crate::vm::{Args, Block};
+use crate::{ErrorKind, Intern, Result, Value};
 
 quest_type! {
 	#[derive(Debug, NamedType)]
@@ -21,7 +49,7 @@ impl Builder {
 		self.0.set_attr(attr, value)
 	}
 
-	pub fn parent(&mut self, parent: Value) {
+	pub fn parent<P: IntoParent>(&mut self, parent: P) {
 		unsafe { &mut *self.0.as_ptr().as_ptr() }.set_parents(parent);
 	}
 
@@ -48,4 +76,188 @@ impl Class {
 	pub fn name(&self) -> &'static str {
 		self.0.data().name
 	}
+
+	/// Creates a new class named `name`, whose attributes are the named locals of `body` after
+	/// running it.
+	///
+	/// The class inherits from `parents` and [`ClassClass`] (which inherits from [`Object`](super::Object)). If
+	/// none of `parents` already inherit from [`ClassClass`], it's placed right before the first of
+	/// them that it inherits from, so its defaults are used over `Object`'s, but not over any
+	/// other parent's.
+	///
+	/// # Errors
+	/// Returns an error if `parents` don't have a consistent method resolution order, or if running
+	/// `body` fails.
+	pub fn create(
+		name: &'static str,
+		parents: &[Value],
+		body: Option<Gc<Block>>,
+	) -> Result<Gc<Self>> {
+		let class_class = ClassClass::instance();
+		let mut parents = parents.to_vec();
+
+		if !crate::value::mro::linearize_parents(&parents)?
+			.iter()
+			.any(|p| p.is_identical(class_class))
+		{
+			let class_mro = crate::value::mro::linearize(class_class)?;
+			let index = parents
+				.iter()
+				.position(|parent| class_mro.iter().any(|ancestor| ancestor.is_identical(*parent)))
+				.unwrap_or(parents.len());
+
+			parents.insert(index, class_class);
+		}
+
+		// Make sure there's a consistent lookup order before anything's looked up.
+		crate::value::mro::linearize_parents(&parents)?;
+
+		let attrs = if let Some(body) = body {
+			let frame = body.create_frame(Args::default())?;
+			frame.run()?;
+			frame.as_ref()?.named_locals()?
+		} else {
+			Vec::new()
+		};
+
+		let mut builder = Self::builder(name, attrs.len());
+		builder.parent(List::from_slice(&parents));
+
+		for (attr, value) in attrs {
+			builder.set_attr(attr, value)?;
+		}
+
+		Ok(builder.finish())
+	}
+}
+
+/// The class of classes defined within Quest, which is `Class` in the [`Kernel`](super::Kernel).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct ClassClass;
+
+impl Singleton for ClassClass {
+	fn instance() -> Value {
+		use once_cell::sync::OnceCell;
+
+		static INSTANCE: OnceCell<Value> = OnceCell::new();
+
+		*INSTANCE.get_or_init(|| {
+			create_class! { "Class", parent Object::instance();
+				Intern::new_ => function funcs::new,
+				Intern::name => function funcs::name,
+				Intern::to_text => function funcs::to_text,
+				Intern::dbg => function funcs::dbg,
+			}
+		})
+	}
+}
+
+pub mod funcs {
+	use super::*;
+
+	/// Creates a new instance of `class`, calling its `init` (if it has one) with `args`. Classes
+	/// without an `init` don't accept any arguments.
+	///
+	/// When `class` is `Class` itself, this instead creates a new class; `args` are its name, and
+	/// (optionally) its parents and the block defining its body.
+	pub fn new(class: Value, args: Args<'_>) -> Result<Value> {
+		if class.is_identical(ClassClass::instance()) {
+			args.assert_no_keyword()?;
+			args.idx_err_unless(|a| (1..=3).contains(&a.positional().len()))?;
+
+			let name = Intern::new(args[0].try_downcast::<Gc<Text>>()?)?.as_str();
+			let parents = match args.positional().get(1) {
+				Some(parents) => parents.try_downcast::<Gc<List>>()?.as_ref()?.as_slice().to_vec(),
+				None => Vec::new(),
+			};
+			let body =
+				args.positional().get(2).map(|body| body.try_downcast::<Gc<Block>>()).transpose()?;
+
+			return Class::create(name, &parents, body).map(ToValue::to_value);
+		}
+
+		let class = class.try_downcast::<Gc<Class>>()?;
+		let instance = Instance::new(class)?.to_value();
+
+		if instance.has_attr(Intern::init)? {
+			instance.call_attr(Intern::init, args)?;
+		} else {
+			// Without an `init` there's nothing to use the arguments, so they'd silently be ignored.
+			args.assert_no_keyword()?;
+
+			if !args.positional().is_empty() {
+				return Err(
+					ErrorKind::PositionalArgumentMismatch {
+						given: args.positional().len(),
+						expected: 0,
+					}
+					.into(),
+				);
+			}
+		}
+
+		Ok(instance)
+	}
+
+	/// The name of a class, or of the class of an instance.
+	pub fn name(obj: Value, args: Args<'_>) -> Result<Value> {
+		args.assert_no_arguments()?;
+
+		Ok(Text::from_static_str(class_name(obj)?).to_value())
+	}
+
+	/// Classes are converted to their name, and instances to their [`dbg`] representation.
+	pub fn to_text(obj: Value, args: Args<'_>) -> Result<Value> {
+		if let Some(class) = obj.downcast::<Gc<Class>>() {
+			args.assert_no_arguments()?;
+			Ok(Text::from_static_str(class.as_ref()?.name()).to_value())
+		} else {
+			obj.call_attr(Intern::dbg, args)
+		}
+	}
+
+	/// Classes are shown as `<class Name>`, and instances as `Name(attr: value, ...)`.
+	pub fn dbg(obj: Value, args: Args<'_>) -> Result<Value> {
+		args.assert_no_arguments()?;
+
+		let mut builder = Text::simple_builder();
+
+		if let Some(class) = obj.downcast::<Gc<Class>>() {
+			builder.push_str("<class ");
+			builder.push_str(class.as_ref()?.name());
+			builder.push('>');
+			return Ok(builder.finish().to_value());
+		}
+
+		builder.push_str(class_name(obj)?);
+		builder.push('(');
+
+		for (i, (attr, value)) in obj.own_attributes()?.into_iter().enumerate() {
+			if i != 0 {
+				builder.push_str(", ");
+			}
+
+			builder.push_str(attr.to_text()?.as_ref()?.as_str());
+			builder.push_str(": ");
+			builder.push_str(value.dbg_text()?.as_ref()?.as_str());
+		}
+
+		builder.push(')');
+		Ok(builder.finish().to_value())
+	}
+
+	fn class_name(obj: Value) -> Result<&'static str> {
+		if let Some(class) = obj.downcast::<Gc<Class>>() {
+			return Ok(class.as_ref()?.name());
+		}
+
+		if let Some(instance) = obj.downcast::<Gc<Instance>>() {
+			return Ok(instance.as_ref()?.class_name());
+		}
+
+		Err(
+			ErrorKind::InvalidTypeGiven { expected: Gc::<Class>::TYPENAME, given: obj.typename() }
+				.into(),
+		)
+	}
 }
@@ -16,6 +16,16 @@ impl From<Float> for Value<Float> {
 	}
 }
 
+impl super::AttrConversionDefined for Float {
+	const ATTR_NAME: crate::Intern = crate::Intern::to_float;
+}
+
+impl crate::value::FromValue for Float {
+	fn from_value(value: Value) -> Result<Self> {
+		value.convert::<Self>()
+	}
+}
+
 impl crate::value::NamedType for Float {
 	const TYPENAME: crate::value::Typename = "Float";
 }
@@ -49,6 +59,8 @@ impl ConvertTo<Integer> for Float {
 
 pub mod funcs {
 	use super::*;
+	use crate::value::compare;
+	use std::cmp::Ordering;
 
 	pub fn to_float(float: Float, args: Args<'_>) -> Result<Value> {
 		ConvertTo::<Float>::convert(&float, args).map(ToValue::to_value)
@@ -114,42 +126,25 @@ pub mod funcs {
 		Ok(float.powf(args[0].try_downcast::<Float>()?).to_value())
 	}
 
-	pub fn op_lth(float: Float, args: Args<'_>) -> Result<Value> {
-		args.assert_no_keyword()?;
-		args.assert_positional_len(1)?;
-
-		Ok((float < args[0].try_downcast::<Float>()?).to_value())
-	}
-
-	pub fn op_leq(float: Float, args: Args<'_>) -> Result<Value> {
+	pub fn op_eql(float: Float, args: Args<'_>) -> Result<Value> {
 		args.assert_no_keyword()?;
 		args.assert_positional_len(1)?;
 
-		Ok((float <= args[0].try_downcast::<Float>()?).to_value())
+		let cmp = compare::cmp_numbers(float.to_value(), args[0])?;
+		Ok((cmp == Some(Ordering::Equal)).to_value())
 	}
 
-	pub fn op_gth(float: Float, args: Args<'_>) -> Result<Value> {
-		args.assert_no_keyword()?;
-		args.assert_positional_len(1)?;
-
-		Ok((float > args[0].try_downcast::<Float>()?).to_value())
-	}
-
-	pub fn op_geq(float: Float, args: Args<'_>) -> Result<Value> {
+	pub fn op_cmp(float: Float, args: Args<'_>) -> Result<Value> {
 		args.assert_no_keyword()?;
 		args.assert_positional_len(1)?;
 
-		Ok((float >= args[0].try_downcast::<Float>()?).to_value())
+		Ok(compare::cmp_numbers(float.to_value(), args[0])?.to_value())
 	}
 
-	pub fn op_cmp(float: Float, args: Args<'_>) -> Result<Value> {
-		args.assert_no_keyword()?;
-		args.assert_positional_len(1)?;
+	pub fn hash(float: Float, args: Args<'_>) -> Result<Value> {
+		args.assert_no_arguments()?;
 
-		Ok(float
-			.partial_cmp(&args[0].try_downcast::<Float>()?)
-			.map(|x| x.to_value())
-			.unwrap_or_default())
+		Ok(compare::hash_to_value(float.to_value().try_hash()?))
 	}
 
 	pub fn is_zero(float: Float, args: Args<'_>) -> Result<Value> {
@@ -197,11 +192,9 @@ impl Singleton for FloatClass {
 				Intern::op_mod => method funcs::op_mod,
 				Intern::op_pow => method funcs::op_pow,
 
-				Intern::op_lth => method funcs::op_lth,
-				Intern::op_leq => method funcs::op_leq,
-				Intern::op_gth => method funcs::op_gth,
-				Intern::op_geq => method funcs::op_geq,
+				Intern::op_eql => method funcs::op_eql,
 				Intern::op_cmp => method funcs::op_cmp,
+				Intern::hash => method funcs::hash,
 
 				Intern::is_zero => method funcs::is_zero,
 				Intern::is_positive => method funcs::is_positive,
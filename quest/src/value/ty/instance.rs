@@ -0,0 +1,54 @@
+use crate::value::base::Base;
+use crate::value::ty::Class;
+use crate::value::{Gc, HasParents, ToValue, Typename};
+use crate::Result;
+use std::fmt::{self, Debug, Formatter};
+
+quest_type! {
+	/// An instance of a [`Class`] defined within Quest, whose parent is that class.
+	#[derive(NamedType)]
+	pub struct Instance(Inner);
+}
+
+#[derive(Debug)]
+#[doc(hidden)]
+pub struct Inner {
+	class_name: Typename,
+}
+
+impl Instance {
+	/// Creates a new, empty instance of `class`.
+	///
+	/// Note that this doesn't call the class's `init`; use `new` on the class for that.
+	///
+	/// # Errors
+	/// Returns an error if `class` is currently mutably borrowed.
+	pub fn new(class: Gc<Class>) -> Result<Gc<Self>> {
+		let class_name = class.as_ref()?.name();
+
+		Ok(Base::new(Inner { class_name }, class.to_value()))
+	}
+
+	/// Creates a new instance with the same class and parents as `self`, but none of its attributes.
+	#[must_use]
+	pub fn dup(&self) -> Gc<Self> {
+		Base::new(Inner { class_name: self.class_name() }, self.parents())
+	}
+
+	/// Gets the name of the class `self` was created from.
+	#[must_use]
+	pub fn class_name(&self) -> Typename {
+		self.0.data().class_name
+	}
+
+	// Since the class name can't change, it can be read even while `instance` is mutably borrowed.
+	pub(crate) fn class_name_of(instance: Gc<Self>) -> Typename {
+		unsafe { (*instance.as_ptr()).class_name() }
+	}
+}
+
+impl Debug for Instance {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		write!(f, "{}({:p})", self.class_name(), self)
+	}
+}
@@ -1,4 +1,4 @@
-use crate::value::ty::{ConvertTo, Float, InstanceOf, Singleton, Text};
+use crate::value::ty::{ConvertTo, Float, InstanceOf, Range, Singleton, Text};
 use crate::value::{Convertible, Gc};
 use crate::vm::Args;
 use crate::{iterator, ErrorKind, Intern, Result, ToValue, Value};
@@ -73,6 +73,18 @@ impl ToValue for Inner {
 	}
 }
 
+impl crate::value::FromValue for Integer {
+	fn from_value(value: Value) -> Result<Self> {
+		value.try_downcast()
+	}
+}
+
+impl crate::value::FromValue for Inner {
+	fn from_value(value: Value) -> Result<Self> {
+		value.to_integer().map(Integer::get)
+	}
+}
+
 impl Debug for Integer {
 	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
 		Debug::fmt(&self.get(), f)
@@ -155,12 +167,12 @@ impl Integer {
 		Self::ZERO.checked_sub(self)
 	}
 
-	#[quest_fn(name)]
+	/// Adds `rhs` to `self`, returning a `BigNum` if it overflows.
+	#[quest_fn(name = "+")]
 	pub fn op_add(self, rhs: Integer) -> Result<Value> {
 		Ok(self.checked_add(rhs))
 	}
 
-	// #[quest_fn(name = "op_add")]
 	pub fn checked_add(self, rhs: Self) -> Value {
 		if let Some(integer) = self.n.checked_add(rhs.n) {
 			// SAFETY: It's impossible to add even `i64`s and get an odd one.
@@ -272,6 +284,8 @@ impl Integer {
 
 pub mod funcs {
 	use super::*;
+	use crate::value::compare;
+	use std::cmp::Ordering;
 
 	pub fn op_add(int: Integer, args: Args<'_>) -> Result<Value> {
 		args.assert_no_keyword()?;
@@ -280,18 +294,16 @@ pub mod funcs {
 		Ok(int.checked_add(args[0].try_downcast::<Integer>()?))
 	}
 
-	pub fn op_sub(int: Integer, args: Args<'_>) -> Result<Value> {
-		args.assert_no_keyword()?;
-		args.assert_positional_len(1)?;
-
-		Ok(int.checked_sub(args[0].try_downcast::<Integer>()?))
+	/// Subtracts `rhs` from `int`, returning a `BigNum` if it overflows.
+	#[quest_fn(name = "-")]
+	pub fn op_sub(#[this] int: Integer, rhs: Integer) -> Value {
+		int.checked_sub(rhs)
 	}
 
-	pub fn op_mul(int: Integer, args: Args<'_>) -> Result<Value> {
-		args.assert_no_keyword()?;
-		args.assert_positional_len(1)?;
-
-		Ok(int.checked_mul(args[0].try_downcast::<Integer>()?))
+	/// Multiplies `int` by `rhs`, returning a `BigNum` if it overflows.
+	#[quest_fn(name = "*")]
+	pub fn op_mul(#[this] int: Integer, rhs: Integer) -> Value {
+		int.checked_mul(rhs)
 	}
 
 	pub fn op_div(int: Integer, args: Args<'_>) -> Result<Value> {
@@ -320,39 +332,25 @@ pub mod funcs {
 		Ok(int.checked_pow(args[0].try_downcast::<Integer>()?))
 	}
 
-	pub fn op_lth(int: Integer, args: Args<'_>) -> Result<Value> {
-		args.assert_no_keyword()?;
-		args.assert_positional_len(1)?;
-
-		Ok((int < args[0].try_downcast::<Integer>()?).to_value())
-	}
-
-	pub fn op_leq(int: Integer, args: Args<'_>) -> Result<Value> {
-		args.assert_no_keyword()?;
-		args.assert_positional_len(1)?;
-
-		Ok((int <= args[0].try_downcast::<Integer>()?).to_value())
-	}
-
-	pub fn op_gth(int: Integer, args: Args<'_>) -> Result<Value> {
+	pub fn op_eql(int: Integer, args: Args<'_>) -> Result<Value> {
 		args.assert_no_keyword()?;
 		args.assert_positional_len(1)?;
 
-		Ok((int > args[0].try_downcast::<Integer>()?).to_value())
+		let cmp = compare::cmp_numbers(int.to_value(), args[0])?;
+		Ok((cmp == Some(Ordering::Equal)).to_value())
 	}
 
-	pub fn op_geq(int: Integer, args: Args<'_>) -> Result<Value> {
+	pub fn op_cmp(int: Integer, args: Args<'_>) -> Result<Value> {
 		args.assert_no_keyword()?;
 		args.assert_positional_len(1)?;
 
-		Ok((int >= args[0].try_downcast::<Integer>()?).to_value())
+		Ok(compare::cmp_numbers(int.to_value(), args[0])?.to_value())
 	}
 
-	pub fn op_cmp(int: Integer, args: Args<'_>) -> Result<Value> {
-		args.assert_no_keyword()?;
-		args.assert_positional_len(1)?;
+	pub fn hash(int: Integer, args: Args<'_>) -> Result<Value> {
+		args.assert_no_arguments()?;
 
-		Ok(int.cmp(&args[0].try_downcast::<Integer>()?).to_value())
+		Ok(compare::hash_to_value(int.to_value().try_hash()?))
 	}
 
 	pub fn op_neg(int: Integer, args: Args<'_>) -> Result<Value> {
@@ -433,24 +431,13 @@ pub mod funcs {
 	// 	}
 	// }
 
-	// TODO: in the future, return an enumerable
 	pub fn upto(int: Integer, args: Args<'_>) -> Result<Value> {
 		args.assert_no_keyword()?;
 		args.assert_positional_len(1)?;
 
 		let max = args[0].try_downcast::<Integer>()?.get();
-		let mut current = int.get();
 
-		Ok(iterator! { "Integer::upto";
-			if current < max {
-				let tmp = current;
-				current += 1;
-				Ok(tmp.to_value())
-			} else {
-				Err(StopIteration.into())
-			}
-		}
-		.to_value())
+		Ok(Range::new(int.get(), max, false).to_value())
 	}
 
 	pub fn downto(int: Integer, args: Args<'_>) -> Result<Value> {
@@ -458,18 +445,26 @@ pub mod funcs {
 		args.assert_positional_len(1)?;
 
 		let min = args[0].try_downcast::<Integer>()?.get();
-		let mut current = int.get();
 
-		Ok(iterator! { "Integer::downto";
-			if min < current {
-				let tmp = current;
-				current -= 1;
-				Ok(tmp.to_value())
-			} else {
-				Err(StopIteration.into())
-			}
-		}
-		.to_value())
+		Ok(Range::with_step(int.get(), min, -1, false)?.to_value())
+	}
+
+	pub fn op_range(int: Integer, args: Args<'_>) -> Result<Value> {
+		args.assert_no_keyword()?;
+		args.assert_positional_len(1)?;
+
+		let end = args[0].try_downcast::<Integer>()?.get();
+
+		Ok(Range::new(int.get(), end, false).to_value())
+	}
+
+	pub fn op_range_incl(int: Integer, args: Args<'_>) -> Result<Value> {
+		args.assert_no_keyword()?;
+		args.assert_positional_len(1)?;
+
+		let end = args[0].try_downcast::<Integer>()?.get();
+
+		Ok(Range::new(int.get(), end, true).to_value())
 	}
 
 	pub fn times(int: Integer, args: Args<'_>) -> Result<Value> {
@@ -552,19 +547,18 @@ impl Singleton for IntegerClass {
 		*INSTANCE.get_or_init(|| {
 			create_class! { "Integer", parent Object::instance();
 				Intern::op_neg => method funcs::op_neg,
-				Intern::op_add => function Integer::qs_op_add,
-				// Intern::op_add => method funcs::op_add,
-				Intern::op_sub => method funcs::op_sub,
-				Intern::op_mul => method funcs::op_mul,
+				Intern::op_add => constant Integer::qs_op_add.to_value(),
+				Intern::op_sub => constant funcs::qs_op_sub.to_value(),
+				Intern::op_mul => constant funcs::qs_op_mul.to_value(),
 				Intern::op_div => method funcs::op_div,
 				Intern::op_mod => method funcs::op_mod,
 				Intern::op_pow => method funcs::op_pow,
 
-				Intern::op_lth => method funcs::op_lth,
-				Intern::op_leq => method funcs::op_leq,
-				Intern::op_gth => method funcs::op_gth,
-				Intern::op_geq => method funcs::op_geq,
+				Intern::op_eql => method funcs::op_eql,
 				Intern::op_cmp => method funcs::op_cmp,
+				Intern::hash => method funcs::hash,
+				Intern::op_range => method funcs::op_range,
+				Intern::op_range_incl => method funcs::op_range_incl,
 
 				Intern::op_shl => method funcs::op_shl,
 				Intern::op_shr => method funcs::op_shr,
@@ -1,5 +1,5 @@
-use crate::value::ty::List;
-use crate::value::{Callable, ToValue};
+use crate::value::ty::{Integer, Iterator, List, Map, Set};
+use crate::value::{Attributed, Callable, Gc, ToValue};
 use crate::vm::Args;
 use crate::{iterator, ErrorKind, Intern, Result, Value};
 
@@ -10,10 +10,34 @@ impl crate::value::NamedType for Iterable {
 	const TYPENAME: crate::value::Typename = "Iterable";
 }
 
+// Values which don't define `next` themselves (such as `List`s) are iterated over via their `iter`.
+fn to_iter(iterable: Value) -> Result<Value> {
+	if iterable.has_attr(Intern::next)? {
+		Ok(iterable)
+	} else {
+		iterable.call_attr(Intern::iter, Args::default())
+	}
+}
+
 fn next(iter: Value) -> Result<Value> {
 	iter.call_attr(Intern::next, Args::default())
 }
 
+// Like `next`, except `StopIteration` is turned into `None`.
+fn try_next(iter: Value) -> Result<Option<Value>> {
+	match next(iter) {
+		Ok(value) => Ok(Some(value)),
+		Err(err) if matches!(err.kind, ErrorKind::StopIteration) => Ok(None),
+		Err(err) => Err(err),
+	}
+}
+
+fn non_negative(amount: Value) -> Result<usize> {
+	let amount = amount.try_downcast::<Integer>()?.get();
+
+	usize::try_from(amount).map_err(|_| format!("amount cannot be negative, not {amount}").into())
+}
+
 macro_rules! for_each {
 	($var:pat in $iterable:ident $body:block) => {
 		loop {
@@ -48,6 +72,7 @@ pub mod funcs {
 		args.assert_no_keyword()?;
 		args.assert_positional_len(1)?;
 
+		let iterable = to_iter(iterable)?;
 		let map_function = args[0];
 
 		Ok(iterator! { "Iterable::max";
@@ -60,6 +85,7 @@ pub mod funcs {
 		args.assert_no_keyword()?;
 		args.assert_positional_len(1)?;
 
+		let iterable = to_iter(iterable)?;
 		let filter_function = args[0];
 
 		Ok(iterator! { "Iterable::filter";
@@ -78,6 +104,7 @@ pub mod funcs {
 		args.assert_no_keyword()?;
 		args.idx_err_unless(|x| x.len() == 1 || x.len() == 2)?;
 
+		let iterable = to_iter(iterable)?;
 		let (init, func) = match args.len() {
 			1 => (None, args[0]),
 			2 => (Some(args[0]), args[1]),
@@ -91,6 +118,7 @@ pub mod funcs {
 		args.assert_no_keyword()?;
 		args.idx_err_unless(|x| x.len() <= 1)?;
 
+		let iterable = to_iter(iterable)?;
 		reduce_down(iterable, args.get(0), |current, next| {
 			current.call_attr(Intern::op_add, Args::new(&[next], &[]))
 		})
@@ -100,6 +128,7 @@ pub mod funcs {
 		args.assert_no_keyword()?;
 		args.idx_err_unless(|x| x.len() <= 1)?;
 
+		let iterable = to_iter(iterable)?;
 		reduce_down(iterable, args.get(0), |current, next| {
 			current.call_attr(Intern::op_mul, Args::new(&[next], &[]))
 		})
@@ -108,6 +137,7 @@ pub mod funcs {
 	pub fn to_list(iterable: Value, args: Args<'_>) -> Result<Value> {
 		args.assert_no_arguments()?;
 
+		let iterable = to_iter(iterable)?;
 		let mut list = List::simple_builder();
 
 		for_each!(value in iterable {
@@ -117,10 +147,24 @@ pub mod funcs {
 		Ok(list.to_value())
 	}
 
+	pub fn to_set(iterable: Value, args: Args<'_>) -> Result<Value> {
+		args.assert_no_arguments()?;
+
+		let iterable = to_iter(iterable)?;
+		let set = Set::new();
+
+		for_each!(value in iterable {
+			set.as_mut()?.insert(value)?;
+		});
+
+		Ok(set.to_value())
+	}
+
 	pub fn each(iterable: Value, args: Args<'_>) -> Result<Value> {
 		args.assert_no_keyword()?;
 		args.assert_positional_len(1)?;
 
+		let iterable = to_iter(iterable)?;
 		let func = args[0];
 
 		for_each!(value in iterable {
@@ -134,6 +178,7 @@ pub mod funcs {
 		args.assert_no_keyword()?;
 		args.assert_positional_len(1)?;
 
+		let iterable = to_iter(iterable)?;
 		let func = args[0];
 
 		Ok(iterator! { "Iterable::tap_each";
@@ -147,6 +192,7 @@ pub mod funcs {
 	pub fn count(iterable: Value, args: Args<'_>) -> Result<Value> {
 		args.assert_no_arguments()?;
 
+		let iterable = to_iter(iterable)?;
 		let mut count = 0;
 
 		for_each!(_ in iterable {
@@ -160,6 +206,7 @@ pub mod funcs {
 		args.assert_no_keyword()?;
 		args.assert_positional_len(1)?;
 
+		let iterable = to_iter(iterable)?;
 		let condition = args[0];
 
 		for_each!(value in iterable {
@@ -175,6 +222,7 @@ pub mod funcs {
 		args.assert_no_keyword()?;
 		args.assert_positional_len(1)?;
 
+		let iterable = to_iter(iterable)?;
 		let condition = args[0];
 
 		for_each!(value in iterable {
@@ -185,6 +233,344 @@ pub mod funcs {
 
 		Ok(true.to_value())
 	}
+
+	pub fn take(iterable: Value, args: Args<'_>) -> Result<Value> {
+		args.assert_no_keyword()?;
+		args.assert_positional_len(1)?;
+
+		let iterable = to_iter(iterable)?;
+		let mut remaining = non_negative(args[0])?;
+
+		Ok(iterator! { "Iterable::take";
+			if remaining == 0 {
+				return Err(StopIteration.into());
+			}
+
+			remaining -= 1;
+			next(iterable)
+		}
+		.to_value())
+	}
+
+	pub fn skip(iterable: Value, args: Args<'_>) -> Result<Value> {
+		args.assert_no_keyword()?;
+		args.assert_positional_len(1)?;
+
+		let iterable = to_iter(iterable)?;
+		let mut to_skip = non_negative(args[0])?;
+
+		Ok(iterator! { "Iterable::skip";
+			while to_skip != 0 {
+				to_skip -= 1;
+				next(iterable)?;
+			}
+
+			next(iterable)
+		}
+		.to_value())
+	}
+
+	pub fn take_while(iterable: Value, args: Args<'_>) -> Result<Value> {
+		args.assert_no_keyword()?;
+		args.assert_positional_len(1)?;
+
+		let iterable = to_iter(iterable)?;
+		let condition = args[0];
+		let mut is_done = false;
+
+		Ok(iterator! { "Iterable::take_while";
+			if is_done {
+				return Err(StopIteration.into());
+			}
+
+			let value = next(iterable)?;
+
+			if condition.call(Args::new(&[value], &[]))?.is_truthy() {
+				Ok(value)
+			} else {
+				is_done = true;
+				Err(StopIteration.into())
+			}
+		}
+		.to_value())
+	}
+
+	pub fn skip_while(iterable: Value, args: Args<'_>) -> Result<Value> {
+		args.assert_no_keyword()?;
+		args.assert_positional_len(1)?;
+
+		let iterable = to_iter(iterable)?;
+		let condition = args[0];
+		let mut is_skipping = true;
+
+		Ok(iterator! { "Iterable::skip_while";
+			loop {
+				let value = next(iterable)?;
+
+				if !is_skipping || !condition.call(Args::new(&[value], &[]))?.is_truthy() {
+					is_skipping = false;
+					return Ok(value);
+				}
+			}
+		}
+		.to_value())
+	}
+
+	pub fn enumerate(iterable: Value, args: Args<'_>) -> Result<Value> {
+		args.assert_no_keyword()?;
+		args.idx_err_unless(|x| x.len() <= 1)?;
+
+		let iterable = to_iter(iterable)?;
+		let start = args.get(0).map(|start| start.try_downcast::<Integer>()).transpose()?;
+		let mut index = start.map_or(0, |start| start.get());
+
+		Ok(iterator! { "Iterable::enumerate";
+			let value = next(iterable)?;
+			let pair = List::from_slice(&[index.to_value(), value]);
+			index += 1;
+			Ok(pair.to_value())
+		}
+		.to_value())
+	}
+
+	pub fn zip(iterable: Value, args: Args<'_>) -> Result<Value> {
+		args.assert_no_keyword()?;
+
+		let mut iterables = vec![to_iter(iterable)?];
+		for &other in args.positional() {
+			iterables.push(to_iter(other)?);
+		}
+
+		Ok(iterator! { "Iterable::zip";
+			let mut row = List::with_capacity(iterables.len());
+
+			for &iterable in &iterables {
+				row.push(next(iterable)?);
+			}
+
+			Ok(row.to_value())
+		}
+		.to_value())
+	}
+
+	pub fn chain(iterable: Value, args: Args<'_>) -> Result<Value> {
+		args.assert_no_keyword()?;
+
+		let mut iterables = vec![to_iter(iterable)?];
+		for &other in args.positional() {
+			iterables.push(to_iter(other)?);
+		}
+
+		// Iterate in reverse, so we can `pop` off exhausted iterables.
+		iterables.reverse();
+
+		Ok(iterator! { "Iterable::chain";
+			while let Some(&iterable) = iterables.last() {
+				if let Some(value) = try_next(iterable)? {
+					return Ok(value);
+				}
+
+				iterables.pop();
+			}
+
+			Err(StopIteration.into())
+		}
+		.to_value())
+	}
+
+	pub fn flat_map(iterable: Value, args: Args<'_>) -> Result<Value> {
+		args.assert_no_keyword()?;
+		args.assert_positional_len(1)?;
+
+		let iterable = to_iter(iterable)?;
+		let map_function = args[0];
+		let mut current = None;
+
+		Ok(iterator! { "Iterable::flat_map";
+			loop {
+				if let Some(inner) = current {
+					if let Some(value) = try_next(inner)? {
+						return Ok(value);
+					}
+				}
+
+				current = Some(to_iter(map_function.call(Args::new(&[next(iterable)?], &[]))?)?);
+			}
+		}
+		.to_value())
+	}
+
+	pub fn step_by(iterable: Value, args: Args<'_>) -> Result<Value> {
+		args.assert_no_keyword()?;
+		args.assert_positional_len(1)?;
+
+		let iterable = to_iter(iterable)?;
+		let step = non_negative(args[0])?;
+		let mut is_first = true;
+
+		if step == 0 {
+			return Err("step cannot be zero".to_string().into());
+		}
+
+		Ok(iterator! { "Iterable::step_by";
+			if !is_first {
+				for _ in 1..step {
+					next(iterable)?;
+				}
+			}
+
+			is_first = false;
+			next(iterable)
+		}
+		.to_value())
+	}
+
+	pub fn peekable(iterable: Value, args: Args<'_>) -> Result<Value> {
+		args.assert_no_arguments()?;
+
+		let iterable = to_iter(iterable)?;
+
+		// Native iterators already support `peek`, so only foreign ones need to be wrapped.
+		if iterable.is_a::<Gc<Iterator>>() {
+			return Ok(iterable);
+		}
+
+		Ok(iterator! { "Iterable::peekable"; next(iterable) }.to_value())
+	}
+
+	pub fn cycle(iterable: Value, args: Args<'_>) -> Result<Value> {
+		args.assert_no_arguments()?;
+
+		let iterable = to_iter(iterable)?;
+		let mut seen = Vec::new();
+		let mut is_exhausted = false;
+		let mut index = 0;
+
+		Ok(iterator! { "Iterable::cycle";
+			if !is_exhausted {
+				if let Some(value) = try_next(iterable)? {
+					seen.push(value);
+					return Ok(value);
+				}
+
+				is_exhausted = true;
+			}
+
+			if seen.is_empty() {
+				return Err(StopIteration.into());
+			}
+
+			let value = seen[index % seen.len()];
+			index += 1;
+			Ok(value)
+		}
+		.to_value())
+	}
+
+	pub fn group_by(iterable: Value, args: Args<'_>) -> Result<Value> {
+		args.assert_no_keyword()?;
+		args.assert_positional_len(1)?;
+
+		let iterable = to_iter(iterable)?;
+		let key_function = args[0];
+		let groups = Map::new();
+
+		for_each!(value in iterable {
+			let key = key_function.call(Args::new(&[value], &[]))?;
+			let group = groups.as_ref()?.get(key)?;
+
+			if let Some(group) = group {
+				group.try_downcast::<Gc<List>>()?.as_mut()?.push(value);
+			} else {
+				groups.as_mut()?.insert(key, List::from_slice(&[value]).to_value())?;
+			}
+		});
+
+		Ok(groups.to_value())
+	}
+
+	pub fn first(iterable: Value, args: Args<'_>) -> Result<Value> {
+		args.assert_no_arguments()?;
+
+		Ok(try_next(to_iter(iterable)?)?.unwrap_or_default())
+	}
+
+	pub fn last(iterable: Value, args: Args<'_>) -> Result<Value> {
+		args.assert_no_arguments()?;
+
+		let iterable = to_iter(iterable)?;
+		let mut last = Value::default();
+
+		for_each!(value in iterable {
+			last = value;
+		});
+
+		Ok(last)
+	}
+
+	pub fn find(iterable: Value, args: Args<'_>) -> Result<Value> {
+		args.assert_no_keyword()?;
+		args.assert_positional_len(1)?;
+
+		let iterable = to_iter(iterable)?;
+		let condition = args[0];
+
+		for_each!(value in iterable {
+			if condition.call(Args::new(&[value], &[]))?.is_truthy() {
+				return Ok(value);
+			}
+		});
+
+		Ok(Value::default())
+	}
+
+	fn extremum_by(iterable: Value, args: Args<'_>, wanted: std::cmp::Ordering) -> Result<Value> {
+		args.assert_no_keyword()?;
+		args.assert_positional_len(1)?;
+
+		let iterable = to_iter(iterable)?;
+		let key_function = args[0];
+		let mut best: Option<(Value, Value)> = None;
+
+		for_each!(value in iterable {
+			let key = key_function.call(Args::new(&[value], &[]))?;
+
+			match best {
+				Some((best_key, _)) if key.try_cmp(best_key)? != wanted => {},
+				_ => best = Some((key, value)),
+			}
+		});
+
+		Ok(best.map(|(_, value)| value).unwrap_or_default())
+	}
+
+	pub fn min_by(iterable: Value, args: Args<'_>) -> Result<Value> {
+		extremum_by(iterable, args, std::cmp::Ordering::Less)
+	}
+
+	pub fn max_by(iterable: Value, args: Args<'_>) -> Result<Value> {
+		extremum_by(iterable, args, std::cmp::Ordering::Greater)
+	}
+
+	pub fn partition(iterable: Value, args: Args<'_>) -> Result<Value> {
+		args.assert_no_keyword()?;
+		args.assert_positional_len(1)?;
+
+		let iterable = to_iter(iterable)?;
+		let condition = args[0];
+		let mut matching = List::simple_builder();
+		let mut rest = List::simple_builder();
+
+		for_each!(value in iterable {
+			if condition.call(Args::new(&[value], &[]))?.is_truthy() {
+				matching.push(value);
+			} else {
+				rest.push(value);
+			}
+		});
+
+		Ok(List::from_slice(&[matching.to_value(), rest.to_value()]).to_value())
+	}
 }
 
 impl Iterable {
@@ -201,12 +587,31 @@ impl Iterable {
 				Intern::reduce => function funcs::reduce,
 				Intern::each => function funcs::each,
 				Intern::to_list => function funcs::to_list,
+				Intern::to_set => function funcs::to_set,
 				Intern::tap_each => function funcs::tap_each,
 				Intern::product => function funcs::product,
 				Intern::sum => function funcs::sum,
 				Intern::count => function funcs::count,
 				Intern::is_any => function funcs::is_any,
 				Intern::are_all => function funcs::are_all,
+				Intern::take => function funcs::take,
+				Intern::skip => function funcs::skip,
+				Intern::take_while => function funcs::take_while,
+				Intern::skip_while => function funcs::skip_while,
+				Intern::enumerate => function funcs::enumerate,
+				Intern::zip => function funcs::zip,
+				Intern::chain => function funcs::chain,
+				Intern::flat_map => function funcs::flat_map,
+				Intern::step_by => function funcs::step_by,
+				Intern::peekable => function funcs::peekable,
+				Intern::cycle => function funcs::cycle,
+				Intern::group_by => function funcs::group_by,
+				Intern::first => function funcs::first,
+				Intern::last => function funcs::last,
+				Intern::find => function funcs::find,
+				Intern::min_by => function funcs::min_by,
+				Intern::max_by => function funcs::max_by,
+				Intern::partition => function funcs::partition,
 			}
 		})
 	}
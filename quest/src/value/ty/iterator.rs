@@ -28,6 +28,7 @@ quest_type! {
 pub struct Inner {
 	name: &'static str,
 	function: Box<dyn FnMut(Args<'_>) -> Result<Value>>,
+	peeked: Option<Value>,
 }
 
 impl Debug for Iterator {
@@ -48,7 +49,7 @@ impl Iterator {
 	) -> Gc<Self> {
 		use crate::value::base::{Base, HasDefaultParent};
 
-		Base::new(Inner { name, function: Box::new(function) }, Gc::<Self>::parent())
+		Base::new(Inner { name, function: Box::new(function), peeked: None }, Gc::<Self>::parent())
 	}
 
 	pub fn empty(name: &'static str) -> Gc<Self> {
@@ -56,7 +57,27 @@ impl Iterator {
 	}
 
 	pub fn next(&mut self, args: Args<'_>) -> Result<Value> {
-		(self.0.data_mut().function)(args)
+		let inner = self.0.data_mut();
+
+		match inner.peeked.take() {
+			Some(peeked) => Ok(peeked),
+			None => (inner.function)(args),
+		}
+	}
+
+	/// Gets the next value without advancing `self`, returning `None` if `self` is exhausted.
+	pub fn peek(&mut self) -> Result<Option<Value>> {
+		let inner = self.0.data_mut();
+
+		if inner.peeked.is_none() {
+			match (inner.function)(Args::default()) {
+				Ok(value) => inner.peeked = Some(value),
+				Err(err) if matches!(err.kind, crate::ErrorKind::StopIteration) => return Ok(None),
+				Err(err) => return Err(err),
+			}
+		}
+
+		Ok(inner.peeked)
 	}
 }
 
@@ -72,6 +93,7 @@ impl Singleton for IteratorClass {
 		*INSTANCE.get_or_init(|| {
 			create_class! { "Iterator", parent Iterable::instance();
 				Intern::next => method funcs::next,
+				Intern::peek => method funcs::peek,
 				Intern::dbg => method funcs::dbg,
 			}
 		})
@@ -89,6 +111,12 @@ pub mod funcs {
 		obj.as_mut()?.next(args)
 	}
 
+	pub fn peek(obj: Gc<Iterator>, args: Args<'_>) -> Result<Value> {
+		args.assert_no_arguments()?;
+
+		Ok(obj.as_mut()?.peek()?.unwrap_or_default())
+	}
+
 	pub fn dbg(iterator: Gc<Iterator>, args: Args<'_>) -> Result<Value> {
 		args.assert_no_arguments()?;
 
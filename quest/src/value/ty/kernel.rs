@@ -1,11 +1,74 @@
-use crate::value::ty::{self, Singleton};
-use crate::value::{Callable, Gc, HasDefaultParent, HasParents};
+use crate::value::ty::{self, RustFn, Singleton};
+use crate::value::{AttributedMut, Callable, Gc, HasDefaultParent, HasParents, ToValue};
 use crate::vm::Args;
-use crate::{Result, Value};
+use crate::{Intern, Result, Value};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
 pub struct Kernel;
 
+/// Which of the [`Kernel`]'s functions that interact with the outside world are available.
+///
+/// Used with [`Kernel::with_capabilities`] to create kernels for sandboxed scripts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Capabilities {
+	/// Whether `exit` and `abort` are available.
+	pub exit: bool,
+	/// Whether `spawn`, as well as the `Thread`, `Channel`, `Mutex` and `Condvar` classes, are
+	/// available.
+	pub threads: bool,
+	/// Whether `await` and `sleep`, as well as the `Task` class, are available.
+	pub tasks: bool,
+	/// Whether `print` and `dump` (and any other IO) are available.
+	pub io: bool,
+	/// Whether `breakpoint` is available.
+	pub debugger: bool,
+}
+
+impl Capabilities {
+	/// Every capability is available; this is what [`Kernel::instance`] has.
+	pub const ALL: Self = Self { exit: true, threads: true, tasks: true, io: true, debugger: true };
+
+	/// No capabilities are available; scripts can only compute values.
+	pub const NONE: Self =
+		Self { exit: false, threads: false, tasks: false, io: false, debugger: false };
+
+	fn functions(self) -> Vec<(Intern, RustFn)> {
+		let mut functions = Vec::new();
+
+		if self.exit {
+			functions.push((Intern::exit, RustFn_new!(Intern::exit, justargs funcs::exit)));
+			functions.push((Intern::abort, RustFn_new!(Intern::abort, justargs funcs::abort)));
+		}
+
+		if self.threads {
+			functions.push((Intern::spawn, RustFn_new!(Intern::spawn, justargs funcs::spawn)));
+		}
+
+		if self.tasks {
+			functions.push((Intern::r#await, RustFn_new!(Intern::r#await, justargs funcs::r#await)));
+			functions.push((Intern::sleep, RustFn_new!(Intern::sleep, justargs funcs::sleep)));
+		}
+
+		if self.io {
+			functions.push((Intern::print, RustFn_new!(Intern::print, justargs funcs::print)));
+			functions.push((Intern::dump, RustFn_new!(Intern::dump, justargs funcs::dump)));
+		}
+
+		if self.debugger {
+			let breakpoint = RustFn_new!(Intern::breakpoint, justargs funcs::breakpoint);
+			functions.push((Intern::breakpoint, breakpoint));
+		}
+
+		functions
+	}
+}
+
+impl Default for Capabilities {
+	fn default() -> Self {
+		Self::ALL
+	}
+}
+
 impl crate::value::NamedType for Kernel {
 	const TYPENAME: crate::value::Typename = "Kernel";
 }
@@ -17,40 +80,62 @@ impl Kernel {
 
 		static INSTANCE: OnceCell<crate::Value> = OnceCell::new();
 
-		*INSTANCE.get_or_init(|| {
-			create_class! { "Kernel", parent Pristine::instance();
-				Intern::print => justargs funcs::print,
-				Intern::dump => justargs funcs::dump,
-				Intern::exit => justargs funcs::exit,
-				Intern::abort => justargs funcs::abort,
-				Intern::object => justargs funcs::object,
-				Intern::r#if => justargs funcs::r#if,
-				Intern::ifl => justargs funcs::ifl,
-				Intern::if_cascade => justargs funcs::if_cascade,
-				Intern::r#while => justargs funcs::r#while,
-				Intern::Integer => constant ty::Integer::parent(),
-				Intern::Float => constant ty::Float::parent(),
-				Intern::Boolean => constant ty::Boolean::parent(),
-				Intern::Text => constant Gc::<ty::Text>::parent(),
-				Intern::BoundFn => constant Gc::<ty::BoundFn>::parent(),
-				Intern::Callable => constant Gc::<ty::Callable>::parent(),
-				// Intern::Class => constant ty::Class::parent(),
-				Intern::List => constant ty::List::parent(),
-				Intern::Null => constant ty::Null::parent(),
-				Intern::RustFn => constant ty::RustFn::parent(),
-				Intern::Scope => constant Gc::<ty::Scope>::parent(),
-				Intern::Object => constant ty::Object::instance(),
-				// Intern::Frame => constant Gc::<crate::vm::Frame>::parent(),
-				Intern::Block => constant Gc::<crate::vm::Block>::parent(),
-				Intern::List => constant ty::List::parent(),
-				// TODO: Other types
-				Intern::r#true => constant true.to_value(),
-				Intern::r#false => constant false.to_value(),
-				Intern::r#null => constant ty::Null.to_value(),
-
-				Intern::spawn => justargs funcs::spawn,
+		*INSTANCE.get_or_init(|| Self::with_capabilities(Capabilities::ALL))
+	}
+
+	/// Creates a new kernel which only has the functions that `capabilities` allows.
+	///
+	/// To have scripts use it instead of [`Kernel::instance`], [set it as the root](
+	/// crate::vm::frame::set_root).
+	#[must_use]
+	pub fn with_capabilities(capabilities: Capabilities) -> Value {
+		let mut kernel = create_class! { "Kernel", parent Pristine::instance();
+			Intern::object => justargs funcs::object,
+			Intern::r#if => justargs funcs::r#if,
+			Intern::ifl => justargs funcs::ifl,
+			Intern::if_cascade => justargs funcs::if_cascade,
+			Intern::r#while => justargs funcs::r#while,
+			Intern::r#yield => justargs funcs::r#yield,
+			Intern::Integer => constant ty::Integer::parent(),
+			Intern::Float => constant ty::Float::parent(),
+			Intern::Boolean => constant ty::Boolean::parent(),
+			Intern::Text => constant Gc::<ty::Text>::parent(),
+			Intern::BoundFn => constant Gc::<ty::BoundFn>::parent(),
+			Intern::Callable => constant Gc::<ty::Callable>::parent(),
+			Intern::Class => constant ty::class::ClassClass::instance(),
+			Intern::List => constant ty::List::parent(),
+			Intern::Map => constant ty::Map::parent(),
+			Intern::Set => constant ty::Set::parent(),
+			Intern::Range => constant ty::Range::parent(),
+			Intern::Null => constant ty::Null::parent(),
+			Intern::RustFn => constant ty::RustFn::parent(),
+			Intern::Scope => constant Gc::<ty::Scope>::parent(),
+			Intern::Object => constant ty::Object::instance(),
+			// Intern::Frame => constant Gc::<crate::vm::Frame>::parent(),
+			Intern::Block => constant Gc::<crate::vm::Block>::parent(),
+			Intern::List => constant ty::List::parent(),
+			// TODO: Other types
+			Intern::r#true => constant true.to_value(),
+			Intern::r#false => constant false.to_value(),
+			Intern::r#null => constant ty::Null.to_value(),
+		};
+
+		for (attr, function) in capabilities.functions() {
+			kernel.set_attr(attr, function.to_value()).expect("unable to add kernel function");
+		}
+
+		if capabilities.threads {
+			for (attr, class) in ty::thread::classes().expect("unable to create thread classes") {
+				kernel.set_attr(attr, class).expect("unable to add thread class");
 			}
-		})
+		}
+
+		if capabilities.tasks {
+			let task = ty::native::Native::<ty::task::Task>::class().expect("unable to create Task");
+			kernel.set_attr(Intern::Task, task).expect("unable to add task class");
+		}
+
+		kernel
 	}
 }
 
@@ -79,19 +164,24 @@ pub mod funcs {
 		Ok(args[0])
 	}
 
+	/// Stops execution with the exit status `args[0]`.
+	///
+	/// This doesn't exit the process itself; instead, it unwinds with [`ErrorKind::Exit`](
+	/// crate::ErrorKind::Exit), which the host can handle however it likes.
 	pub fn exit(args: Args<'_>) -> Result<Value> {
 		args.assert_no_keyword()?;
 		args.assert_positional_len(1)?;
 
-		std::process::exit(args[0].to_integer()?.get() as i32);
+		Err(crate::ErrorKind::Exit(args[0].to_integer()?.get() as i32).into())
 	}
 
+	/// Prints `args[0]` to stderr, then [exits](exit) with status `1`.
 	pub fn abort(args: Args<'_>) -> Result<Value> {
 		args.assert_no_keyword()?;
 		args.assert_positional_len(1)?;
 
 		eprintln!("{}", *args[0].to_text()?.as_ref()?);
-		std::process::exit(1);
+		Err(crate::ErrorKind::Exit(1).into())
 	}
 
 	pub fn r#if(args: Args<'_>) -> Result<Value> {
@@ -148,6 +238,31 @@ pub mod funcs {
 		Ok(last)
 	}
 
+	/// Suspends the current generator, handing `args[0]` (or `null`) to whoever resumed it.
+	///
+	/// This can be called from anywhere within the generator, including from nested blocks.
+	pub fn r#yield(args: Args<'_>) -> Result<Value> {
+		use crate::vm::coroutine::{self, Kind};
+
+		args.assert_no_keyword()?;
+		args.idx_err_unless(|args| args.len() <= 1)?;
+
+		if coroutine::current() != Some(Kind::Generator) {
+			return Err("`yield` can only be used within a generator".to_string().into());
+		}
+
+		Ok(coroutine::suspend(args.get(0).unwrap_or_default()))
+	}
+
+	/// Pauses execution within the debugger, or does nothing if no debugger is attached.
+	pub fn breakpoint(args: Args<'_>) -> Result<Value> {
+		args.assert_no_arguments()?;
+
+		crate::vm::debugger::breakpoint()?;
+
+		Ok(Value::default())
+	}
+
 	pub fn object(args: Args<'_>) -> Result<Value> {
 		use crate::value::ty::{List, Object, Wrap};
 		use crate::vm::Block;
@@ -163,7 +278,11 @@ pub mod funcs {
 
 		let frame = body.try_downcast::<Gc<Block>>()?.create_frame(Args::default())?;
 		if args.len() == 2 {
-			frame.as_mut().unwrap().set_parents(args[0].try_downcast::<Gc<List>>()?);
+			let parents = args[0].try_downcast::<Gc<List>>()?;
+
+			// Make sure there's a consistent lookup order before anything's looked up.
+			crate::value::mro::linearize_parents(parents.as_ref()?.as_slice())?;
+			frame.as_mut().unwrap().set_parents(parents);
 		}
 
 		frame.run()?;
@@ -171,54 +290,34 @@ pub mod funcs {
 		Ok(frame.to_value())
 	}
 
-	// this isn't the actual interface, im just curious how threads will work out
+	/// Calls the function with the remaining arguments on a new thread, returning a `Thread`; the
+	/// same as `Thread.spawn`.
 	pub fn spawn(args: Args<'_>) -> Result<Value> {
-		use crate::value::base::Base;
-		use crate::value::ty::InstanceOf;
-		use std::thread::{self, JoinHandle};
+		use crate::value::ty::native::Native;
+		use crate::value::ty::thread::Thread;
 
-		quest_type! {
-			#[derive(NamedType)]
-			pub struct Thread(Option<JoinHandle<Result<Value>>>);
-		}
+		args.assert_no_keyword()?;
+		args.idx_err_unless(|args| !args.positional().is_empty())?;
 
-		#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
-		pub struct ThreadClass;
-		unsafe impl crate::value::base::HasTypeFlag for Thread {
-			const TYPE_FLAG: crate::value::base::TypeFlag = crate::value::base::TypeFlag::ThreadClass;
-		}
+		Ok(Native::new(Thread::spawn(args[0], args.positional()[1..].to_vec()))?.to_value())
+	}
 
-		impl Singleton for ThreadClass {
-			fn instance() -> crate::Value {
-				use once_cell::sync::OnceCell;
-
-				static INSTANCE: OnceCell<crate::Value> = OnceCell::new();
-
-				*INSTANCE.get_or_init(|| {
-					create_class! { "Thread", parent Object::instance();
-						Intern::join => method |thread: Gc<Thread>, args: Args<'_>| -> Result<Value> {
-							args.assert_no_arguments()?;
-
-							if let Some(thread) = thread.as_mut()?.0.data_mut().take() {
-								thread.join().expect("couldnt join")
-							} else {
-								Err("unable to join an already join thread".to_string().into())
-							}
-						},
-					}
-				})
-			}
-		}
+	/// Waits for `args[0]` to finish if it's a `Task`, returning its result; see
+	/// [`await_value`](ty::task::await_value).
+	pub fn r#await(args: Args<'_>) -> Result<Value> {
+		args.assert_no_keyword()?;
+		args.assert_positional_len(1)?;
 
-		impl InstanceOf for Gc<Thread> {
-			type Parent = ThreadClass;
-		}
+		ty::task::await_value(args[0])
+	}
+
+	/// Waits for `args[0]` seconds. Within a task, other tasks run in the meantime.
+	pub fn sleep(args: Args<'_>) -> Result<Value> {
+		use crate::value::ty::task::{await_value, duration, Task};
 
 		args.assert_no_keyword()?;
 		args.assert_positional_len(1)?;
-		let func = args[0];
 
-		let thread = thread::spawn(move || func.call(Args::default()));
-		Ok(Base::<Thread>::new(Some(thread), Gc::<Thread>::parent()).to_value())
+		await_value(Task::sleep(duration(args[0])?)?.to_value())
 	}
 }
@@ -241,6 +241,7 @@ impl List {
 			self.flags().insert_user(FLAG_SHARED);
 
 			let mut builder = InternalBuilder::allocate();
+			builder.insert_flags(FLAG_SHARED);
 			let builder_ptr = builder.inner_mut() as *mut Inner;
 			builder_ptr.copy_from_nonoverlapping(self.inner() as *const Inner, 1);
 			builder.finish()
@@ -290,8 +291,9 @@ impl List {
 		if self.is_pointer_immutable() {
 			// Both static Rust strings (`FLAG_NOFREE`) and shared strings (`FLAG_SHARED`) don't allow
 			// us to write to their pointer. As such, we need to duplicate the `alloc.ptr` field, which
-			// gives us ownership of it. Afterwards, we have to remove the relevant flags.
-			self.duplicate_alloc_ptr(self.inner().alloc.len);
+			// gives us ownership of it. Afterwards, we have to remove the relevant flags. (We keep the
+			// same capacity, as callers may have already checked there's enough room.)
+			self.duplicate_alloc_ptr(self.inner().alloc.cap);
 		}
 
 		self.inner_mut().alloc.ptr
@@ -384,6 +386,50 @@ impl List {
 		}
 	}
 
+	/// Inserts `ele` at `idx`, shifting all elements after it to the right.
+	///
+	/// # Panics
+	/// Panics if `idx` is greater than [`len()`](Self::len).
+	pub fn insert(&mut self, idx: usize, ele: Value) {
+		assert!(
+			idx <= self.len(),
+			"insertion index (is {idx}) should be <= len (is {})",
+			self.len()
+		);
+
+		if self.capacity() <= self.len() + 1 {
+			self.allocate_more(1);
+		}
+
+		// SAFETY: `idx <= len`, and we've made sure there's room for at least one more element, so
+		// shifting the `len - idx` elements after `idx` over by one stays within the capacity. The
+		// gap this leaves at `idx` is then initialized by `ele`.
+		unsafe {
+			let ptr = self.as_mut_ptr().add(idx);
+			ptr.copy_to(ptr.add(1), self.len() - idx);
+			ptr.write(ele);
+			self.set_len(self.len() + 1);
+		}
+	}
+
+	/// Removes and returns the element at `idx`, shifting all elements after it to the left.
+	///
+	/// Returns `None` if `idx` is out of bounds.
+	pub fn remove(&mut self, idx: usize) -> Option<Value> {
+		let ret = self.as_slice().get(idx).copied()?;
+
+		// SAFETY: `idx < len` as `get` succeeded, so the `len - idx - 1` elements after it are all
+		// initialized. Shifting them over `idx` leaves the last element duplicated, which is fine
+		// as it's then excluded by the new length. (`Value`s are `Copy`, so nothing's dropped.)
+		unsafe {
+			let ptr = self.as_mut_ptr().add(idx);
+			ptr.copy_from(ptr.add(1), self.len() - idx - 1);
+			self.set_len(self.len() - 1);
+		}
+
+		Some(ret)
+	}
+
 	pub fn push(&mut self, ele: Value) {
 		// OPTIMIZE: you can make this work better for single values.
 		self.extend_from_slice(std::slice::from_ref(&ele));
@@ -478,7 +524,7 @@ impl Drop for List {
 		// how do we want to deal with that, especially with substring shares, which dont
 		// know where the entire string starts.
 
-		unsafe { alloc::dealloc(self.alloc.ptr, alloc_ptr_layout(self.alloc.cap)) }
+		unsafe { crate::dealloc(self.alloc.ptr, alloc_ptr_layout(self.alloc.cap)) }
 	}
 }*/
 
@@ -510,11 +556,70 @@ impl From<&'_ [Value]> for crate::Value<Gc<List>> {
 	}
 }
 
+impl<T: crate::ToValue> crate::ToValue for Vec<T> {
+	fn to_value(self) -> Value {
+		let values = self.into_iter().map(crate::ToValue::to_value).collect::<Vec<_>>();
+
+		List::from_slice(&values).to_value()
+	}
+}
+
+// We copy the elements out of the list first, as converting them may run arbitrary code (which
+// could modify the list).
+fn list_elements(value: Value) -> crate::Result<Vec<Value>> {
+	Ok(value.to_list()?.as_ref()?.as_slice().to_vec())
+}
+
+/// Converts `value` to a [`List`], and then converts each element.
+impl<T: crate::FromValue> crate::FromValue for Vec<T> {
+	fn from_value(value: Value) -> crate::Result<Self> {
+		list_elements(value)?.into_iter().map(T::from_value).collect()
+	}
+}
+
+macro_rules! impl_tuple_conversions {
+	($($len:literal => ($($idx:tt $ty:ident),+);)*) => {$(
+		impl<$($ty: crate::ToValue),+> crate::ToValue for ($($ty,)+) {
+			fn to_value(self) -> Value {
+				List::from_slice(&[$(self.$idx.to_value()),+]).to_value()
+			}
+		}
+
+		/// Converts `value` to a [`List`], which must have exactly as many elements as the tuple.
+		impl<$($ty: crate::FromValue),+> crate::FromValue for ($($ty,)+) {
+			fn from_value(value: Value) -> crate::Result<Self> {
+				let elements = list_elements(value)?;
+
+				if elements.len() != $len {
+					return Err(crate::ErrorKind::Message(format!(
+						"expected a list of length {}, got one of length {}",
+						$len,
+						elements.len()
+					))
+					.into());
+				}
+
+				Ok(($($ty::from_value(elements[$idx])?,)+))
+			}
+		}
+	)*};
+}
+
+impl_tuple_conversions! {
+	1 => (0 A);
+	2 => (0 A, 1 B);
+	3 => (0 A, 1 B, 2 C);
+	4 => (0 A, 1 B, 2 C, 3 D);
+	5 => (0 A, 1 B, 2 C, 3 D, 4 E);
+	6 => (0 A, 1 B, 2 C, 3 D, 4 E, 5 F);
+}
+
 pub mod funcs {
 	use super::*;
 	use crate::value::ty::Text;
 	use crate::value::ToValue;
 	use crate::{vm::Args, Result};
+	use std::cmp::Ordering;
 
 	pub fn len(list: Gc<List>, args: Args<'_>) -> Result<Value> {
 		args.assert_no_arguments()?;
@@ -571,29 +676,36 @@ pub mod funcs {
 		args.assert_no_keyword()?;
 		args.assert_positional_len(1)?;
 
+		let rhs = if let Some(rhs) = args[0].downcast::<Gc<List>>() {
+			rhs
+		} else {
+			return Ok(Value::NULL.to_value());
+		};
+
 		let lhs = list.as_ref()?;
-		let rhs = args[0].try_downcast::<Gc<List>>()?.as_ref()?;
+		let rhs = rhs.as_ref()?;
 
 		for (&l, &r) in lhs.as_slice().iter().zip(rhs.as_slice().iter()) {
-			let cmp = l.call_attr(Intern::op_cmp, Args::new(&[r], &[]))?;
-
-			if cmp.is_a::<crate::value::ty::Null>() {
-				panic!("todo: what do we do with null in compare?")
-			}
-
-			let cmp = cmp.try_downcast::<crate::value::ty::Integer>()?;
-
-			if cmp.get() != 0 {
-				return Ok(cmp.to_value());
+			match l.try_partial_cmp(r)? {
+				Some(std::cmp::Ordering::Equal) => {}
+				Some(ordering) => return Ok(ordering.to_value()),
+				None => return Ok(Value::NULL.to_value()),
 			}
 		}
 
 		Ok(lhs.len().cmp(&rhs.len()).to_value())
 	}
 
-	pub fn lth(list: Gc<List>, args: Args<'_>) -> Result<Value> {
-		// cop out
-		Ok((cmp(list, args)?.try_downcast::<crate::value::ty::Integer>()?.get() < 0).to_value())
+	pub fn hash(list: Gc<List>, args: Args<'_>) -> Result<Value> {
+		use crate::value::compare;
+
+		args.assert_no_arguments()?;
+
+		let hash = compare::hash_recursive(list.to_value(), || {
+			compare::hash_all(list.as_ref()?.as_slice().iter().copied())
+		})?;
+
+		Ok(compare::hash_to_value(hash))
 	}
 
 	pub fn index(list: Gc<List>, args: Args<'_>) -> Result<Value> {
@@ -601,6 +713,19 @@ pub mod funcs {
 		args.idx_err_unless(|args| args.len() == 1 || args.len() == 2)?;
 
 		let listref = list.as_ref()?;
+
+		if let Some(range) = args[0].downcast::<Gc<crate::value::ty::Range>>() {
+			args.assert_positional_len(1)?;
+
+			let slice = listref.as_slice();
+			let range = range.as_ref()?;
+			let indices = range.slice_indices(slice.len());
+			let mut sliced = List::with_capacity(indices.len());
+			sliced.extend(indices.into_iter().map(|idx| slice[idx]));
+
+			return Ok(sliced.to_value());
+		}
+
 		let mut index = args[0].to_integer()?.get();
 
 		if index < 0 {
@@ -628,21 +753,23 @@ pub mod funcs {
 		args.assert_no_keyword()?;
 		args.assert_positional_len(2)?; // todo: more positional args for slicing
 
-		let mut listmut = list.as_mut()?;
 		let mut index = args[0].to_integer()?.get();
 		let value = args[1];
 
-		if index < 0 {
-			index += listmut.len() as i64;
-
+		modify(list, |listmut| {
 			if index < 0 {
-				return Err("todo: error for out of bounds".to_string().into());
+				index += listmut.len() as i64;
+
+				if index < 0 {
+					return Err("todo: error for out of bounds".to_string().into());
+				}
 			}
-		}
 
-		assert!(index <= listmut.len() as _, "todo: index out of bounds fills with null");
+			assert!(index <= listmut.len() as _, "todo: index out of bounds fills with null");
 
-		listmut.as_mut()[index as usize] = value;
+			listmut.as_mut()[index as usize] = value;
+			Ok(())
+		})?;
 
 		Ok(value)
 	}
@@ -652,6 +779,13 @@ pub mod funcs {
 		Ok(list.to_value())
 	}
 
+	pub fn to_set(list: Gc<List>, args: Args<'_>) -> Result<Value> {
+		args.assert_no_arguments()?;
+
+		let elements = list.as_ref()?.as_slice().to_vec();
+		Ok(crate::value::ty::Set::from_values(elements)?.to_value())
+	}
+
 	pub fn concat(list: Gc<List>, args: Args<'_>) -> Result<Value> {
 		args.assert_no_keyword()?;
 		args.assert_positional_len(1)?;
@@ -662,7 +796,11 @@ pub mod funcs {
 			todo!("push identical");
 		}
 
-		list.as_mut()?.extend_from_slice(rhs.as_ref()?.as_slice());
+		let rhs = rhs.as_ref()?;
+		modify(list, |listmut| {
+			listmut.extend_from_slice(rhs.as_slice());
+			Ok(())
+		})?;
 
 		Ok(list.to_value())
 	}
@@ -676,7 +814,10 @@ pub mod funcs {
 		args.assert_no_keyword()?;
 		args.assert_positional_len(1)?;
 
-		list.as_mut()?.push(args[0]);
+		modify(list, |listmut| {
+			listmut.push(args[0]);
+			Ok(())
+		})?;
 
 		Ok(list.to_value())
 	}
@@ -697,7 +838,10 @@ pub mod funcs {
 		args.assert_no_keyword()?;
 		args.assert_positional_len(1)?; // todo: more positional args for slicing
 
-		list.as_mut()?.unshift(args[0]);
+		modify(list, |listmut| {
+			listmut.unshift(args[0]);
+			Ok(())
+		})?;
 
 		Ok(list.to_value())
 	}
@@ -854,11 +998,401 @@ pub mod funcs {
 
 		let mut rng = thread_rng();
 
-		list.as_mut()?.as_mut_slice().shuffle(&mut rng);
+		modify(list, |listmut| {
+			listmut.as_mut_slice().shuffle(&mut rng);
+			Ok(())
+		})?;
+
+		Ok(list.to_value())
+	}
+
+	// `sort_by` is stable, but its comparator can't fail; so we stop comparing after the first
+	// error and then return it.
+	fn sort_slice_by<T: Copy>(
+		slice: &mut [T],
+		mut cmp: impl FnMut(T, T) -> Result<Ordering>,
+	) -> Result<()> {
+		let mut error = None;
+
+		slice.sort_by(|&lhs, &rhs| {
+			if error.is_some() {
+				return Ordering::Equal;
+			}
+
+			cmp(lhs, rhs).unwrap_or_else(|err| {
+				error = Some(err);
+				Ordering::Equal
+			})
+		});
+
+		error.map_or(Ok(()), Err)
+	}
+
+	// Each key is only computed once, regardless of how many comparisons are done.
+	fn sorted_by_key(elements: &[Value], func: Value) -> Result<Vec<Value>> {
+		let mut keyed = elements
+			.iter()
+			.map(|&ele| Ok((func.call(Args::new(&[ele], &[]))?, ele)))
+			.collect::<Result<Vec<_>>>()?;
+
+		sort_slice_by(&mut keyed, |(lhs, _), (rhs, _)| lhs.try_cmp(rhs))?;
+
+		Ok(keyed.into_iter().map(|(_, ele)| ele).collect())
+	}
+
+	// Adds to, or reorders, `list`. If it's the parents of a value, the change is undone (and an
+	// error returned) if it'd make the value its own ancestor or, unless they're lexical scopes, give
+	// it an inconsistent method resolution order, just like assigning `__parents__` does.
+	fn modify<T>(list: Gc<List>, func: impl FnOnce(&mut List) -> Result<T>) -> Result<T> {
+		if !list.flags().contains(Flags::PARENTS_LIST) {
+			return func(&mut *list.as_mut()?);
+		}
+
+		let original = list.as_ref()?.as_slice().to_vec();
+		let result = func(&mut *list.as_mut()?);
+		let result = result.and_then(|value| {
+			let parents = list.as_ref()?.as_slice().to_vec();
+
+			if list.flags().contains(Flags::SCOPES_LIST) {
+				crate::value::mro::linearize_scopes(&parents)?;
+			} else {
+				crate::value::mro::linearize_parents(&parents)?;
+			}
+
+			Ok(value)
+		});
+
+		// Failed sorts can leave the list partially modified, so they're undone too.
+		if result.is_err() {
+			let mut listmut = list.as_mut()?;
+			// SAFETY: Zero is always a valid length.
+			unsafe { listmut.set_len(0) };
+			listmut.extend_from_slice(&original);
+		}
+
+		result
+	}
+
+	// Resolves a (possibly negative) `index` into a list of length `len`, ensuring it's less than
+	// `bound`.
+	fn resolve_index(index: Value, len: usize, bound: usize) -> Result<usize> {
+		let index = index.to_integer()?.get();
+		let resolved = if index < 0 { index + len as i64 } else { index };
+
+		if 0 <= resolved && (resolved as usize) < bound {
+			Ok(resolved as usize)
+		} else {
+			Err(format!("index {index} is out of bounds for a list of length {len}").into())
+		}
+	}
+
+	fn position(list: Gc<List>, value: Value) -> Result<Option<usize>> {
+		for (idx, &ele) in list.as_ref()?.as_slice().iter().enumerate() {
+			if ele.try_eq(value)? {
+				return Ok(Some(idx));
+			}
+		}
+
+		Ok(None)
+	}
+
+	fn positive_size(size: Value) -> Result<usize> {
+		let size = size.to_integer()?.get();
+
+		if size <= 0 {
+			return Err(format!("size must be positive, not {size}").into());
+		}
+
+		Ok(size as usize)
+	}
+
+	fn list_of_slices<'a>(slices: impl ExactSizeIterator<Item = &'a [Value]>) -> Value {
+		let mut builder = List::with_capacity(slices.len());
+
+		for slice in slices {
+			builder.push(List::from_slice(slice).to_value());
+		}
+
+		builder.to_value()
+	}
+
+	pub fn sort(list: Gc<List>, args: Args<'_>) -> Result<Value> {
+		args.assert_no_arguments()?;
+
+		let sorted = list.as_ref()?.dup();
+		sort_slice_by(sorted.as_mut()?.as_mut_slice(), Value::try_cmp)?;
+
+		Ok(sorted.to_value())
+	}
+
+	pub fn sort_in_place(list: Gc<List>, args: Args<'_>) -> Result<Value> {
+		args.assert_no_arguments()?;
+
+		modify(list, |listmut| sort_slice_by(listmut.as_mut_slice(), Value::try_cmp))?;
+
+		Ok(list.to_value())
+	}
+
+	pub fn sort_by(list: Gc<List>, args: Args<'_>) -> Result<Value> {
+		args.assert_no_keyword()?;
+		args.assert_positional_len(1)?;
+
+		let sorted = sorted_by_key(list.as_ref()?.as_slice(), args[0])?;
+
+		Ok(List::from_slice(&sorted).to_value())
+	}
+
+	pub fn sort_by_in_place(list: Gc<List>, args: Args<'_>) -> Result<Value> {
+		args.assert_no_keyword()?;
+		args.assert_positional_len(1)?;
+
+		let sorted = sorted_by_key(list.as_ref()?.as_slice(), args[0])?;
+		modify(list, |listmut| {
+			listmut.as_mut_slice().copy_from_slice(&sorted);
+			Ok(())
+		})?;
+
+		Ok(list.to_value())
+	}
+
+	pub fn reverse(list: Gc<List>, args: Args<'_>) -> Result<Value> {
+		args.assert_no_arguments()?;
+
+		let listref = list.as_ref()?;
+		let mut reversed = List::with_capacity(listref.len());
+		reversed.extend(listref.as_slice().iter().rev().copied());
+
+		Ok(reversed.to_value())
+	}
+
+	pub fn index_of(list: Gc<List>, args: Args<'_>) -> Result<Value> {
+		args.assert_no_keyword()?;
+		args.assert_positional_len(1)?;
+
+		Ok(position(list, args[0])?.map(|idx| (idx as i64).to_value()).unwrap_or_default())
+	}
+
+	pub fn includes(list: Gc<List>, args: Args<'_>) -> Result<Value> {
+		args.assert_no_keyword()?;
+		args.assert_positional_len(1)?;
+
+		Ok(position(list, args[0])?.is_some().to_value())
+	}
+
+	pub fn insert(list: Gc<List>, args: Args<'_>) -> Result<Value> {
+		args.assert_no_keyword()?;
+		args.assert_positional_len(2)?;
+
+		modify(list, |listmut| {
+			let len = listmut.len();
+			let index = resolve_index(args[0], len, len + 1)?;
+			listmut.insert(index, args[1]);
+			Ok(())
+		})?;
+
+		Ok(list.to_value())
+	}
+
+	pub fn remove_at(list: Gc<List>, args: Args<'_>) -> Result<Value> {
+		args.assert_no_keyword()?;
+		args.assert_positional_len(1)?;
+
+		let mut listmut = list.as_mut()?;
+		let len = listmut.len();
+		let index = resolve_index(args[0], len, len)?;
+
+		Ok(listmut.remove(index).expect("index was bounds checked"))
+	}
+
+	pub fn slice(list: Gc<List>, args: Args<'_>) -> Result<Value> {
+		args.assert_no_keyword()?;
+		args.idx_err_unless(|a| a.len() == 1 || a.len() == 2)?;
+
+		let listref = list.as_ref()?;
+		let len = listref.len() as i64;
+
+		// Unlike indexing, slicing never fails: out of bounds indices are simply clamped.
+		let clamp = |index: Value| -> Result<usize> {
+			let index = index.to_integer()?.get();
+			Ok((if index < 0 { index + len } else { index }).clamp(0, len) as usize)
+		};
+
+		let start = clamp(args[0])?;
+		let end = args.get(1).map(clamp).transpose()?.unwrap_or(len as usize);
+
+		Ok(List::from_slice(listref.as_slice().get(start..end).unwrap_or_default()).to_value())
+	}
+
+	pub fn flatten(list: Gc<List>, args: Args<'_>) -> Result<Value> {
+		fn flatten_into(
+			list: Gc<List>,
+			depth: i64,
+			builder: &mut Builder,
+			visited: &mut Vec<Gc<List>>,
+		) -> Result<()> {
+			if visited.iter().any(|&ac| list.ptr_eq(ac)) {
+				return Err("cannot flatten a recursive list".to_string().into());
+			}
+
+			visited.push(list);
+
+			for &ele in list.as_ref()?.as_slice() {
+				match ele.downcast::<Gc<List>>() {
+					Some(sublist) if depth != 0 => flatten_into(sublist, depth - 1, builder, visited)?,
+					_ => builder.push(ele),
+				}
+			}
+
+			visited.pop();
+			Ok(())
+		}
+
+		args.assert_no_keyword()?;
+		args.idx_err_unless(|a| a.len() <= 1)?;
+
+		// Negative depths (the default) flatten all the way down.
+		let depth = args.get(0).map(|depth| depth.to_integer()).transpose()?.map_or(-1, |d| d.get());
+		let mut builder = List::with_capacity(list.as_ref()?.len());
+		flatten_into(list, depth, &mut builder, &mut Vec::new())?;
+
+		Ok(builder.to_value())
+	}
+
+	pub fn uniq(list: Gc<List>, args: Args<'_>) -> Result<Value> {
+		args.assert_no_arguments()?;
+
+		let listref = list.as_ref()?;
+		let seen = crate::value::ty::Set::with_capacity(listref.len());
+		let mut seenmut = seen.as_mut()?;
+		let mut uniq = List::with_capacity(listref.len());
+
+		for &ele in listref.as_slice() {
+			if seenmut.insert(ele)? {
+				uniq.push(ele);
+			}
+		}
+
+		Ok(uniq.to_value())
+	}
+
+	pub fn zip(list: Gc<List>, args: Args<'_>) -> Result<Value> {
+		args.assert_no_keyword()?;
+
+		let mut lists = vec![list.as_ref()?];
+		for other in args.positional() {
+			lists.push(other.try_downcast::<Gc<List>>()?.as_ref()?);
+		}
+
+		let len = lists.iter().map(|list| list.len()).min().unwrap_or_default();
+		let mut zipped = List::with_capacity(len);
+
+		for idx in 0..len {
+			let mut row = List::with_capacity(lists.len());
+			row.extend(lists.iter().map(|list| list[idx]));
+			zipped.push(row.to_value());
+		}
+
+		Ok(zipped.to_value())
+	}
+
+	pub fn chunks(list: Gc<List>, args: Args<'_>) -> Result<Value> {
+		args.assert_no_keyword()?;
+		args.assert_positional_len(1)?;
+
+		let size = positive_size(args[0])?;
+
+		Ok(list_of_slices(list.as_ref()?.as_slice().chunks(size)))
+	}
+
+	pub fn windows(list: Gc<List>, args: Args<'_>) -> Result<Value> {
+		args.assert_no_keyword()?;
+		args.assert_positional_len(1)?;
+
+		let size = positive_size(args[0])?;
+
+		Ok(list_of_slices(list.as_ref()?.as_slice().windows(size)))
+	}
+
+	fn extremum(list: Gc<List>, args: Args<'_>, wanted: Ordering) -> Result<Value> {
+		args.assert_no_arguments()?;
+
+		let listref = list.as_ref()?;
+		let (&first, rest) = match listref.as_slice().split_first() {
+			Some(split) => split,
+			None => return Ok(Value::default()),
+		};
+
+		let mut best = first;
+		for &ele in rest {
+			if ele.try_cmp(best)? == wanted {
+				best = ele;
+			}
+		}
+
+		Ok(best)
+	}
+
+	pub fn min(list: Gc<List>, args: Args<'_>) -> Result<Value> {
+		extremum(list, args, Ordering::Less)
+	}
+
+	pub fn max(list: Gc<List>, args: Args<'_>) -> Result<Value> {
+		extremum(list, args, Ordering::Greater)
+	}
+
+	pub fn binary_search(list: Gc<List>, args: Args<'_>) -> Result<Value> {
+		args.assert_no_keyword()?;
+		args.assert_positional_len(1)?;
+
+		let listref = list.as_ref()?;
+		let slice = listref.as_slice();
+		let (mut low, mut high) = (0, slice.len());
+
+		while low < high {
+			let mid = low + (high - low) / 2;
+
+			match slice[mid].try_cmp(args[0])? {
+				Ordering::Less => low = mid + 1,
+				Ordering::Greater => high = mid,
+				Ordering::Equal => return Ok((mid as i64).to_value()),
+			}
+		}
+
+		Ok(Value::default())
+	}
+
+	pub fn fill(list: Gc<List>, args: Args<'_>) -> Result<Value> {
+		args.assert_no_keyword()?;
+		args.assert_positional_len(1)?;
+
+		modify(list, |listmut| {
+			listmut.as_mut_slice().fill(args[0]);
+			Ok(())
+		})?;
 
 		Ok(list.to_value())
 	}
 
+	pub fn rotate(list: Gc<List>, args: Args<'_>) -> Result<Value> {
+		args.assert_no_keyword()?;
+		args.idx_err_unless(|a| a.len() <= 1)?;
+
+		let amount =
+			args.get(0).map(|amount| amount.to_integer()).transpose()?.map_or(1, |a| a.get());
+		let rotated = list.as_ref()?.dup();
+
+		{
+			let mut rotatedmut = rotated.as_mut()?;
+			let len = rotatedmut.len();
+
+			if len != 0 {
+				rotatedmut.as_mut_slice().rotate_left(amount.rem_euclid(len as i64) as usize);
+			}
+		}
+
+		Ok(rotated.to_value())
+	}
+
 	pub fn iter(list: Gc<List>, args: Args<'_>) -> Result<Value> {
 		args.assert_no_arguments()?;
 
@@ -888,10 +1422,11 @@ impl Singleton for ListClass {
 				Intern::op_eql => method funcs::eql,
 				Intern::op_add => method funcs::add,
 				Intern::op_cmp => method funcs::cmp,
-				Intern::op_lth => method funcs::lth,
+				Intern::hash => method funcs::hash,
 				Intern::op_index => method funcs::index,
 				Intern::op_index_assign => method funcs::index_assign,
 				Intern::to_list => method funcs::to_list,
+				Intern::to_set => method funcs::to_set,
 				Intern::concat => method funcs::concat,
 				Intern::is_empty => method funcs::is_empty,
 				Intern::len => method funcs::len,
@@ -909,6 +1444,26 @@ impl Singleton for ListClass {
 				Intern::dup => method funcs::dup,
 				Intern::shuffle => method funcs::shuffle,
 				Intern::iter => method funcs::iter,
+				Intern::sort => method funcs::sort,
+				Intern::sort_in_place => method funcs::sort_in_place,
+				Intern::sort_by => method funcs::sort_by,
+				Intern::sort_by_in_place => method funcs::sort_by_in_place,
+				Intern::reverse => method funcs::reverse,
+				Intern::index_of => method funcs::index_of,
+				Intern::includes => method funcs::includes,
+				Intern::insert => method funcs::insert,
+				Intern::remove_at => method funcs::remove_at,
+				Intern::slice => method funcs::slice,
+				Intern::flatten => method funcs::flatten,
+				Intern::uniq => method funcs::uniq,
+				Intern::zip => method funcs::zip,
+				Intern::chunks => method funcs::chunks,
+				Intern::windows => method funcs::windows,
+				Intern::min => method funcs::min,
+				Intern::max => method funcs::max,
+				Intern::binary_search => method funcs::binary_search,
+				Intern::fill => method funcs::fill,
+				Intern::rotate => method funcs::rotate,
 			}
 		})
 	}
@@ -917,3 +1472,48 @@ impl Singleton for ListClass {
 impl InstanceOf for List {
 	type Parent = ListClass;
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::value::ty::Integer;
+	use crate::ToValue;
+
+	fn integers(list: Gc<List>) -> Vec<i64> {
+		let list = list.as_ref().unwrap();
+		list.as_slice().iter().map(|ele| ele.try_downcast::<Integer>().unwrap().get()).collect()
+	}
+
+	fn list_of(range: std::ops::Range<i64>) -> Gc<List> {
+		List::from_slice(&range.map(|n| n.to_value()).collect::<Vec<_>>())
+	}
+
+	#[test]
+	fn duplicates_are_independent() {
+		let original = list_of(0..10);
+		let copy = original.as_ref().unwrap().dup();
+
+		copy.as_mut().unwrap().as_mut_slice()[0] = 99.to_value();
+		original.as_mut().unwrap().as_mut_slice()[1] = 98.to_value();
+
+		assert_eq!(integers(original), [0, 98, 2, 3, 4, 5, 6, 7, 8, 9]);
+		assert_eq!(integers(copy), [99, 1, 2, 3, 4, 5, 6, 7, 8, 9]);
+	}
+
+	#[test]
+	fn unsharing_keeps_capacity() {
+		let original = list_of(0..10);
+		original.as_mut().unwrap().push(10.to_value());
+
+		let copy = original.as_ref().unwrap().dup();
+		let capacity = copy.as_ref().unwrap().capacity();
+		assert!(capacity > 11, "pushing should've left spare capacity");
+
+		// There's enough room, so this writes directly into the buffer it just unshared.
+		copy.as_mut().unwrap().push(11.to_value());
+
+		assert!(copy.as_ref().unwrap().capacity() >= capacity);
+		assert_eq!(integers(original), (0..=10).collect::<Vec<_>>());
+		assert_eq!(integers(copy), (0..=11).collect::<Vec<_>>());
+	}
+}
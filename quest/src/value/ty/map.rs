@@ -0,0 +1,486 @@
+//! The dictionary type within Quest.
+
+use crate::value::ty::{InstanceOf, List, Singleton, Text};
+use crate::value::{Gc, ToValue};
+use crate::vm::Args;
+use crate::{Result, Value};
+use hashbrown::hash_map::RawEntryMut;
+use hashbrown::HashMap;
+use std::fmt::{self, Debug, Formatter};
+
+quest_type! {
+	/// A mapping of arbitrary [`Value`]s to other [`Value`]s.
+	///
+	/// Keys are compared via [`Value::try_hash`] and [`Value::try_eq`], which means that any value
+	/// which defines `hash` and `==` can be used as a key. [`Text`] and [`Integer`](
+	/// crate::value::ty::Integer) keys never call into Quest code, as they have dedicated fast paths.
+	///
+	/// Maps can also have a default value, which is returned when indexing into the map with a key
+	/// that doesn't exist.
+	///
+	/// # Examples
+	/// ```
+	/// # use quest::value::ty::Map;
+	/// # use quest::value::ToValue;
+	/// let map = Map::new();
+	/// map.as_mut().unwrap().insert("foo".to_value(), 12.to_value()).unwrap();
+	///
+	/// let mapref = map.as_ref().unwrap();
+	/// assert_eq!(mapref.len(), 1);
+	/// assert!(mapref.get("foo".to_value()).unwrap().unwrap().is_identical(12.to_value()));
+	/// assert!(mapref.get("bar".to_value()).unwrap().is_none());
+	/// ```
+	#[derive(NamedType)]
+	pub struct Map(Inner);
+}
+
+#[doc(hidden)]
+#[derive(Default)]
+pub struct Inner {
+	entries: HashMap<Hashed, Value>,
+	default: Option<Value>,
+}
+
+/// A key (or a [`Set`](crate::value::ty::Set) element) along with its hash.
+///
+/// Hashing a key can run arbitrary Quest code, which may fail or try to access the map itself. So
+/// keys are only hashed once, when they're inserted, and resizing uses the cached hash instead.
+#[derive(Clone, Copy)]
+pub(super) struct Hashed {
+	pub(super) hash: u64,
+	pub(super) value: Value,
+}
+
+// Looks up the entry for `key` (which hashes to `hash`) via `$method`, propagating any errors that
+// occur when comparing keys.
+macro_rules! lookup {
+	($entries:expr, $method:ident, $hash:expr, $key:expr) => {{
+		let key = $key;
+		let mut eq_err: Result<()> = Ok(());
+
+		let res = $entries.$method().from_hash($hash, |k: &Hashed| match key.try_eq(k.value) {
+			Ok(val) => val,
+			Err(err) => {
+				eq_err = Err(err);
+				true
+			}
+		});
+		eq_err?;
+
+		res
+	}};
+}
+
+pub(super) use lookup;
+
+impl Map {
+	/// Creates a new, empty `Map`.
+	#[must_use]
+	pub fn new() -> Gc<Self> {
+		Self::with_capacity(0)
+	}
+
+	/// Creates a new, empty `Map` with space for at least `capacity` entries.
+	#[must_use]
+	pub fn with_capacity(capacity: usize) -> Gc<Self> {
+		use crate::value::base::{Base, HasDefaultParent};
+
+		Base::new(
+			Inner { entries: HashMap::with_capacity(capacity), default: None },
+			Self::parent(),
+		)
+	}
+
+	/// Creates a new `Map` from the `(key, value)` pairs of `iter`.
+	///
+	/// If a key is given multiple times, the last value is used.
+	pub fn from_pairs(iter: impl IntoIterator<Item = (Value, Value)>) -> Result<Gc<Self>> {
+		let iter = iter.into_iter();
+		let map = Self::with_capacity(iter.size_hint().0);
+
+		{
+			let mut mapmut = map.as_mut()?;
+			for (key, value) in iter {
+				mapmut.insert(key, value)?;
+			}
+		}
+
+		Ok(map)
+	}
+
+	fn inner(&self) -> &Inner {
+		self.0.data()
+	}
+
+	fn inner_mut(&mut self) -> &mut Inner {
+		self.0.data_mut()
+	}
+
+	/// Gets the amount of entries within `self`.
+	#[must_use]
+	pub fn len(&self) -> usize {
+		self.inner().entries.len()
+	}
+
+	/// Returns whether `self` has no entries.
+	#[must_use]
+	pub fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+
+	/// Gets the value that's returned when indexing with a nonexistent key, if it's been set.
+	#[must_use]
+	pub fn default_value(&self) -> Option<Value> {
+		self.inner().default
+	}
+
+	/// Sets the value that's returned when indexing with a nonexistent key.
+	pub fn set_default_value(&mut self, default: Option<Value>) {
+		self.inner_mut().default = default;
+	}
+
+	/// Gets the value associated with `key`, if it exists.
+	///
+	/// This returns an `Err` if hashing `key`, or comparing it against another key, fails.
+	pub fn get(&self, key: Value) -> Result<Option<Value>> {
+		let hash = key.try_hash()?;
+		let res = lookup!(self.inner().entries, raw_entry, hash, key);
+
+		Ok(res.map(|(_key, &value)| value))
+	}
+
+	/// Checks to see if `key` exists in `self`.
+	pub fn contains_key(&self, key: Value) -> Result<bool> {
+		self.get(key).map(|value| value.is_some())
+	}
+
+	/// Associates `value` with `key`, returning the previous value if one existed.
+	pub fn insert(&mut self, key: Value, value: Value) -> Result<Option<Value>> {
+		let hash = key.try_hash()?;
+
+		match lookup!(self.inner_mut().entries, raw_entry_mut, hash, key) {
+			RawEntryMut::Occupied(mut occ) => Ok(Some(occ.insert(value))),
+			RawEntryMut::Vacant(vac) => {
+				vac.insert_with_hasher(hash, Hashed { hash, value: key }, value, |k| k.hash);
+
+				Ok(None)
+			}
+		}
+	}
+
+	/// Removes `key` from `self`, returning its value if it existed.
+	pub fn remove(&mut self, key: Value) -> Result<Option<Value>> {
+		let hash = key.try_hash()?;
+
+		match lookup!(self.inner_mut().entries, raw_entry_mut, hash, key) {
+			RawEntryMut::Occupied(occ) => Ok(Some(occ.remove())),
+			RawEntryMut::Vacant(_) => Ok(None),
+		}
+	}
+
+	/// Returns an iterator over the `(key, value)` pairs of `self`, in an unspecified order.
+	pub fn iter(&self) -> impl std::iter::Iterator<Item = (Value, Value)> + '_ {
+		self.inner().entries.iter().map(|(key, &value)| (key.value, value))
+	}
+
+	/// Creates a (shallow) copy of `self`, including its default value.
+	#[must_use]
+	pub fn dup(&self) -> Gc<Self> {
+		use crate::value::base::{Base, HasDefaultParent};
+
+		Base::new(
+			Inner { entries: self.inner().entries.clone(), default: self.inner().default },
+			Self::parent(),
+		)
+	}
+}
+
+/// Converts each key and value of a [`Map`].
+impl<K, V, S> crate::value::FromValue for std::collections::HashMap<K, V, S>
+where
+	K: crate::value::FromValue + Eq + std::hash::Hash,
+	V: crate::value::FromValue,
+	S: std::hash::BuildHasher + Default,
+{
+	fn from_value(value: Value) -> Result<Self> {
+		// We copy the entries out first, as converting them may run arbitrary code.
+		let entries = value.try_downcast::<Gc<Map>>()?.as_ref()?.iter().collect::<Vec<_>>();
+
+		entries
+			.into_iter()
+			.map(|(key, value)| Ok((K::from_value(key)?, V::from_value(value)?)))
+			.collect()
+	}
+}
+
+impl Debug for Map {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		if f.alternate() {
+			f.write_str("Map(")?;
+		}
+
+		f.debug_map().entries(self.iter()).finish()?;
+
+		if f.alternate() {
+			f.write_str(")")?;
+		}
+
+		Ok(())
+	}
+}
+
+pub mod funcs {
+	use super::*;
+
+	pub fn len(map: Gc<Map>, args: Args<'_>) -> Result<Value> {
+		args.assert_no_arguments()?;
+
+		Ok((map.as_ref()?.len() as i64).to_value())
+	}
+
+	pub fn is_empty(map: Gc<Map>, args: Args<'_>) -> Result<Value> {
+		args.assert_no_arguments()?;
+
+		Ok(map.as_ref()?.is_empty().to_value())
+	}
+
+	pub fn eql(map: Gc<Map>, args: Args<'_>) -> Result<Value> {
+		args.assert_no_keyword()?;
+		args.assert_positional_len(1)?;
+
+		if map.to_value().is_identical(args[0]) {
+			return Ok(true.to_value());
+		}
+
+		let rhs = if let Some(rhs) = args[0].downcast::<Gc<Map>>() {
+			rhs
+		} else {
+			return Ok(false.to_value());
+		};
+
+		let lhsref = map.as_ref()?;
+		let rhsref = rhs.as_ref()?;
+
+		if lhsref.len() != rhsref.len() {
+			return Ok(false.to_value());
+		}
+
+		for (key, value) in lhsref.iter() {
+			match rhsref.get(key)? {
+				Some(rvalue) if value.try_eq(rvalue)? => {}
+				_ => return Ok(false.to_value()),
+			}
+		}
+
+		Ok(true.to_value())
+	}
+
+	/// Hashes the entries of `map`, regardless of their order, so that maps which are `==` have the
+	/// same hash. (Default values aren't compared, so they aren't hashed either.)
+	pub fn hash(map: Gc<Map>, args: Args<'_>) -> Result<Value> {
+		use crate::value::compare;
+		use std::hash::Hasher;
+
+		args.assert_no_arguments()?;
+
+		let hash = compare::hash_recursive(map.to_value(), || {
+			let mapref = map.as_ref()?;
+			let mut hashes = Vec::with_capacity(mapref.len());
+
+			for (key, &value) in &mapref.inner().entries {
+				let mut hasher = std::collections::hash_map::DefaultHasher::new();
+				hasher.write_u64(key.hash);
+				hasher.write_u64(value.try_hash()?);
+				hashes.push(hasher.finish());
+			}
+
+			Ok(compare::hash_unordered(hashes))
+		})?;
+
+		Ok(compare::hash_to_value(hash))
+	}
+
+	pub fn index(map: Gc<Map>, args: Args<'_>) -> Result<Value> {
+		args.assert_no_keyword()?;
+		args.assert_positional_len(1)?;
+
+		let mapref = map.as_ref()?;
+
+		Ok(mapref.get(args[0])?.or_else(|| mapref.default_value()).unwrap_or_default())
+	}
+
+	pub fn index_assign(map: Gc<Map>, args: Args<'_>) -> Result<Value> {
+		args.assert_no_keyword()?;
+		args.assert_positional_len(2)?;
+
+		map.as_mut()?.insert(args[0], args[1])?;
+
+		Ok(args[1])
+	}
+
+	pub fn get(map: Gc<Map>, args: Args<'_>) -> Result<Value> {
+		args.assert_no_keyword()?;
+		args.idx_err_unless(|a| a.len() == 1 || a.len() == 2)?;
+
+		Ok(map.as_ref()?.get(args[0])?.or_else(|| args.get(1)).unwrap_or_default())
+	}
+
+	pub fn includes(map: Gc<Map>, args: Args<'_>) -> Result<Value> {
+		args.assert_no_keyword()?;
+		args.assert_positional_len(1)?;
+
+		Ok(map.as_ref()?.contains_key(args[0])?.to_value())
+	}
+
+	pub fn delete(map: Gc<Map>, args: Args<'_>) -> Result<Value> {
+		args.assert_no_keyword()?;
+		args.assert_positional_len(1)?;
+
+		Ok(map.as_mut()?.remove(args[0])?.unwrap_or_default())
+	}
+
+	pub fn merge(map: Gc<Map>, args: Args<'_>) -> Result<Value> {
+		args.assert_no_keyword()?;
+		args.assert_positional_len(1)?;
+
+		let rhs = args[0].try_downcast::<Gc<Map>>()?;
+		let merged = map.as_ref()?.dup();
+
+		// `rhs` may be `map`, so we can't borrow it at the same time as `merged`.
+		let pairs = rhs.as_ref()?.iter().collect::<Vec<_>>();
+		let mut mergedmut = merged.as_mut()?;
+
+		for (key, value) in pairs {
+			mergedmut.insert(key, value)?;
+		}
+
+		Ok(merged.to_value())
+	}
+
+	pub fn default(map: Gc<Map>, args: Args<'_>) -> Result<Value> {
+		args.assert_no_arguments()?;
+
+		Ok(map.as_ref()?.default_value().unwrap_or_default())
+	}
+
+	pub fn set_default(map: Gc<Map>, args: Args<'_>) -> Result<Value> {
+		args.assert_no_keyword()?;
+		args.assert_positional_len(1)?;
+
+		map.as_mut()?.set_default_value(Some(args[0]));
+
+		Ok(map.to_value())
+	}
+
+	pub fn dup(map: Gc<Map>, args: Args<'_>) -> Result<Value> {
+		args.assert_no_arguments()?;
+
+		Ok(map.as_ref()?.dup().to_value())
+	}
+
+	// Since the map may be modified while it's being iterated over, all the iterators operate on a
+	// snapshot of the map's contents at the time they were created.
+	fn snapshot_iter(name: &'static str, values: Vec<Value>) -> Value {
+		let mut values = values.into_iter();
+
+		crate::iterator! { name;
+			values.next().ok_or_else(|| StopIteration.into())
+		}
+		.to_value()
+	}
+
+	pub fn keys(map: Gc<Map>, args: Args<'_>) -> Result<Value> {
+		args.assert_no_arguments()?;
+
+		let keys = map.as_ref()?.iter().map(|(key, _)| key).collect();
+		Ok(snapshot_iter("Map::keys", keys))
+	}
+
+	pub fn values(map: Gc<Map>, args: Args<'_>) -> Result<Value> {
+		args.assert_no_arguments()?;
+
+		let values = map.as_ref()?.iter().map(|(_, value)| value).collect();
+		Ok(snapshot_iter("Map::values", values))
+	}
+
+	pub fn entries(map: Gc<Map>, args: Args<'_>) -> Result<Value> {
+		args.assert_no_arguments()?;
+
+		let entries = map
+			.as_ref()?
+			.iter()
+			.map(|(key, value)| List::from_slice(&[key, value]).to_value())
+			.collect();
+		Ok(snapshot_iter("Map::entries", entries))
+	}
+
+	pub fn to_text(map: Gc<Map>, args: Args<'_>) -> Result<Value> {
+		args.assert_no_arguments()?;
+
+		let mapref = map.as_ref()?;
+
+		if mapref.is_empty() {
+			return Ok(Text::from_static_str("[=>]").to_value());
+		}
+
+		let mut builder = Text::simple_builder();
+		builder.push('[');
+
+		for (i, (key, value)) in mapref.iter().enumerate() {
+			if i != 0 {
+				builder.push_str(", ");
+			}
+
+			builder.push_str(key.dbg_text()?.as_ref()?.as_str());
+			builder.push_str(" => ");
+			builder.push_str(value.dbg_text()?.as_ref()?.as_str());
+		}
+
+		builder.push(']');
+
+		Ok(builder.finish().to_value())
+	}
+
+	pub fn dbg(map: Gc<Map>, args: Args<'_>) -> Result<Value> {
+		to_text(map, args)
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct MapClass;
+
+impl Singleton for MapClass {
+	fn instance() -> crate::Value {
+		use once_cell::sync::OnceCell;
+
+		static INSTANCE: OnceCell<crate::Value> = OnceCell::new();
+
+		*INSTANCE.get_or_init(|| {
+			create_class! { "Map", parent Iterable::instance();
+				Intern::op_eql => method funcs::eql,
+				Intern::hash => method funcs::hash,
+				Intern::op_index => method funcs::index,
+				Intern::op_index_assign => method funcs::index_assign,
+				Intern::len => method funcs::len,
+				Intern::is_empty => method funcs::is_empty,
+				Intern::get => method funcs::get,
+				Intern::includes => method funcs::includes,
+				Intern::delete => method funcs::delete,
+				Intern::merge => method funcs::merge,
+				Intern::default => method funcs::default,
+				Intern::set_default => method funcs::set_default,
+				Intern::dup => method funcs::dup,
+				Intern::keys => method funcs::keys,
+				Intern::values => method funcs::values,
+				Intern::entries => method funcs::entries,
+				Intern::iter => method funcs::entries,
+				Intern::to_text => method funcs::to_text,
+				Intern::dbg => method funcs::dbg,
+			}
+		})
+	}
+}
+
+impl InstanceOf for Map {
+	type Parent = MapClass;
+}
@@ -13,15 +13,22 @@ pub mod boundfn;
 pub mod callable;
 pub mod class;
 pub mod float;
+pub mod instance;
 pub mod integer;
 pub mod iterator;
 pub mod kernel;
 pub mod list;
+pub mod map;
+pub mod native;
 pub mod null;
 pub mod object;
 pub mod pristine;
+pub mod range;
 pub mod scope;
+pub mod set;
+pub mod task;
 pub mod text;
+pub mod thread;
 mod wrap;
 
 pub use bignum::BigNum;
@@ -30,16 +37,21 @@ pub use boundfn::BoundFn;
 pub use callable::Callable;
 pub use class::Class;
 pub use float::Float;
+pub use instance::Instance;
 pub use integer::Integer;
 pub use iterable::Iterable;
 pub use iterator::Iterator;
 pub use kernel::Kernel;
 pub use list::List;
+pub use map::Map;
+pub use native::Native;
 pub use null::Null;
 pub use object::Object;
 pub use pristine::Pristine;
+pub use range::Range;
 pub use rustfn::RustFn;
 pub use scope::Scope;
+pub use set::Set;
 pub use text::Text;
 pub use wrap::Wrap;
 
@@ -0,0 +1,169 @@
+//! Quest types that are defined outside of `quest`.
+//!
+//! Builtin types each have their own [`TypeFlag`], but there's only a limited amount of those. So
+//! instead, types defined by embedders implement [`NativeType`], and are then allocated as a
+//! [`Native`], which all share a single [`TypeFlag::Native`] and are told apart by their
+//! [`TypeId`].
+//!
+//! # Examples
+//! ```
+//! use quest::value::ty::{class, native::{Native, NativeType}};
+//! use quest::value::Gc;
+//! use quest::{quest_fn, Interpreter, Result, ToValue, FromValue};
+//!
+//! struct Counter(i64);
+//!
+//! /// Increments the counter, returning its new value.
+//! #[quest_fn]
+//! fn incr(#[this] counter: Gc<Native<Counter>>) -> Result<i64> {
+//!     let mut counter = counter.as_mut()?;
+//!     counter.0 += 1;
+//!     Ok(counter.0)
+//! }
+//!
+//! impl NativeType for Counter {
+//!     const TYPENAME: &'static str = "Counter";
+//!
+//!     fn init_class(class: &mut class::Builder) -> Result<()> {
+//!         class.set_attr("incr".to_value(), qs_incr.to_value())
+//!     }
+//! }
+//!
+//! let mut interpreter = Interpreter::new();
+//! interpreter.set_global("counter", Native::new(Counter(10))?.to_value())?;
+//!
+//! let result = interpreter.eval("counter.incr(); counter.incr()")?;
+//! assert_eq!(i64::from_value(result)?, 12);
+//! # quest::Result::Ok(())
+//! ```
+
+use crate::value::base::{Base, HasTypeFlag, TypeFlag};
+use crate::value::ty::{class, Class, Object};
+use crate::value::{Gc, HasFlags, NamedType, ToValue, Typename};
+use crate::{Result, Value};
+use std::any::TypeId;
+use std::collections::HashMap;
+use std::fmt::{self, Debug, Formatter};
+use std::ops::{Deref, DerefMut};
+use std::sync::Mutex;
+
+/// A Rust type that can be used within Quest.
+pub trait NativeType: Sized + Send + Sync + 'static {
+	/// The name of the type (and its class).
+	const TYPENAME: Typename;
+
+	/// Initializes the class that's used as the parent for all values of this type.
+	///
+	/// This is called once per type, the first time a value of it is created. The class's parent
+	/// defaults to [`Object`], but can be overwritten.
+	///
+	/// # Errors
+	/// Returns any errors that occur when setting up the class.
+	fn init_class(class: &mut class::Builder) -> Result<()>;
+}
+
+quest_type! {
+	/// An allocated `T`, whose parent is `T`'s class.
+	///
+	/// This derefs to `T`, so [`Gc::as_ref`] and [`Gc::as_mut`] can be used to access it.
+	pub struct Native<T>(Inner<T>) where {T: 'static};
+}
+
+// The layout of this doesn't depend on `T` (as `data` is boxed), so the `type_id` and `typename` of
+// any `Native` can be read without knowing its type.
+#[repr(C)]
+#[doc(hidden)]
+pub struct Inner<T> {
+	type_id: TypeId,
+	typename: Typename,
+	data: Box<T>,
+}
+
+unsafe impl<T: 'static> HasTypeFlag for Native<T> {
+	const TYPE_FLAG: TypeFlag = TypeFlag::Native;
+
+	unsafe fn is_instance(ptr: *const Self) -> bool {
+		(*ptr).0.data().type_id == TypeId::of::<T>()
+	}
+}
+
+impl<T: NativeType> NamedType for Gc<Native<T>> {
+	const TYPENAME: Typename = T::TYPENAME;
+}
+
+static CLASSES: Mutex<Option<HashMap<TypeId, Value>>> = Mutex::new(None);
+
+fn lookup_class(type_id: TypeId) -> Option<Value> {
+	CLASSES.lock().unwrap().as_ref().and_then(|classes| classes.get(&type_id).copied())
+}
+
+impl<T: NativeType> Native<T> {
+	/// Gets the class for `T`, initializing it if it hasn't been yet.
+	///
+	/// # Errors
+	/// Returns any errors from [`NativeType::init_class`].
+	pub fn class() -> Result<Value> {
+		let type_id = TypeId::of::<T>();
+
+		if let Some(class) = lookup_class(type_id) {
+			return Ok(class);
+		}
+
+		// We don't hold the lock while initializing, as `init_class` might need other native classes.
+		let mut builder = Class::builder(T::TYPENAME, 0);
+		builder.parent(Object::instance());
+		T::init_class(&mut builder)?;
+		let class = builder.finish().to_value();
+
+		// If another thread initialized the class at the same time, use theirs so there's only one.
+		let mut classes = CLASSES.lock().unwrap();
+		Ok(*classes.get_or_insert_with(HashMap::new).entry(type_id).or_insert(class))
+	}
+
+	/// Allocates `data` as a new [`Native`].
+	///
+	/// # Errors
+	/// Returns any errors from initializing the class (see [`class`](Self::class)).
+	pub fn new(data: T) -> Result<Gc<Self>> {
+		let inner = Inner { type_id: TypeId::of::<T>(), typename: T::TYPENAME, data: Box::new(data) };
+
+		Ok(Base::new(inner, Self::class()?))
+	}
+}
+
+impl<T> Deref for Native<T> {
+	type Target = T;
+
+	fn deref(&self) -> &T {
+		&self.0.data().data
+	}
+}
+
+impl<T> DerefMut for Native<T> {
+	fn deref_mut(&mut self) -> &mut T {
+		&mut self.0.data_mut().data
+	}
+}
+
+impl<T: Debug> Debug for Native<T> {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		Debug::fmt(&**self, f)
+	}
+}
+
+/// Gets the typename of `value`, if it's a [`Native`].
+pub(crate) fn typename_of(value: Value) -> Option<Typename> {
+	if !value.is_allocated() {
+		return None;
+	}
+
+	// SAFETY: `value` is allocated, so it's a valid pointer to some `Base`. Since the layout of
+	// `Inner` doesn't depend on `T`, we can read its `typename` after checking its type flag.
+	let native = unsafe { Gc::<Native<()>>::new_unchecked(value.bits() as usize as *mut _) };
+
+	if native.flags().type_flag() != TypeFlag::Native {
+		return None;
+	}
+
+	Some(unsafe { (*native.as_ptr()).0.data().typename })
+}
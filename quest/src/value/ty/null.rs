@@ -27,6 +27,23 @@ impl From<Null> for Value<Null> {
 	}
 }
 
+impl<T: ToValue> ToValue for Option<T> {
+	fn to_value(self) -> Value {
+		self.map_or(Value::NULL.to_value(), ToValue::to_value)
+	}
+}
+
+/// `null` is converted to `None`, and everything else is converted to `Some`.
+impl<T: crate::value::FromValue> crate::value::FromValue for Option<T> {
+	fn from_value(value: Value) -> Result<Self> {
+		if value.is_a::<Null>() {
+			Ok(None)
+		} else {
+			T::from_value(value).map(Some)
+		}
+	}
+}
+
 unsafe impl Convertible for Null {
 	fn is_a(value: Value) -> bool {
 		value.bits() == Value::NULL.bits()
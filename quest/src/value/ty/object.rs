@@ -1,4 +1,4 @@
-use crate::value::{Callable, ToValue};
+use crate::value::{Attributed, Callable, ToValue};
 use crate::vm::Args;
 use crate::{ErrorKind, Intern, Result, Value};
 
@@ -20,6 +20,11 @@ impl Object {
 			create_class! { "Object", parent Pristine::instance();
 				Intern::op_eql => function funcs::eql,
 				Intern::op_neq => function funcs::neq,
+				Intern::op_cmp => function funcs::cmp,
+				Intern::op_lth => function funcs::lth,
+				Intern::op_leq => function funcs::leq,
+				Intern::op_gth => function funcs::gth,
+				Intern::op_geq => function funcs::geq,
 				Intern::op_not => function funcs::not,
 				Intern::to_bool => function funcs::to_bool,
 				Intern::to_text => function funcs::to_text,
@@ -36,8 +41,15 @@ impl Object {
 				Intern::itself => function funcs::itself,
 				Intern::display => function funcs::display,
 				Intern::freeze => function funcs::freeze,
+				Intern::deep_freeze => function funcs::deep_freeze,
+				Intern::is_frozen => function funcs::is_frozen,
+				Intern::dup => function funcs::dup,
+				Intern::clone => function funcs::clone,
 				Intern::dbg => function funcs::dbg,
 				Intern::assert => function funcs::assert,
+				Intern::is_a => function funcs::is_a,
+				Intern::responds_to => function funcs::responds_to,
+				Intern::typename => function funcs::typename,
 			}
 		})
 	}
@@ -45,6 +57,8 @@ impl Object {
 
 pub mod funcs {
 	use super::*;
+	use crate::value::compare;
+	use std::cmp::Ordering;
 
 	pub fn eql(obj: Value, args: Args<'_>) -> Result<Value> {
 		args.assert_no_keyword()?;
@@ -57,6 +71,47 @@ pub mod funcs {
 		obj.call_attr(Intern::op_eql, args)?.call_attr(Intern::op_not, Args::default())
 	}
 
+	/// Returns `0` if `obj` is `==` to the argument, and `null` otherwise, as objects don't have
+	/// an ordering by default.
+	pub fn cmp(obj: Value, args: Args<'_>) -> Result<Value> {
+		args.assert_no_keyword()?;
+		args.assert_positional_len(1)?;
+
+		if obj.try_eq(args[0])? {
+			Ok(0.to_value())
+		} else {
+			Ok(Value::NULL.to_value())
+		}
+	}
+
+	pub fn lth(obj: Value, args: Args<'_>) -> Result<Value> {
+		args.assert_no_keyword()?;
+		args.assert_positional_len(1)?;
+
+		compare::relate(obj, args[0], Ordering::is_lt)
+	}
+
+	pub fn leq(obj: Value, args: Args<'_>) -> Result<Value> {
+		args.assert_no_keyword()?;
+		args.assert_positional_len(1)?;
+
+		compare::relate(obj, args[0], Ordering::is_le)
+	}
+
+	pub fn gth(obj: Value, args: Args<'_>) -> Result<Value> {
+		args.assert_no_keyword()?;
+		args.assert_positional_len(1)?;
+
+		compare::relate(obj, args[0], Ordering::is_gt)
+	}
+
+	pub fn geq(obj: Value, args: Args<'_>) -> Result<Value> {
+		args.assert_no_keyword()?;
+		args.assert_positional_len(1)?;
+
+		compare::relate(obj, args[0], Ordering::is_ge)
+	}
+
 	pub fn not(obj: Value, args: Args<'_>) -> Result<Value> {
 		args.assert_no_arguments()?;
 
@@ -79,11 +134,18 @@ pub mod funcs {
 		Ok((obj.id() as i64).to_value())
 	}
 
+	/// Returns an unfrozen copy of `obj`, whose attributes and contents are also copied.
 	pub fn clone(obj: Value, args: Args<'_>) -> Result<Value> {
 		args.assert_no_arguments()?;
 
-		let _ = obj;
-		todo!("clone")
+		obj.deep_dup()
+	}
+
+	/// Returns an unfrozen copy of `obj`, which shares its attributes and contents.
+	pub fn dup(obj: Value, args: Args<'_>) -> Result<Value> {
+		args.assert_no_arguments()?;
+
+		obj.dup()
 	}
 
 	pub fn r#return(obj: Value, args: Args<'_>) -> Result<Value> {
@@ -184,6 +246,40 @@ pub mod funcs {
 		Ok(obj)
 	}
 
+	pub fn deep_freeze(obj: Value, args: Args<'_>) -> Result<Value> {
+		args.assert_no_arguments()?;
+
+		obj.deep_freeze()?;
+
+		Ok(obj)
+	}
+
+	pub fn is_frozen(obj: Value, args: Args<'_>) -> Result<Value> {
+		args.assert_no_arguments()?;
+
+		Ok(obj.is_frozen().to_value())
+	}
+
+	pub fn is_a(obj: Value, args: Args<'_>) -> Result<Value> {
+		args.assert_no_keyword()?;
+		args.assert_positional_len(1)?;
+
+		Ok(obj.inherits_from(args[0])?.to_value())
+	}
+
+	pub fn responds_to(obj: Value, args: Args<'_>) -> Result<Value> {
+		args.assert_no_keyword()?;
+		args.assert_positional_len(1)?;
+
+		Ok(obj.has_attr(args[0])?.to_value())
+	}
+
+	pub fn typename(obj: Value, args: Args<'_>) -> Result<Value> {
+		args.assert_no_arguments()?;
+
+		Ok(obj.typename().to_value())
+	}
+
 	pub fn dbg(obj: Value, args: Args<'_>) -> Result<Value> {
 		use crate::value::ty::text::SimpleBuilder;
 
@@ -1,6 +1,7 @@
+use crate::value::ty::List;
 use crate::value::{base::Base, Attributed, AttributedMut, Gc, TryAttributed};
 use crate::vm::Args;
-use crate::{Intern, Result, ToValue, Value};
+use crate::{ErrorKind, Intern, Result, ToValue, Value};
 
 quest_type! {
 	#[derive(Debug, NamedType)]
@@ -14,7 +15,7 @@ impl Pristine {
 
 		INSTANCE
 			.get_or_init(|| {
-				let mut builder = Base::<Pristine>::builder(7);
+				let mut builder = Base::<Pristine>::builder(12);
 
 				builder
 					.set_attr(
@@ -59,6 +60,41 @@ impl Pristine {
 					)
 					.unwrap();
 
+				builder
+					.set_attr(
+						Intern::__attrs__,
+						RustFn_new!("__attrs__", function funcs::__attrs__).to_value(),
+					)
+					.unwrap();
+
+				builder
+					.set_attr(
+						Intern::__all_attrs__,
+						RustFn_new!("__all_attrs__", function funcs::__all_attrs__).to_value(),
+					)
+					.unwrap();
+
+				builder
+					.set_attr(
+						Intern::__ancestors__,
+						RustFn_new!("__ancestors__", function funcs::__ancestors__).to_value(),
+					)
+					.unwrap();
+
+				builder
+					.set_attr(
+						Intern::__mro__,
+						RustFn_new!("__mro__", function funcs::__mro__).to_value(),
+					)
+					.unwrap();
+
+				builder
+					.set_attr(
+						Intern::__super__,
+						RustFn_new!("__super__", function funcs::__super__).to_value(),
+					)
+					.unwrap();
+
 				// we don't set parents, as empty parents is default.
 				unsafe { builder.finish() }
 			})
@@ -110,4 +146,41 @@ pub mod funcs {
 
 		Ok(obj.del_attr(args[0])?.unwrap_or_default())
 	}
+
+	pub fn __attrs__(obj: Value, args: Args<'_>) -> Result<Value> {
+		args.assert_no_arguments()?;
+
+		let names = obj.own_attributes()?.into_iter().map(|(name, _)| name).collect::<Vec<_>>();
+		Ok(List::from_slice(&names).to_value())
+	}
+
+	pub fn __all_attrs__(obj: Value, args: Args<'_>) -> Result<Value> {
+		args.assert_no_arguments()?;
+
+		let names = obj.all_attributes()?.into_iter().map(|(name, _)| name).collect::<Vec<_>>();
+		Ok(List::from_slice(&names).to_value())
+	}
+
+	pub fn __ancestors__(obj: Value, args: Args<'_>) -> Result<Value> {
+		args.assert_no_arguments()?;
+
+		Ok(List::from_slice(&obj.ancestors()?).to_value())
+	}
+
+	pub fn __mro__(obj: Value, args: Args<'_>) -> Result<Value> {
+		args.assert_no_arguments()?;
+
+		Ok(List::from_slice(&obj.mro()?).to_value())
+	}
+
+	pub fn __super__(obj: Value, args: Args<'_>) -> Result<Value> {
+		args.assert_no_keyword()?;
+		args.assert_positional_len(2)?;
+
+		let attr = obj
+			.get_super_unbound_attr(args[0], args[1])?
+			.ok_or_else(|| ErrorKind::UnknownAttribute { object: obj, attribute: args[1] })?;
+
+		crate::value::attributed::bind(obj, attr)
+	}
 }
@@ -0,0 +1,353 @@
+//! Ranges of integers within Quest.
+
+use crate::value::ty::{InstanceOf, Integer, Singleton, Text};
+use crate::value::{Gc, ToValue};
+use crate::vm::Args;
+use crate::{ErrorKind, Result, Value};
+use std::fmt::{self, Debug, Display, Formatter};
+
+quest_type! {
+	/// A (possibly stepped) range of [`Integer`]s.
+	///
+	/// Ranges are created in Quest via `start..end` (which excludes `end`) and `start..=end` (which
+	/// includes it). Unlike iterators, ranges can be iterated over any number of times, and they're
+	/// also used to slice [`List`](crate::value::ty::List)s and [`Text`]s.
+	///
+	/// # Examples
+	/// ```
+	/// # use quest::value::ty::Range;
+	/// let range = Range::new(1, 10, false);
+	/// let rangeref = range.as_ref().unwrap();
+	///
+	/// assert_eq!(rangeref.len().unwrap(), 9);
+	/// assert!(rangeref.includes(9));
+	/// assert!(!rangeref.includes(10));
+	/// assert_eq!(rangeref.iter().sum::<i64>(), 45);
+	/// ```
+	#[derive(NamedType)]
+	pub struct Range(Inner);
+}
+
+#[doc(hidden)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Inner {
+	start: i64,
+	end: i64,
+	step: i64,
+	inclusive: bool,
+}
+
+impl Range {
+	/// Creates a new `Range` from `start` to `end`, with a step of one.
+	#[must_use]
+	pub fn new(start: i64, end: i64, inclusive: bool) -> Gc<Self> {
+		Self::from_inner(Inner { start, end, step: 1, inclusive })
+	}
+
+	/// Creates a new `Range` with the given `step`, returning an error if `step` is zero.
+	pub fn with_step(start: i64, end: i64, step: i64, inclusive: bool) -> Result<Gc<Self>> {
+		if step == 0 {
+			return Err("range step cannot be zero".to_string().into());
+		}
+
+		Ok(Self::from_inner(Inner { start, end, step, inclusive }))
+	}
+
+	fn from_inner(inner: Inner) -> Gc<Self> {
+		use crate::value::base::{Base, HasDefaultParent};
+
+		Base::new(inner, Self::parent())
+	}
+
+	fn inner(&self) -> &Inner {
+		self.0.data()
+	}
+
+	/// The first value of the range.
+	#[must_use]
+	pub fn start(&self) -> i64 {
+		self.inner().start
+	}
+
+	/// The end bound of the range, which is only a member of `self` if it's [inclusive](
+	/// Self::is_inclusive).
+	#[must_use]
+	pub fn end(&self) -> i64 {
+		self.inner().end
+	}
+
+	/// The distance between successive elements; never zero.
+	#[must_use]
+	pub fn step(&self) -> i64 {
+		self.inner().step
+	}
+
+	/// Whether [`end`](Self::end) is included in the range.
+	#[must_use]
+	pub fn is_inclusive(&self) -> bool {
+		self.inner().inclusive
+	}
+
+	/// Gets the amount of integers within `self`.
+	///
+	/// This returns an `Err` if there are too many integers to fit in a `usize`.
+	pub fn len(&self) -> Result<usize> {
+		usize::try_from(self.inner().count()).map_err(|_| too_long(*self.inner()))
+	}
+
+	/// Returns whether there are no integers within `self`.
+	#[must_use]
+	pub fn is_empty(&self) -> bool {
+		self.inner().count() == 0
+	}
+
+	/// Checks to see if `int` would be yielded when iterating over `self`.
+	#[must_use]
+	pub fn includes(&self, int: i64) -> bool {
+		self.inner().includes(int)
+	}
+
+	/// Returns an iterator over the integers within `self`.
+	pub fn iter(&self) -> impl std::iter::Iterator<Item = i64> {
+		self.inner().iter()
+	}
+
+	/// Gets the indices `self` selects out of a container of length `len`.
+	///
+	/// Just like when indexing with integers, negative bounds are relative to the end of the
+	/// container. Any indices that are out of bounds are ignored.
+	#[must_use]
+	pub fn slice_indices(&self, len: usize) -> Vec<usize> {
+		let Inner { start, end, step, inclusive } = *self.inner();
+		let len = len as i64;
+		let resolve = |n: i64| if n < 0 { n + len } else { n };
+
+		Inner { start: resolve(start), end: resolve(end), step, inclusive }
+			.iter_within(len)
+			.map(|n| n as usize)
+			.collect()
+	}
+}
+
+fn too_long(range: Inner) -> crate::Error {
+	ErrorKind::Message(format!(
+		"range {start}..{end} is too long",
+		start = range.start,
+		end = range.end
+	))
+	.into()
+}
+
+impl Inner {
+	// The amount of integers within the range. Unlike `usize`, this can't overflow.
+	fn count(self) -> u128 {
+		let (start, end, step) =
+			(i128::from(self.start), i128::from(self.end), i128::from(self.step));
+
+		// The distance from `start` to the last possible element, in the direction of `step`.
+		let distance = if step.is_positive() { end - start } else { start - end };
+		let distance = if self.inclusive { distance } else { distance - 1 };
+
+		if distance < 0 {
+			0
+		} else {
+			(distance / step.abs() + 1) as u128
+		}
+	}
+
+	fn includes(self, int: i64) -> bool {
+		let Self { start, end, step, inclusive } = self;
+
+		let in_bounds = match (step.is_positive(), inclusive) {
+			(true, true) => start <= int && int <= end,
+			(true, false) => start <= int && int < end,
+			(false, true) => end <= int && int <= start,
+			(false, false) => end < int && int <= start,
+		};
+
+		in_bounds && (i128::from(int) - i128::from(start)) % i128::from(step) == 0
+	}
+
+	fn iter(self) -> impl std::iter::Iterator<Item = i64> {
+		let (start, step) = (i128::from(self.start), i128::from(self.step));
+
+		// Every element is between `start` and `end`, so converting back to `i64` is lossless.
+		(0..self.count()).map(move |n| (start + n as i128 * step) as i64)
+	}
+
+	// Like `iter`, except it only yields the values within `0..len`.
+	fn iter_within(self, len: i64) -> impl std::iter::Iterator<Item = i64> {
+		let Self { mut start, step, .. } = self;
+
+		// Skip ahead to the first element that's in bounds, so we don't iterate over huge ranges.
+		if step.is_positive() && start < 0 {
+			start += (-start + step - 1) / step * step;
+		} else if step.is_negative() && len <= start {
+			start += (start - len + 1 - step - 1) / -step * step;
+		}
+
+		Self { start, ..self }.iter().take_while(move |&n| 0 <= n && n < len)
+	}
+}
+
+impl Display for Range {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		let Inner { start, end, step, inclusive } = *self.inner();
+
+		if step != 1 {
+			f.write_str("(")?;
+		}
+
+		write!(f, "{start}{}{end}", if inclusive { "..=" } else { ".." })?;
+
+		if step != 1 {
+			write!(f, ").step({step})")?;
+		}
+
+		Ok(())
+	}
+}
+
+impl Debug for Range {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		if f.alternate() {
+			write!(f, "Range({self})")
+		} else {
+			Display::fmt(self, f)
+		}
+	}
+}
+
+pub mod funcs {
+	use super::*;
+
+	pub fn len(range: Gc<Range>, args: Args<'_>) -> Result<Value> {
+		args.assert_no_arguments()?;
+
+		let rangeref = range.as_ref()?;
+		let len = rangeref.len()?;
+
+		i64::try_from(len)
+			.ok()
+			.and_then(Integer::new)
+			.map(ToValue::to_value)
+			.ok_or_else(|| too_long(*rangeref.inner()))
+	}
+
+	pub fn is_empty(range: Gc<Range>, args: Args<'_>) -> Result<Value> {
+		args.assert_no_arguments()?;
+
+		Ok(range.as_ref()?.is_empty().to_value())
+	}
+
+	pub fn includes(range: Gc<Range>, args: Args<'_>) -> Result<Value> {
+		args.assert_no_keyword()?;
+		args.assert_positional_len(1)?;
+
+		// Non-integers are never within a range.
+		let is_included = match args[0].downcast::<Integer>() {
+			Some(int) => range.as_ref()?.includes(int.get()),
+			None => false,
+		};
+
+		Ok(is_included.to_value())
+	}
+
+	pub fn eql(range: Gc<Range>, args: Args<'_>) -> Result<Value> {
+		args.assert_no_keyword()?;
+		args.assert_positional_len(1)?;
+
+		if let Some(rhs) = args[0].downcast::<Gc<Range>>() {
+			Ok((range.as_ref()?.inner() == rhs.as_ref()?.inner()).to_value())
+		} else {
+			Ok(false.to_value())
+		}
+	}
+
+	pub fn hash(range: Gc<Range>, args: Args<'_>) -> Result<Value> {
+		use std::hash::{Hash, Hasher};
+
+		args.assert_no_arguments()?;
+
+		let mut hasher = std::collections::hash_map::DefaultHasher::new();
+		range.as_ref()?.inner().hash(&mut hasher);
+		Ok(crate::value::compare::hash_to_value(hasher.finish()))
+	}
+
+	pub fn step(range: Gc<Range>, args: Args<'_>) -> Result<Value> {
+		args.assert_no_keyword()?;
+		args.assert_positional_len(1)?;
+
+		let step = args[0].try_downcast::<Integer>()?.get();
+		let rangeref = range.as_ref()?;
+
+		Ok(
+			Range::with_step(rangeref.start(), rangeref.end(), step, rangeref.is_inclusive())?
+				.to_value(),
+		)
+	}
+
+	pub fn start(range: Gc<Range>, args: Args<'_>) -> Result<Value> {
+		args.assert_no_arguments()?;
+
+		Ok(range.as_ref()?.start().to_value())
+	}
+
+	pub fn end(range: Gc<Range>, args: Args<'_>) -> Result<Value> {
+		args.assert_no_arguments()?;
+
+		Ok(range.as_ref()?.end().to_value())
+	}
+
+	pub fn iter(range: Gc<Range>, args: Args<'_>) -> Result<Value> {
+		args.assert_no_arguments()?;
+
+		let mut iter = range.as_ref()?.iter();
+
+		Ok(crate::iterator! { "Range::iter";
+			iter.next().map(ToValue::to_value).ok_or_else(|| ErrorKind::StopIteration.into())
+		}
+		.to_value())
+	}
+
+	pub fn to_text(range: Gc<Range>, args: Args<'_>) -> Result<Value> {
+		args.assert_no_arguments()?;
+
+		Ok(Text::from_string(range.as_ref()?.to_string()).to_value())
+	}
+
+	pub fn dbg(range: Gc<Range>, args: Args<'_>) -> Result<Value> {
+		to_text(range, args)
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct RangeClass;
+
+impl Singleton for RangeClass {
+	fn instance() -> crate::Value {
+		use once_cell::sync::OnceCell;
+
+		static INSTANCE: OnceCell<crate::Value> = OnceCell::new();
+
+		*INSTANCE.get_or_init(|| {
+			create_class! { "Range", parent Iterable::instance();
+				Intern::op_eql => method funcs::eql,
+				Intern::hash => method funcs::hash,
+				Intern::len => method funcs::len,
+				Intern::is_empty => method funcs::is_empty,
+				Intern::includes => method funcs::includes,
+				Intern::step => method funcs::step,
+				Intern::start => method funcs::start,
+				Intern::end => method funcs::end,
+				Intern::iter => method funcs::iter,
+				Intern::to_text => method funcs::to_text,
+				Intern::dbg => method funcs::dbg,
+			}
+		})
+	}
+}
+
+impl InstanceOf for Range {
+	type Parent = RangeClass;
+}
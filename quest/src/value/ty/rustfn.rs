@@ -1,8 +1,8 @@
 use crate::value::ty::{InstanceOf, Singleton};
 use crate::value::{Callable, Convertible};
-use crate::vm::Args;
-use crate::{Result, Value};
-use std::fmt::{self, Debug, Formatter};
+use crate::vm::{profiler, Args};
+use crate::{ErrorKind, Result, Value};
+use std::fmt::{self, Debug, Display, Formatter};
 
 pub type Function = for<'a> fn(Args<'a>) -> Result<Value>;
 
@@ -15,6 +15,97 @@ pub struct RustFn(&'static Inner);
 pub struct Inner {
 	pub name: &'static str,
 	pub func: Function,
+	pub signature: Option<&'static Signature>,
+}
+
+/// The parameters a [`RustFn`] accepts, as generated by `#[quest_fn]`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Signature {
+	/// Whether the first parameter is `this` (or the first positional argument, if there's no
+	/// `this`).
+	pub this: bool,
+	/// The names of the positional parameters that must be given.
+	pub required: &'static [&'static str],
+	/// The names of the positional parameters that may be given, after the `required` ones.
+	pub optional: &'static [&'static str],
+	/// The names of the keyword parameters that must be given.
+	pub required_keyword: &'static [&'static str],
+	/// The names of the keyword parameters that may be given.
+	pub optional_keyword: &'static [&'static str],
+	/// The name of the parameter that collects any remaining positional arguments, if any.
+	pub rest: Option<&'static str>,
+	/// The documentation of the function.
+	pub doc: &'static str,
+}
+
+impl Signature {
+	/// Gets the minimum and maximum (if any) amount of positional arguments, excluding `this`.
+	#[must_use]
+	pub const fn arity(&self) -> (usize, Option<usize>) {
+		let min = self.required.len();
+
+		if self.rest.is_some() {
+			(min, None)
+		} else {
+			(min, Some(min + self.optional.len()))
+		}
+	}
+
+	/// Checks that `args` (after `this` has been removed) match `self`.
+	///
+	/// # Errors
+	/// Returns an error if too few or too many positional arguments were given, if an unknown
+	/// keyword argument was given, or if a required keyword argument is missing.
+	pub fn check(&self, args: Args<'_>) -> Result<()> {
+		let given = args.positional().len();
+		let (min, max) = self.arity();
+
+		if given < min {
+			return Err(ErrorKind::PositionalArgumentMismatch { given, expected: min }.into());
+		}
+
+		if let Some(max) = max.filter(|&max| max < given) {
+			return Err(ErrorKind::PositionalArgumentMismatch { given, expected: max }.into());
+		}
+
+		if self.required_keyword.is_empty() && self.optional_keyword.is_empty() {
+			return args.assert_no_keyword();
+		}
+
+		for &(keyword, _) in args.keyword() {
+			if !self.required_keyword.contains(&keyword) && !self.optional_keyword.contains(&keyword) {
+				return Err(ErrorKind::Message(format!("unknown keyword argument '{keyword}'")).into());
+			}
+		}
+
+		for &keyword in self.required_keyword {
+			if args.get(keyword).is_none() {
+				return Err(ErrorKind::Message(format!("missing keyword argument '{keyword}'")).into());
+			}
+		}
+
+		Ok(())
+	}
+}
+
+/// Writes the parameter list, eg `(a, [b], c:, [d:], *e)`.
+impl Display for Signature {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		let required = self.required.iter().map(|name| name.to_string());
+		let optional = self.optional.iter().map(|name| format!("[{name}]"));
+		let required_keyword = self.required_keyword.iter().map(|name| format!("{name}:"));
+		let optional_keyword = self.optional_keyword.iter().map(|name| format!("[{name}:]"));
+		let rest = self.rest.iter().map(|name| format!("*{name}"));
+
+		let params = required
+			.chain(optional)
+			.chain(required_keyword)
+			.chain(optional_keyword)
+			.chain(rest)
+			.collect::<Vec<_>>();
+
+		write!(f, "({})", params.join(", "))
+	}
 }
 
 #[macro_export]
@@ -35,6 +126,7 @@ macro_rules! RustFn_new {
 		const INNER: &'static $crate::value::ty::rustfn::Inner = &$crate::value::ty::rustfn::Inner {
 			name: $name,
 			func: $func,
+			signature: None,
 		};
 
 		$crate::value::ty::RustFn::new(INNER)
@@ -43,6 +135,7 @@ macro_rules! RustFn_new {
 		const INNER: &'static $crate::value::ty::rustfn::Inner = &$crate::value::ty::rustfn::Inner {
 			name: $name.as_str_const(),
 			func: $func,
+			signature: None,
 		};
 
 		$crate::value::ty::RustFn::new(INNER)
@@ -72,12 +165,28 @@ impl RustFn {
 	pub fn func(self) -> Function {
 		self.0.func
 	}
+
+	/// Gets the parameters `self` accepts, if they're known (ie if it was made via `#[quest_fn]`).
+	#[must_use]
+	pub const fn signature(self) -> Option<&'static Signature> {
+		self.0.signature
+	}
+
+	/// Gets a description of how to call `self`, including its documentation if it has any.
+	#[must_use]
+	pub fn help(self) -> String {
+		match self.signature() {
+			Some(signature) if signature.doc.is_empty() => format!("{}{signature}", self.name()),
+			Some(signature) => format!("{}{signature}\n\n{}", self.name(), signature.doc),
+			None => format!("{}(...)", self.name()),
+		}
+	}
 }
 
 impl Callable for RustFn {
 	#[inline]
 	fn call(self, args: Args<'_>) -> Result<Value> {
-		(self.0.func)(args)
+		profiler::profile(|| profiler::Function::RustFn(self), || (self.0.func)(args))
 	}
 }
 
@@ -88,6 +197,12 @@ impl PartialEq for RustFn {
 	}
 }
 
+impl std::hash::Hash for RustFn {
+	fn hash<H: std::hash::Hasher>(&self, h: &mut H) {
+		std::ptr::hash(self.0, h);
+	}
+}
+
 impl RustFn {
 	pub const NOOP: Self = RustFn_new!("noop", justargs | _ | Ok(Value::default()));
 }
@@ -138,6 +253,12 @@ pub mod funcs {
 
 		Ok(builder.finish().to_value())
 	}
+
+	pub fn help(func: RustFn, args: Args<'_>) -> Result<Value> {
+		args.assert_no_arguments()?;
+
+		Ok(func.help().to_value())
+	}
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
@@ -153,6 +274,7 @@ impl Singleton for RustFnClass {
 			create_class! { "RustFn", parent Callable::instance();
 				Intern::op_call => method funcs::call,
 				Intern::dbg => method funcs::dbg,
+				Intern::help => method funcs::help,
 			}
 		})
 	}
@@ -184,4 +306,27 @@ mod tests {
 	fn test_get() {
 		assert_eq!(RustFn::NOOP, RustFn::get(Value::from(RustFn::NOOP)));
 	}
+
+	#[test]
+	fn signature() {
+		let signature = Signature {
+			required: &["a"],
+			optional: &["b"],
+			optional_keyword: &["c"],
+			rest: Some("d"),
+			..Signature::default()
+		};
+
+		assert_eq!(signature.to_string(), "(a, [b], [c:], *d)");
+		assert_eq!(signature.arity(), (1, None));
+
+		let one = [Value::ONE.to_value()];
+		assert!(signature.check(Args::new(&one, &[])).is_ok());
+		assert!(signature.check(Args::new(&one, &[("c", one[0])])).is_ok());
+		assert!(signature.check(Args::new(&one, &[("e", one[0])])).is_err());
+		assert!(signature.check(Args::new(&[], &[])).is_err());
+
+		assert_eq!(RustFn::NOOP.signature(), None);
+		assert_eq!(RustFn::NOOP.help(), "noop(...)");
+	}
 }
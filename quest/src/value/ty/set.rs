@@ -0,0 +1,425 @@
+//! The set type within Quest.
+
+use super::map::{lookup, Hashed};
+use crate::value::ty::{InstanceOf, List, Singleton, Text};
+use crate::value::{Gc, ToValue};
+use crate::vm::Args;
+use crate::{Result, Value};
+use hashbrown::hash_map::RawEntryMut;
+use hashbrown::HashMap;
+use std::fmt::{self, Debug, Formatter};
+
+quest_type! {
+	/// An unordered collection of unique [`Value`]s.
+	///
+	/// Just like [`Map`](crate::value::ty::Map) keys, elements are compared via [`Value::try_hash`]
+	/// and [`Value::try_eq`], so anything which defines `hash` and `==` can be stored in a set.
+	///
+	/// # Examples
+	/// ```
+	/// # use quest::value::ty::Set;
+	/// # use quest::value::ToValue;
+	/// let set = Set::new();
+	/// let mut setmut = set.as_mut().unwrap();
+	///
+	/// assert!(setmut.insert("foo".to_value()).unwrap());
+	/// assert!(!setmut.insert("foo".to_value()).unwrap());
+	/// assert!(!setmut.contains(12.to_value()).unwrap());
+	/// assert_eq!(setmut.len(), 1);
+	/// ```
+	#[derive(NamedType)]
+	pub struct Set(Inner);
+}
+
+#[doc(hidden)]
+pub struct Inner {
+	elements: HashMap<Hashed, ()>,
+}
+
+impl Set {
+	/// Creates a new, empty `Set`.
+	#[must_use]
+	pub fn new() -> Gc<Self> {
+		Self::with_capacity(0)
+	}
+
+	/// Creates a new, empty `Set` with space for at least `capacity` elements.
+	#[must_use]
+	pub fn with_capacity(capacity: usize) -> Gc<Self> {
+		use crate::value::base::{Base, HasDefaultParent};
+
+		Base::new(Inner { elements: HashMap::with_capacity(capacity) }, Self::parent())
+	}
+
+	/// Creates a new `Set` from the elements of `iter`, discarding duplicates.
+	pub fn from_values(iter: impl IntoIterator<Item = Value>) -> Result<Gc<Self>> {
+		let iter = iter.into_iter();
+		let set = Self::with_capacity(iter.size_hint().0);
+
+		{
+			let mut setmut = set.as_mut()?;
+			for element in iter {
+				setmut.insert(element)?;
+			}
+		}
+
+		Ok(set)
+	}
+
+	fn inner(&self) -> &Inner {
+		self.0.data()
+	}
+
+	fn inner_mut(&mut self) -> &mut Inner {
+		self.0.data_mut()
+	}
+
+	/// Gets the amount of elements within `self`.
+	#[must_use]
+	pub fn len(&self) -> usize {
+		self.inner().elements.len()
+	}
+
+	/// Returns whether `self` has no elements.
+	#[must_use]
+	pub fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+
+	/// Checks to see if `element` is within `self`.
+	pub fn contains(&self, element: Value) -> Result<bool> {
+		let hash = element.try_hash()?;
+
+		Ok(lookup!(self.inner().elements, raw_entry, hash, element).is_some())
+	}
+
+	/// Adds `element` to `self`, returning whether it was newly inserted.
+	pub fn insert(&mut self, element: Value) -> Result<bool> {
+		let hash = element.try_hash()?;
+
+		match lookup!(self.inner_mut().elements, raw_entry_mut, hash, element) {
+			RawEntryMut::Occupied(_) => Ok(false),
+			RawEntryMut::Vacant(vac) => {
+				vac.insert_with_hasher(hash, Hashed { hash, value: element }, (), |k| k.hash);
+
+				Ok(true)
+			}
+		}
+	}
+
+	/// Removes `element` from `self`, returning whether it was present.
+	pub fn remove(&mut self, element: Value) -> Result<bool> {
+		let hash = element.try_hash()?;
+
+		match lookup!(self.inner_mut().elements, raw_entry_mut, hash, element) {
+			RawEntryMut::Occupied(occ) => {
+				occ.remove();
+				Ok(true)
+			}
+			RawEntryMut::Vacant(_) => Ok(false),
+		}
+	}
+
+	/// Returns an iterator over the elements of `self`, in an unspecified order.
+	pub fn iter(&self) -> impl std::iter::Iterator<Item = Value> + '_ {
+		self.inner().elements.keys().map(|element| element.value)
+	}
+
+	/// Creates a (shallow) copy of `self`.
+	#[must_use]
+	pub fn dup(&self) -> Gc<Self> {
+		use crate::value::base::{Base, HasDefaultParent};
+
+		Base::new(Inner { elements: self.inner().elements.clone() }, Self::parent())
+	}
+
+	/// Checks to see if every element of `self` is also in `rhs`.
+	pub fn is_subset(&self, rhs: &Self) -> Result<bool> {
+		if rhs.len() < self.len() {
+			return Ok(false);
+		}
+
+		for element in self.iter() {
+			if !rhs.contains(element)? {
+				return Ok(false);
+			}
+		}
+
+		Ok(true)
+	}
+}
+
+impl Debug for Set {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		if f.alternate() {
+			f.write_str("Set(")?;
+		}
+
+		f.debug_set().entries(self.iter()).finish()?;
+
+		if f.alternate() {
+			f.write_str(")")?;
+		}
+
+		Ok(())
+	}
+}
+
+pub mod funcs {
+	use super::*;
+
+	// Copies out `value`'s elements; `value` may be the same set as the one we're building.
+	fn elements_of(value: Value) -> Result<Vec<Value>> {
+		Ok(value.try_downcast::<Gc<Set>>()?.as_ref()?.iter().collect())
+	}
+
+	pub fn len(set: Gc<Set>, args: Args<'_>) -> Result<Value> {
+		args.assert_no_arguments()?;
+
+		Ok((set.as_ref()?.len() as i64).to_value())
+	}
+
+	pub fn is_empty(set: Gc<Set>, args: Args<'_>) -> Result<Value> {
+		args.assert_no_arguments()?;
+
+		Ok(set.as_ref()?.is_empty().to_value())
+	}
+
+	pub fn eql(set: Gc<Set>, args: Args<'_>) -> Result<Value> {
+		args.assert_no_keyword()?;
+		args.assert_positional_len(1)?;
+
+		if set.to_value().is_identical(args[0]) {
+			return Ok(true.to_value());
+		}
+
+		let rhs = if let Some(rhs) = args[0].downcast::<Gc<Set>>() {
+			rhs
+		} else {
+			return Ok(false.to_value());
+		};
+
+		let lhsref = set.as_ref()?;
+		let rhsref = rhs.as_ref()?;
+
+		Ok((lhsref.len() == rhsref.len() && lhsref.is_subset(&rhsref)?).to_value())
+	}
+
+	/// Hashes the elements of `set`, regardless of their order, so that sets which are `==` have
+	/// the same hash.
+	pub fn hash(set: Gc<Set>, args: Args<'_>) -> Result<Value> {
+		use crate::value::compare;
+
+		args.assert_no_arguments()?;
+
+		// Elements' hashes are cached when they're inserted, so this never calls into Quest code,
+		// and thus can't recurse into `set` even if it contains itself.
+		let hash = compare::hash_unordered(set.as_ref()?.inner().elements.keys().map(|e| e.hash));
+		Ok(compare::hash_to_value(hash))
+	}
+
+	pub fn add(set: Gc<Set>, args: Args<'_>) -> Result<Value> {
+		args.assert_no_keyword()?;
+		args.assert_positional_len(1)?;
+
+		set.as_mut()?.insert(args[0])?;
+
+		Ok(set.to_value())
+	}
+
+	pub fn delete(set: Gc<Set>, args: Args<'_>) -> Result<Value> {
+		args.assert_no_keyword()?;
+		args.assert_positional_len(1)?;
+
+		Ok(set.as_mut()?.remove(args[0])?.to_value())
+	}
+
+	pub fn includes(set: Gc<Set>, args: Args<'_>) -> Result<Value> {
+		args.assert_no_keyword()?;
+		args.assert_positional_len(1)?;
+
+		Ok(set.as_ref()?.contains(args[0])?.to_value())
+	}
+
+	pub fn union(set: Gc<Set>, args: Args<'_>) -> Result<Value> {
+		args.assert_no_keyword()?;
+		args.assert_positional_len(1)?;
+
+		let rhs = elements_of(args[0])?;
+		let result = set.as_ref()?.dup();
+
+		{
+			let mut resultmut = result.as_mut()?;
+			for element in rhs {
+				resultmut.insert(element)?;
+			}
+		}
+
+		Ok(result.to_value())
+	}
+
+	pub fn intersection(set: Gc<Set>, args: Args<'_>) -> Result<Value> {
+		args.assert_no_keyword()?;
+		args.assert_positional_len(1)?;
+
+		let rhs = elements_of(args[0])?;
+		let setref = set.as_ref()?;
+		let result = Set::new();
+
+		{
+			let mut resultmut = result.as_mut()?;
+			for element in rhs {
+				if setref.contains(element)? {
+					resultmut.insert(element)?;
+				}
+			}
+		}
+
+		Ok(result.to_value())
+	}
+
+	pub fn difference(set: Gc<Set>, args: Args<'_>) -> Result<Value> {
+		args.assert_no_keyword()?;
+		args.assert_positional_len(1)?;
+
+		let rhs = elements_of(args[0])?;
+		let result = set.as_ref()?.dup();
+
+		{
+			let mut resultmut = result.as_mut()?;
+			for element in rhs {
+				resultmut.remove(element)?;
+			}
+		}
+
+		Ok(result.to_value())
+	}
+
+	pub fn symmetric_difference(set: Gc<Set>, args: Args<'_>) -> Result<Value> {
+		args.assert_no_keyword()?;
+		args.assert_positional_len(1)?;
+
+		let rhs = elements_of(args[0])?;
+		let result = set.as_ref()?.dup();
+
+		{
+			let mut resultmut = result.as_mut()?;
+			for element in rhs {
+				if !resultmut.remove(element)? {
+					resultmut.insert(element)?;
+				}
+			}
+		}
+
+		Ok(result.to_value())
+	}
+
+	pub fn is_subset(set: Gc<Set>, args: Args<'_>) -> Result<Value> {
+		args.assert_no_keyword()?;
+		args.assert_positional_len(1)?;
+
+		let lhs = set.as_ref()?;
+		let rhs = args[0].try_downcast::<Gc<Set>>()?.as_ref()?;
+
+		Ok(lhs.is_subset(&rhs)?.to_value())
+	}
+
+	pub fn is_superset(set: Gc<Set>, args: Args<'_>) -> Result<Value> {
+		args.assert_no_keyword()?;
+		args.assert_positional_len(1)?;
+
+		let lhs = set.as_ref()?;
+		let rhs = args[0].try_downcast::<Gc<Set>>()?.as_ref()?;
+
+		Ok(rhs.is_subset(&lhs)?.to_value())
+	}
+
+	pub fn dup(set: Gc<Set>, args: Args<'_>) -> Result<Value> {
+		args.assert_no_arguments()?;
+
+		Ok(set.as_ref()?.dup().to_value())
+	}
+
+	pub fn to_list(set: Gc<Set>, args: Args<'_>) -> Result<Value> {
+		args.assert_no_arguments()?;
+
+		Ok(List::from_slice(&set.as_ref()?.iter().collect::<Vec<_>>()).to_value())
+	}
+
+	pub fn iter(set: Gc<Set>, args: Args<'_>) -> Result<Value> {
+		args.assert_no_arguments()?;
+
+		// Sets can be modified during iteration, so we iterate over a snapshot of the elements.
+		let mut elements = set.as_ref()?.iter().collect::<Vec<_>>().into_iter();
+
+		Ok(crate::iterator! { "Set::iter";
+			elements.next().ok_or_else(|| StopIteration.into())
+		}
+		.to_value())
+	}
+
+	pub fn to_text(set: Gc<Set>, args: Args<'_>) -> Result<Value> {
+		args.assert_no_arguments()?;
+
+		let mut builder = Text::simple_builder();
+		builder.push_str("Set([");
+
+		for (i, element) in set.as_ref()?.iter().enumerate() {
+			if i != 0 {
+				builder.push_str(", ");
+			}
+
+			builder.push_str(element.dbg_text()?.as_ref()?.as_str());
+		}
+
+		builder.push_str("])");
+
+		Ok(builder.finish().to_value())
+	}
+
+	pub fn dbg(set: Gc<Set>, args: Args<'_>) -> Result<Value> {
+		to_text(set, args)
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct SetClass;
+
+impl Singleton for SetClass {
+	fn instance() -> crate::Value {
+		use once_cell::sync::OnceCell;
+
+		static INSTANCE: OnceCell<crate::Value> = OnceCell::new();
+
+		*INSTANCE.get_or_init(|| {
+			create_class! { "Set", parent Iterable::instance();
+				Intern::op_eql => method funcs::eql,
+				Intern::hash => method funcs::hash,
+				Intern::op_bitor => method funcs::union,
+				Intern::op_bitand => method funcs::intersection,
+				Intern::op_sub => method funcs::difference,
+				Intern::op_bitxor => method funcs::symmetric_difference,
+				Intern::union => method funcs::union,
+				Intern::intersection => method funcs::intersection,
+				Intern::difference => method funcs::difference,
+				Intern::symmetric_difference => method funcs::symmetric_difference,
+				Intern::is_subset => method funcs::is_subset,
+				Intern::is_superset => method funcs::is_superset,
+				Intern::len => method funcs::len,
+				Intern::is_empty => method funcs::is_empty,
+				Intern::add => method funcs::add,
+				Intern::delete => method funcs::delete,
+				Intern::includes => method funcs::includes,
+				Intern::dup => method funcs::dup,
+				Intern::to_list => method funcs::to_list,
+				Intern::iter => method funcs::iter,
+				Intern::to_text => method funcs::to_text,
+				Intern::dbg => method funcs::dbg,
+			}
+		})
+	}
+}
+
+impl InstanceOf for Set {
+	type Parent = SetClass;
+}
@@ -0,0 +1,483 @@
+//! Cooperative tasks, which run concurrently on a single thread.
+//!
+//! A [`Task`] is created via `Task.spawn(func, args...)`, which calls `func` with `args` on the
+//! current thread's [event loop](crate::vm::event_loop). Within a task, `await(task)` (or
+//! `task.await()`) suspends the task until `task` has finished, letting other tasks run in the
+//! meantime, and then returns `task`'s result. Outside of a task, `await` instead runs the event
+//! loop until `task` has finished. Awaiting anything that isn't a task simply returns it.
+//!
+//! Each task runs on its own [coroutine](crate::vm::coroutine), so `await` can be used anywhere
+//! within it, including within nested blocks such as a `while` loop's body or an `each` callback.
+//! The one exception is within a generator that the task is running, as only the generator could be
+//! suspended there. Coroutines' stacks are only
+//! [`coroutine_stack_size`](crate::vm::Limits::coroutine_stack_size) bytes, so code within a task
+//! can't recurse as deeply as code outside of one.
+//!
+//! # Timers
+//! - `sleep(seconds)` awaits `Task.sleep(seconds)`, a task which finishes after `seconds`.
+//! - `Task.after(seconds, func, args...)` calls `func` with `args` after `seconds`.
+//! - `Task.every(seconds, func, args...)` calls `func` with `args` every `seconds`, until it's
+//!   cancelled or `func` raises an error.
+//!
+//! # Combining tasks
+//! - `Task.all(tasks)` finishes with a list of every task's result once they've all finished, or
+//!   with the first error one of them raises.
+//! - `Task.race(tasks)` finishes with the result of whichever task finishes first.
+//!
+//! Both take a list of tasks, and raise an error if it contains anything else.
+//!
+//! A task that's `cancel`led finishes with an error, and never runs again.
+
+use crate::value::ty::native::{Native, NativeType};
+use crate::value::ty::{class, Float, List};
+use crate::value::{Callable, Gc, ToValue};
+use crate::vm::coroutine::{self, Coroutine, Kind, Resumed};
+use crate::vm::event_loop::{self, Completer};
+use crate::vm::Args;
+use crate::{Error, ErrorKind, Intern, Result, Value};
+use std::sync::Mutex as StdMutex;
+use std::time::{Duration, Instant};
+
+fn error(message: &str) -> Error {
+	ErrorKind::Message(message.to_string()).into()
+}
+
+enum Body {
+	// Taken out while it's running, so it's `None` then.
+	Coroutine(Option<Coroutine>),
+	All(Vec<Gc<Native<Task>>>),
+	Race(Vec<Gc<Native<Task>>>),
+	Sleep,
+	External,
+}
+
+struct State {
+	body: Body,
+	result: Option<Result<Value>>,
+	waiters: Vec<Gc<Native<Task>>>,
+}
+
+/// A unit of work that's run by the current thread's [event loop](crate::vm::event_loop).
+pub struct Task {
+	state: StdMutex<State>,
+}
+
+impl Task {
+	fn create(body: Body) -> Result<Gc<Native<Self>>> {
+		let state = State { body, result: None, waiters: Vec::new() };
+		Native::new(Self { state: StdMutex::new(state) })
+	}
+
+	/// Creates a task which runs `body` on its own coroutine, so it can `await` other tasks.
+	fn coroutine(body: impl FnOnce() -> Result<Value> + 'static) -> Result<Gc<Native<Self>>> {
+		let task = Self::create(Body::Coroutine(Some(Coroutine::new(Kind::Task, body)?)))?;
+		event_loop::wake(task);
+		Ok(task)
+	}
+
+	/// Creates a task which calls `func` with `args`.
+	pub fn spawn(func: Value, args: Vec<Value>) -> Result<Gc<Native<Self>>> {
+		Self::coroutine(move || func.call(Args::new(&args, &[])))
+	}
+
+	/// Creates a task which finishes with `null` after `duration`.
+	pub fn sleep(duration: Duration) -> Result<Gc<Native<Self>>> {
+		let task = Self::create(Body::Sleep)?;
+		event_loop::wake_at(Instant::now() + duration, task);
+		Ok(task)
+	}
+
+	/// Creates a task which calls `func` with `args` after `duration`.
+	pub fn after(duration: Duration, func: Value, args: Vec<Value>) -> Result<Gc<Native<Self>>> {
+		let timer = Self::sleep(duration)?;
+
+		Self::coroutine(move || {
+			await_value(timer.to_value())?;
+			func.call(Args::new(&args, &[]))
+		})
+	}
+
+	/// Creates a task which calls `func` with `args` every `interval`.
+	///
+	/// The task only finishes once it's [cancelled](Self::cancel), or `func` returns an error.
+	pub fn every(interval: Duration, func: Value, args: Vec<Value>) -> Result<Gc<Native<Self>>> {
+		let mut timer = Self::sleep(interval)?;
+
+		Self::coroutine(move || loop {
+			await_value(timer.to_value())?;
+			// The next timer starts before calling `func`, so how long it takes doesn't add up.
+			timer = Self::sleep(interval)?;
+			func.call(Args::new(&args, &[]))?;
+		})
+	}
+
+	/// Creates a task which finishes with a list of `tasks`' results once they've all finished, or
+	/// with the first error any of them finishes with.
+	pub fn all(tasks: Vec<Gc<Native<Self>>>) -> Result<Gc<Native<Self>>> {
+		let task = Self::create(Body::All(tasks))?;
+		event_loop::wake(task);
+		Ok(task)
+	}
+
+	/// Creates a task which finishes with the result of whichever of `tasks` finishes first.
+	pub fn race(tasks: Vec<Gc<Native<Self>>>) -> Result<Gc<Native<Self>>> {
+		let task = Self::create(Body::Race(tasks))?;
+		event_loop::wake(task);
+		Ok(task)
+	}
+
+	/// Creates a task which is finished by the returned [`Completer`], for work that's done outside
+	/// of Quest.
+	///
+	/// Until it's completed, the event loop keeps [waiting](event_loop::Turn::Waiting) for it.
+	pub fn external() -> Result<(Gc<Native<Self>>, Completer)> {
+		let task = Self::create(Body::External)?;
+		Ok((task, event_loop::register_external(task)))
+	}
+
+	/// Returns the task's result, or `None` if it hasn't finished.
+	pub fn result(&self) -> Option<Result<Value>> {
+		self.state.lock().unwrap().result.clone()
+	}
+
+	/// Returns whether the task has finished.
+	pub fn is_finished(&self) -> bool {
+		self.state.lock().unwrap().result.is_some()
+	}
+
+	/// Cancels the task, so that it finishes with an error and never runs again.
+	///
+	/// If the task is suspended and this is called from another thread, its coroutine is unwound
+	/// later on, by the thread that created it.
+	///
+	/// Returns `false` if the task had already finished.
+	pub fn cancel(&self) -> bool {
+		let was_running = !self.is_finished();
+		self.complete(Err(error("task was cancelled")));
+		was_running
+	}
+
+	/// Finishes the task with `result`, waking up any tasks awaiting it. Does nothing if it's
+	/// already finished.
+	pub(crate) fn complete(&self, result: Result<Value>) {
+		let mut state = self.state.lock().unwrap();
+
+		if state.result.is_some() {
+			return;
+		}
+
+		if matches!(state.body, Body::External) {
+			event_loop::external_finished();
+		}
+
+		state.result = Some(result);
+		let waiters = std::mem::take(&mut state.waiters);
+		let suspended = match &mut state.body {
+			Body::Coroutine(coroutine) => coroutine.take(),
+			_ => None,
+		};
+		drop(state);
+
+		// Dropping a suspended coroutine unwinds its stack, which may run arbitrary code, so it's
+		// done without holding the lock.
+		drop(suspended);
+		waiters.into_iter().for_each(event_loop::wake);
+	}
+
+	/// Has `waiter` be woken up once `self` has finished.
+	fn add_waiter(&self, waiter: Gc<Native<Self>>) {
+		let mut state = self.state.lock().unwrap();
+
+		if state.result.is_some() {
+			drop(state);
+			event_loop::wake(waiter);
+		} else if !state.waiters.iter().any(|w| w.ptr_eq(waiter)) {
+			state.waiters.push(waiter);
+		}
+	}
+}
+
+/// Runs `task` until it finishes or has to wait for something.
+pub(crate) fn poll(task: Gc<Native<Task>>) {
+	let Ok(this) = task.as_ref() else {
+		return;
+	};
+
+	if let Err(err) = poll_body(task, &this) {
+		this.complete(Err(err));
+	}
+}
+
+fn poll_body(task: Gc<Native<Task>>, this: &Task) -> Result<()> {
+	let mut state = this.state.lock().unwrap();
+
+	if state.result.is_some() {
+		return Ok(());
+	}
+
+	match &mut state.body {
+		Body::Coroutine(coroutine) => {
+			// If it's already been taken, it's running, and has just woken itself up.
+			let Some(mut coroutine) = coroutine.take() else {
+				return Ok(());
+			};
+			drop(state);
+
+			match coroutine.resume(Value::default())? {
+				Resumed::Suspended(_) => {
+					let mut state = this.state.lock().unwrap();
+
+					// If it was cancelled while running, it's dropped (after unlocking) instead.
+					if state.result.is_none() {
+						state.body = Body::Coroutine(Some(coroutine));
+					}
+				}
+				Resumed::Finished(result) => this.complete(result),
+			}
+		}
+		Body::All(tasks) => {
+			let tasks = tasks.clone();
+			drop(state);
+			let mut results = Vec::with_capacity(tasks.len());
+
+			for child in tasks {
+				let child = child.as_ref()?;
+
+				match child.result() {
+					Some(result) => results.push(result?),
+					None => {
+						child.add_waiter(task);
+						return Ok(());
+					}
+				}
+			}
+
+			this.complete(Ok(List::from_slice(&results).to_value()));
+		}
+		Body::Race(tasks) => {
+			let tasks = tasks.clone();
+			drop(state);
+
+			for child in &tasks {
+				if let Some(result) = child.as_ref()?.result() {
+					this.complete(result);
+					return Ok(());
+				}
+			}
+
+			for child in tasks {
+				child.as_ref()?.add_waiter(task);
+			}
+		}
+		// Sleeps are only woken up by their timers.
+		Body::Sleep => {
+			drop(state);
+			this.complete(Ok(Value::default()));
+		}
+		Body::External => {}
+	}
+
+	Ok(())
+}
+
+/// Waits for `value` to finish if it's a task, returning its result. Other values are returned
+/// as-is.
+///
+/// Within a task, this suspends it until `value` has finished. Otherwise, this runs the
+/// [event loop](event_loop::block_on) until it has.
+pub fn await_value(value: Value) -> Result<Value> {
+	let Some(task) = value.downcast::<Gc<Native<Task>>>() else {
+		return Ok(value);
+	};
+
+	loop {
+		if let Some(result) = task.as_ref()?.result() {
+			return result;
+		}
+
+		match (coroutine::current(), event_loop::current_task()) {
+			(Some(Kind::Task), Some(current)) => {
+				task.as_ref()?.add_waiter(current);
+				coroutine::suspend(Value::default());
+			}
+			(_, Some(_)) => {
+				return Err(error("`await` can't be used within a generator that's running in a task"))
+			}
+			(_, None) => return event_loop::block_on(task),
+		}
+	}
+}
+
+/// Converts `seconds` into a [`Duration`].
+pub(crate) fn duration(seconds: Value) -> Result<Duration> {
+	Duration::try_from_secs_f64(seconds.convert::<Float>()?)
+		.map_err(|_| error("durations must be finite and non-negative"))
+}
+
+fn tasks(list: Value) -> Result<Vec<Gc<Native<Task>>>> {
+	let list = list.try_downcast::<Gc<List>>()?;
+	let tasks = list.as_ref()?.as_slice().to_vec();
+
+	tasks.into_iter().map(Value::try_downcast).collect()
+}
+
+/// Creates a task which calls `func` with the remaining arguments.
+#[quest_fn(name = "spawn")]
+fn task_spawn(
+	#[this] _class: Value,
+	func: Value,
+	#[rest] args: Vec<Value>,
+) -> Result<Gc<Native<Task>>> {
+	Task::spawn(func, args)
+}
+
+/// Creates a task which finishes with `null` after `seconds`.
+#[quest_fn(name = "sleep")]
+fn task_sleep(#[this] _class: Value, seconds: Value) -> Result<Gc<Native<Task>>> {
+	Task::sleep(duration(seconds)?)
+}
+
+/// Creates a task which calls `func` with the remaining arguments after `seconds`.
+#[quest_fn]
+fn after(
+	#[this] _class: Value,
+	seconds: Value,
+	func: Value,
+	#[rest] args: Vec<Value>,
+) -> Result<Gc<Native<Task>>> {
+	Task::after(duration(seconds)?, func, args)
+}
+
+/// Creates a task which calls `func` with the remaining arguments every `seconds`, until it's
+/// cancelled.
+#[quest_fn]
+fn every(
+	#[this] _class: Value,
+	seconds: Value,
+	func: Value,
+	#[rest] args: Vec<Value>,
+) -> Result<Gc<Native<Task>>> {
+	Task::every(duration(seconds)?, func, args)
+}
+
+/// Creates a task which finishes with a list of the results of the list of `tasks`, once they've
+/// all finished.
+#[quest_fn]
+fn all(#[this] _class: Value, tasks: Value) -> Result<Gc<Native<Task>>> {
+	Task::all(self::tasks(tasks)?)
+}
+
+/// Creates a task which finishes with the result of whichever of the list of `tasks` finishes
+/// first.
+#[quest_fn]
+fn race(#[this] _class: Value, tasks: Value) -> Result<Gc<Native<Task>>> {
+	Task::race(self::tasks(tasks)?)
+}
+
+/// Waits for the task to finish, returning its result.
+#[quest_fn(name = "await")]
+fn task_await(#[this] task: Gc<Native<Task>>) -> Result<Value> {
+	await_value(task.to_value())
+}
+
+/// Returns whether the task has finished.
+#[quest_fn]
+fn is_done(#[this] task: Gc<Native<Task>>) -> Result<bool> {
+	Ok(task.as_ref()?.is_finished())
+}
+
+/// Cancels the task, returning whether it was still running.
+#[quest_fn]
+fn cancel(#[this] task: Gc<Native<Task>>) -> Result<bool> {
+	Ok(task.as_ref()?.cancel())
+}
+
+impl NativeType for Task {
+	const TYPENAME: crate::value::Typename = "Task";
+
+	fn init_class(class: &mut class::Builder) -> Result<()> {
+		class.set_attr(Intern::spawn, qs_task_spawn.to_value())?;
+		class.set_attr(Intern::sleep, qs_task_sleep.to_value())?;
+		class.set_attr(Intern::after, qs_after.to_value())?;
+		class.set_attr(Intern::every, qs_every.to_value())?;
+		class.set_attr(Intern::all, qs_all.to_value())?;
+		class.set_attr(Intern::race, qs_race.to_value())?;
+		class.set_attr(Intern::r#await, qs_task_await.to_value())?;
+		class.set_attr(Intern::is_done, qs_is_done.to_value())?;
+		class.set_attr(Intern::cancel, qs_cancel.to_value())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::vm::event_loop::{block_on, turn, Turn};
+
+	#[test]
+	fn external_tasks_are_completed_from_other_threads() {
+		let (task, completer) = Task::external().unwrap();
+		let waiter = Task::all(vec![task]).unwrap();
+
+		assert_eq!(turn(), Turn::Waiting);
+
+		std::thread::spawn(move || completer.complete(Ok(Value::ONE.to_value())));
+		let results = block_on(waiter).unwrap().downcast::<Gc<List>>().unwrap();
+
+		let results = results.as_ref().unwrap();
+		assert_eq!(results.len(), 1);
+		assert!(results.as_slice()[0].is_identical(Value::ONE.to_value()));
+		assert_eq!(turn(), Turn::Idle);
+	}
+
+	#[test]
+	fn external_tasks_in_use_are_completed_next_turn() {
+		let (task, completer) = Task::external().unwrap();
+		completer.complete(Ok(Value::ONE.to_value()));
+
+		let guard = task.as_mut().unwrap();
+		assert_eq!(turn(), Turn::Ready);
+		drop(guard);
+
+		assert_eq!(turn(), Turn::Idle);
+		assert!(task
+			.as_ref()
+			.unwrap()
+			.result()
+			.unwrap()
+			.unwrap()
+			.is_identical(Value::ONE.to_value()));
+	}
+
+	#[test]
+	fn dropped_completers_fail_their_tasks() {
+		let (task, completer) = Task::external().unwrap();
+		drop(completer);
+
+		assert!(block_on(task).is_err());
+	}
+
+	#[test]
+	fn empty_races_never_finish() {
+		let race = Task::race(vec![]).unwrap();
+
+		assert!(block_on(race).is_err());
+		assert!(race.as_ref().unwrap().cancel());
+		assert_eq!(turn(), Turn::Idle);
+	}
+
+	#[test]
+	fn tasks_cancelled_on_other_threads_stop_running() {
+		let (external, completer) = Task::external().unwrap();
+		let task = Task::coroutine(move || await_value(external.to_value())).unwrap();
+		assert_eq!(turn(), Turn::Waiting);
+
+		std::thread::scope(|scope| {
+			scope.spawn(|| assert!(task.as_ref().unwrap().cancel()));
+		});
+		assert!(block_on(task).is_err());
+
+		drop(completer);
+		assert!(block_on(external).is_err());
+		assert_eq!(turn(), Turn::Idle);
+	}
+}
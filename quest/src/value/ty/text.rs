@@ -926,7 +926,7 @@ impl Drop for Text {
 		// how do we want to deal with that, especially with substring shares, which dont
 		// know where the entire string starts.
 
-		unsafe { alloc::dealloc(self.alloc.ptr, alloc_ptr_layout(self.alloc.cap)) }
+		unsafe { crate::dealloc(self.alloc.ptr, alloc_ptr_layout(self.alloc.cap)) }
 	}
 }*/
 
@@ -982,6 +982,12 @@ impl ToValue for String {
 	}
 }
 
+impl crate::value::FromValue for String {
+	fn from_value(value: Value) -> Result<Self> {
+		Ok(value.to_text()?.as_ref()?.as_str().to_owned())
+	}
+}
+
 impl Eq for Text {}
 impl PartialEq for Text {
 	fn eq(&self, rhs: &Self) -> bool {
@@ -1069,11 +1075,60 @@ pub mod funcs {
 		}
 	}
 
+	pub fn op_cmp(text: Gc<Text>, args: Args<'_>) -> Result<Value> {
+		args.assert_no_keyword()?;
+		args.assert_positional_len(1)?;
+
+		if let Some(rhs) = args[0].downcast::<Gc<Text>>() {
+			Ok(text.as_ref()?.as_str().cmp(rhs.as_ref()?.as_str()).to_value())
+		} else {
+			Ok(Value::NULL.to_value())
+		}
+	}
+
+	pub fn hash(text: Gc<Text>, args: Args<'_>) -> Result<Value> {
+		args.assert_no_arguments()?;
+
+		Ok(crate::value::compare::hash_to_value(text.as_ref()?.fast_hash()))
+	}
+
 	pub fn len(text: Gc<Text>, args: Args<'_>) -> Result<Value> {
 		args.assert_no_arguments()?;
 		Ok((text.as_ref()?.len() as i64).to_value())
 	}
 
+	// Note that, unlike `len`, indexing works in terms of `char`s, not bytes.
+	pub fn op_index(text: Gc<Text>, args: Args<'_>) -> Result<Value> {
+		use crate::value::ty::Range;
+
+		args.assert_no_keyword()?;
+		args.assert_positional_len(1)?;
+
+		let chars = text.as_ref()?.as_str().chars().collect::<Vec<_>>();
+
+		if let Some(range) = args[0].downcast::<Gc<Range>>() {
+			let mut builder = Text::simple_builder();
+
+			for idx in range.as_ref()?.slice_indices(chars.len()) {
+				builder.push(chars[idx]);
+			}
+
+			return Ok(builder.finish().to_value());
+		}
+
+		let mut index = args[0].to_integer()?.get();
+
+		if index < 0 {
+			index += chars.len() as i64;
+		}
+
+		usize::try_from(index)
+			.ok()
+			.and_then(|idx| chars.get(idx))
+			.map(|&chr| Text::from_char(chr).to_value())
+			.ok_or_else(|| "index out of bounds".to_string().into())
+	}
+
 	pub fn op_assign(text: Gc<Text>, args: Args<'_>) -> Result<Value> {
 		args.assert_no_keyword()?;
 		args.assert_positional_len(1)?;
@@ -1112,7 +1167,10 @@ quest_type_attrs! { for Gc<Text>,
 	concat => meth funcs::concat,
 	len => meth funcs::len,
 	op_eql => meth funcs::op_eql,
+	op_cmp => meth funcs::op_cmp,
+	hash => meth funcs::hash,
 	op_add => meth funcs::op_add,
+	op_index => meth funcs::op_index,
 	op_assign => meth funcs::op_assign,
 	dbg => meth funcs::dbg,
 	to_list => meth funcs::to_list,
@@ -0,0 +1,727 @@
+//! Running Quest code on multiple threads, and synchronizing between them.
+//!
+//! A [`Thread`] is created via `Thread.spawn(func, args...)` (or the kernel's `spawn`), which calls
+//! `func` with `args` on a new OS thread; `join`ing it waits for `func` to finish and returns its
+//! result.
+//! Threads communicate via [`Channel`]s, and coordinate via [`Mutex`]es and [`Condvar`]s. Unlike
+//! the per-value locks, these all block the calling thread until they can proceed.
+//!
+//! # Sharing values between threads
+//! Any value can be passed to another thread. If one thread accesses a value while another is
+//! modifying it, the locks that [`Gc::as_ref`] and [`Gc::as_mut`] acquire wait for the other
+//! thread to finish, according to the thread's [contention strategy](gc::Contention). However,
+//! each individual access is only atomic by itself, and a value that's locked for a long time (or
+//! that would deadlock) still causes an [`ErrorKind::AlreadyLocked`] (or [`ErrorKind::Deadlock`]).
+//! As such:
+//! - Unallocated values (such as `Integer`s and `Boolean`s) and [frozen](Value::freeze) values
+//!   can be freely shared, as they're never modified.
+//! - Any other value should either only be used by one thread at a time (eg by sending it over a
+//!   `Channel` and not touching it afterwards), or only be accessed while holding a `Mutex`.
+//! - Frames are values too, so a spawned function that looks up variables in the scope it was
+//!   defined in has to wait for that scope's thread whenever it's assigning to them. Passing values
+//!   as arguments to `Thread.spawn` avoids this.
+//! - A frame can only run on one thread at a time; running one that's already running elsewhere
+//!   is an error.
+//!
+//! Each thread has its own stackframes, [root](crate::vm::frame::root),
+//! [limits](crate::vm::Limits) and contention strategy. Spawned threads start with the same root,
+//! limits and contention strategy as the thread that spawned them, and share its budget: fuel and
+//! memory used by either count towards the same limits, and they have the same deadline.
+//! Interned strings are shared by all threads.
+
+use crate::value::ty::native::{Native, NativeType};
+use crate::value::ty::{class, Iterable, List};
+use crate::value::{gc, Callable, Gc, ToValue};
+use crate::vm::{coroutine, frame, limits, Args};
+use crate::{ErrorKind, Intern, Result, Value};
+use std::collections::VecDeque;
+use std::sync::{Arc, Condvar as StdCondvar, Mutex as StdMutex};
+use std::thread::{self, JoinHandle, ThreadId};
+
+fn error(message: &str) -> crate::Error {
+	ErrorKind::Message(message.to_string()).into()
+}
+
+/// A handle to a thread that's running a Quest function.
+pub struct Thread {
+	handle: StdMutex<Option<JoinHandle<Result<Value>>>>,
+}
+
+impl Thread {
+	/// Calls `func` with `args` on a new thread.
+	#[must_use]
+	pub fn spawn(func: Value, args: Vec<Value>) -> Self {
+		let root = frame::root();
+		let budget = limits::budget();
+		let contention = gc::contention();
+		let sharing = gc::start_sharing();
+
+		let handle = thread::spawn(move || {
+			frame::set_root(root);
+			limits::set_budget(budget);
+			gc::set_contention(contention);
+			let result = func.call(Args::new(&args, &[]));
+			coroutine::drop_orphans();
+			drop(sharing);
+			result
+		});
+
+		Self { handle: StdMutex::new(Some(handle)) }
+	}
+
+	/// Waits for the thread to finish, returning whatever its function returned.
+	///
+	/// # Errors
+	/// Returns an error if the function did, if the thread panicked, or if it's already been joined.
+	pub fn join(&self) -> Result<Value> {
+		let handle = self.handle.lock().unwrap().take();
+		let handle = handle.ok_or_else(|| error("thread has already been joined"))?;
+
+		handle.join().unwrap_or_else(|_| Err(error("thread panicked")))
+	}
+
+	/// Returns whether the thread's function has finished running.
+	pub fn is_finished(&self) -> bool {
+		self.handle.lock().unwrap().as_ref().is_none_or(JoinHandle::is_finished)
+	}
+}
+
+/// Spawns a new thread which calls `func` with the remaining arguments.
+#[quest_fn(name = "spawn")]
+fn thread_spawn(
+	#[this] _class: Value,
+	func: Value,
+	#[rest] args: Vec<Value>,
+) -> Result<Gc<Native<Thread>>> {
+	Native::new(Thread::spawn(func, args))
+}
+
+/// Waits for the thread to finish, returning its function's result.
+#[quest_fn]
+fn join(#[this] thread: Gc<Native<Thread>>) -> Result<Value> {
+	thread.as_ref()?.join()
+}
+
+/// Returns whether the thread has finished running.
+#[quest_fn]
+fn is_finished(#[this] thread: Gc<Native<Thread>>) -> Result<bool> {
+	Ok(thread.as_ref()?.is_finished())
+}
+
+impl NativeType for Thread {
+	const TYPENAME: crate::value::Typename = "Thread";
+
+	fn init_class(class: &mut class::Builder) -> Result<()> {
+		class.set_attr(Intern::spawn, qs_thread_spawn.to_value())?;
+		class.set_attr(Intern::join, qs_join.to_value())?;
+		class.set_attr(Intern::is_finished, qs_is_finished.to_value())
+	}
+}
+
+// Used by `Channel.select` to wait on multiple channels at once.
+#[derive(Default)]
+struct Signal {
+	is_set: StdMutex<bool>,
+	condvar: StdCondvar,
+}
+
+impl Signal {
+	fn set(&self) {
+		*self.is_set.lock().unwrap() = true;
+		self.condvar.notify_all();
+	}
+
+	fn wait(&self) {
+		let is_set = self.is_set.lock().unwrap();
+		let mut is_set = self.condvar.wait_while(is_set, |is_set| !*is_set).unwrap();
+		*is_set = false;
+	}
+}
+
+struct ChannelState {
+	queue: VecDeque<Value>,
+	capacity: Option<usize>,
+	is_closed: bool,
+	selectors: Vec<Arc<Signal>>,
+}
+
+/// A queue of values which threads can send to and receive from.
+///
+/// Channels are either unbounded, or bounded, in which case senders wait while the channel is full.
+/// Once a channel's closed, no more values may be sent, but receivers can still receive whatever
+/// was sent beforehand.
+pub struct Channel {
+	state: StdMutex<ChannelState>,
+	// Notified whenever a value is sent or received, or the channel is closed.
+	changed: StdCondvar,
+}
+
+impl Channel {
+	/// Creates a new channel, which holds at most `capacity` values if given.
+	#[must_use]
+	pub fn new(capacity: Option<usize>) -> Self {
+		let state =
+			ChannelState { queue: VecDeque::new(), capacity, is_closed: false, selectors: Vec::new() };
+
+		Self { state: StdMutex::new(state), changed: StdCondvar::new() }
+	}
+
+	/// Sends `value`, waiting until there's room for it if the channel's bounded.
+	///
+	/// # Errors
+	/// Returns an error if the channel is (or becomes) closed.
+	pub fn send(&self, value: Value) -> Result<()> {
+		let mut state = self.state.lock().unwrap();
+
+		loop {
+			if state.is_closed {
+				return Err(error("cannot send on a closed channel"));
+			}
+
+			if state.capacity.is_none_or(|capacity| state.queue.len() < capacity) {
+				break;
+			}
+
+			state = self.changed.wait(state).unwrap();
+		}
+
+		state.queue.push_back(value);
+		state.selectors.iter().for_each(|signal| signal.set());
+		self.changed.notify_all();
+
+		Ok(())
+	}
+
+	/// Receives the next value, waiting until one's sent.
+	///
+	/// Returns `None` if the channel is closed and there's nothing left to receive.
+	pub fn recv(&self) -> Option<Value> {
+		let mut state = self.state.lock().unwrap();
+
+		loop {
+			if let Some(value) = state.queue.pop_front() {
+				self.changed.notify_all();
+				return Some(value);
+			}
+
+			if state.is_closed {
+				return None;
+			}
+
+			state = self.changed.wait(state).unwrap();
+		}
+	}
+
+	/// Receives the next value if there is one, without waiting.
+	pub fn try_recv(&self) -> Option<Value> {
+		let value = self.state.lock().unwrap().queue.pop_front();
+
+		if value.is_some() {
+			self.changed.notify_all();
+		}
+
+		value
+	}
+
+	/// Closes the channel, waking up anything that's waiting on it.
+	pub fn close(&self) {
+		let mut state = self.state.lock().unwrap();
+		state.is_closed = true;
+		state.selectors.iter().for_each(|signal| signal.set());
+		self.changed.notify_all();
+	}
+
+	/// Returns whether the channel has been closed.
+	pub fn is_closed(&self) -> bool {
+		self.state.lock().unwrap().is_closed
+	}
+
+	/// Returns how many values are waiting to be received.
+	pub fn len(&self) -> usize {
+		self.state.lock().unwrap().queue.len()
+	}
+
+	/// Returns whether there's no values waiting to be received.
+	pub fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+
+	// Returns `Err` if there's nothing to receive, along with whether the channel's closed.
+	fn try_recv_or_closed(&self) -> std::result::Result<Value, bool> {
+		let mut state = self.state.lock().unwrap();
+
+		if let Some(value) = state.queue.pop_front() {
+			self.changed.notify_all();
+			Ok(value)
+		} else {
+			Err(state.is_closed)
+		}
+	}
+}
+
+/// Waits until any of `channels` has a value, returning its index and the value.
+///
+/// Returns `None` if every channel is closed and has nothing left to receive.
+fn select(channels: &[&Channel]) -> Option<(usize, Value)> {
+	let signal = Arc::new(Signal::default());
+
+	for channel in channels {
+		channel.state.lock().unwrap().selectors.push(signal.clone());
+	}
+
+	let result = loop {
+		let mut are_all_closed = true;
+		let mut received = None;
+
+		for (index, channel) in channels.iter().enumerate() {
+			match channel.try_recv_or_closed() {
+				Ok(value) => {
+					received = Some((index, value));
+					break;
+				}
+				Err(is_closed) => are_all_closed &= is_closed,
+			}
+		}
+
+		if received.is_some() || are_all_closed {
+			break received;
+		}
+
+		// Anything sent after we checked will have set `signal`, so we can't miss it.
+		signal.wait();
+	};
+
+	for channel in channels {
+		channel.state.lock().unwrap().selectors.retain(|other| !Arc::ptr_eq(other, &signal));
+	}
+
+	result
+}
+
+/// Creates a new channel, which is bounded if `capacity` is given.
+#[quest_fn(name = "new")]
+fn channel_new(#[this] _class: Value, capacity: Option<i64>) -> Result<Gc<Native<Channel>>> {
+	let capacity = capacity
+		.map(|capacity| match usize::try_from(capacity) {
+			Ok(capacity) if capacity != 0 => Ok(capacity),
+			_ => Err(error("channel capacity must be positive")),
+		})
+		.transpose()?;
+
+	Native::new(Channel::new(capacity))
+}
+
+/// Sends `value`, waiting until there's room for it if the channel is bounded.
+#[quest_fn]
+fn send(#[this] channel: Gc<Native<Channel>>, value: Value) -> Result<Value> {
+	channel.as_ref()?.send(value)?;
+	Ok(Value::default())
+}
+
+/// Receives the next value, waiting until one's sent. Once the channel's closed and empty, this
+/// stops iteration instead.
+#[quest_fn]
+fn recv(#[this] channel: Gc<Native<Channel>>) -> Result<Value> {
+	channel.as_ref()?.recv().ok_or_else(|| ErrorKind::StopIteration.into())
+}
+
+/// Receives the next value if there is one, or returns `null` otherwise.
+#[quest_fn]
+fn try_recv(#[this] channel: Gc<Native<Channel>>) -> Result<Value> {
+	Ok(channel.as_ref()?.try_recv().unwrap_or_default())
+}
+
+/// Closes the channel, so nothing more can be sent.
+#[quest_fn]
+fn close(#[this] channel: Gc<Native<Channel>>) -> Result<Value> {
+	channel.as_ref()?.close();
+	Ok(Value::default())
+}
+
+/// Returns whether the channel's been closed.
+#[quest_fn]
+fn is_closed(#[this] channel: Gc<Native<Channel>>) -> Result<bool> {
+	Ok(channel.as_ref()?.is_closed())
+}
+
+/// Returns how many values are waiting to be received.
+#[quest_fn]
+fn len(#[this] channel: Gc<Native<Channel>>) -> Result<i64> {
+	Ok(channel.as_ref()?.len() as i64)
+}
+
+/// Waits until any of the given channels has a value, returning `[channel, value]`. Once every
+/// channel is closed and empty, this stops iteration instead.
+#[quest_fn(name = "select")]
+fn channel_select(
+	#[this] _class: Value,
+	#[rest] channels: Vec<Gc<Native<Channel>>>,
+) -> Result<Value> {
+	let refs = channels.iter().map(|channel| channel.as_ref()).collect::<Result<Vec<_>>>()?;
+	let refs = refs.iter().map(|channel| &***channel).collect::<Vec<_>>();
+
+	let (index, value) = select(&refs).ok_or(ErrorKind::StopIteration)?;
+	Ok(List::from_slice(&[channels[index].to_value(), value]).to_value())
+}
+
+impl NativeType for Channel {
+	const TYPENAME: crate::value::Typename = "Channel";
+
+	fn init_class(class: &mut class::Builder) -> Result<()> {
+		// Channels are iterable, yielding values until they're closed.
+		class.parent(Iterable::instance());
+		class.set_attr(Intern::new_, qs_channel_new.to_value())?;
+		class.set_attr(Intern::select, qs_channel_select.to_value())?;
+		class.set_attr(Intern::send, qs_send.to_value())?;
+		class.set_attr(Intern::recv, qs_recv.to_value())?;
+		class.set_attr(Intern::next, qs_recv.to_value())?;
+		class.set_attr(Intern::try_recv, qs_try_recv.to_value())?;
+		class.set_attr(Intern::close, qs_close.to_value())?;
+		class.set_attr(Intern::is_closed, qs_is_closed.to_value())?;
+		class.set_attr(Intern::len, qs_len.to_value())
+	}
+}
+
+struct MutexState {
+	owner: Option<ThreadId>,
+	value: Value,
+}
+
+/// A lock which protects a value, so only one thread can access it at a time.
+///
+/// The protected value is only accessible while holding the lock: `lock` and `update` call a
+/// function with it while holding the lock, within which `get` and `set` can be used.
+pub struct Mutex {
+	state: StdMutex<MutexState>,
+	unlocked: StdCondvar,
+}
+
+// Releases the mutex when dropped, so it's released even if the function returns an error.
+struct MutexGuard<'a>(&'a Mutex);
+
+impl Drop for MutexGuard<'_> {
+	fn drop(&mut self) {
+		self.0.release();
+	}
+}
+
+impl Mutex {
+	/// Creates a new mutex protecting `value`.
+	#[must_use]
+	pub fn new(value: Value) -> Self {
+		let state = MutexState { owner: None, value };
+
+		Self { state: StdMutex::new(state), unlocked: StdCondvar::new() }
+	}
+
+	fn acquire(&self) -> Result<MutexGuard<'_>> {
+		let current = thread::current().id();
+		let mut state = self.state.lock().unwrap();
+
+		if state.owner == Some(current) {
+			return Err(error("mutex is already locked by the current thread"));
+		}
+
+		state = self.unlocked.wait_while(state, |state| state.owner.is_some()).unwrap();
+		state.owner = Some(current);
+
+		Ok(MutexGuard(self))
+	}
+
+	fn release(&self) {
+		self.state.lock().unwrap().owner = None;
+		self.unlocked.notify_one();
+	}
+
+	fn is_held_by_current_thread(&self) -> bool {
+		self.state.lock().unwrap().owner == Some(thread::current().id())
+	}
+
+	/// Gets the protected value.
+	///
+	/// # Errors
+	/// Returns an error if the current thread doesn't hold the lock.
+	pub fn get(&self) -> Result<Value> {
+		let state = self.state.lock().unwrap();
+
+		if state.owner == Some(thread::current().id()) {
+			Ok(state.value)
+		} else {
+			Err(error("the mutex must be locked by the current thread"))
+		}
+	}
+
+	/// Replaces the protected value.
+	///
+	/// # Errors
+	/// Returns an error if the current thread doesn't hold the lock.
+	pub fn set(&self, value: Value) -> Result<()> {
+		let mut state = self.state.lock().unwrap();
+
+		if state.owner == Some(thread::current().id()) {
+			state.value = value;
+			Ok(())
+		} else {
+			Err(error("the mutex must be locked by the current thread"))
+		}
+	}
+
+	/// Calls `func` with the protected value while holding the lock, returning its result.
+	///
+	/// # Errors
+	/// Returns an error if the current thread already holds the lock, or if `func` does.
+	pub fn lock(&self, func: impl FnOnce(Value) -> Result<Value>) -> Result<Value> {
+		let _guard = self.acquire()?;
+
+		func(self.get()?)
+	}
+
+	/// Replaces the protected value with the result of calling `func` with it while holding the
+	/// lock, returning the new value.
+	///
+	/// # Errors
+	/// Returns an error if the current thread already holds the lock, or if `func` does.
+	pub fn update(&self, func: impl FnOnce(Value) -> Result<Value>) -> Result<Value> {
+		let _guard = self.acquire()?;
+		let value = func(self.get()?)?;
+		self.set(value)?;
+
+		Ok(value)
+	}
+
+	/// Returns whether any thread holds the lock.
+	pub fn is_locked(&self) -> bool {
+		self.state.lock().unwrap().owner.is_some()
+	}
+}
+
+/// Creates a new mutex, which protects `value`.
+#[quest_fn(name = "new")]
+fn mutex_new(#[this] _class: Value, value: Option<Value>) -> Result<Gc<Native<Mutex>>> {
+	Native::new(Mutex::new(value.unwrap_or_default()))
+}
+
+/// Calls `func` with the protected value while holding the lock, returning its result.
+#[quest_fn]
+fn lock(#[this] mutex: Gc<Native<Mutex>>, func: Value) -> Result<Value> {
+	mutex.as_ref()?.lock(|value| func.call(Args::new(&[value], &[])))
+}
+
+/// Replaces the protected value with `func(value)` while holding the lock, returning the new value.
+#[quest_fn]
+fn update(#[this] mutex: Gc<Native<Mutex>>, func: Value) -> Result<Value> {
+	mutex.as_ref()?.update(|value| func.call(Args::new(&[value], &[])))
+}
+
+/// Gets the protected value; the current thread must hold the lock.
+#[quest_fn]
+fn get(#[this] mutex: Gc<Native<Mutex>>) -> Result<Value> {
+	mutex.as_ref()?.get()
+}
+
+/// Replaces the protected value; the current thread must hold the lock.
+#[quest_fn]
+fn set(#[this] mutex: Gc<Native<Mutex>>, value: Value) -> Result<Value> {
+	mutex.as_ref()?.set(value)?;
+	Ok(value)
+}
+
+/// Returns whether any thread holds the lock.
+#[quest_fn]
+fn is_locked(#[this] mutex: Gc<Native<Mutex>>) -> Result<bool> {
+	Ok(mutex.as_ref()?.is_locked())
+}
+
+impl NativeType for Mutex {
+	const TYPENAME: crate::value::Typename = "Mutex";
+
+	fn init_class(class: &mut class::Builder) -> Result<()> {
+		class.set_attr(Intern::new_, qs_mutex_new.to_value())?;
+		class.set_attr(Intern::lock, qs_lock.to_value())?;
+		class.set_attr(Intern::update, qs_update.to_value())?;
+		class.set_attr(Intern::get, qs_get.to_value())?;
+		class.set_attr(Intern::set, qs_set.to_value())?;
+		class.set_attr(Intern::is_locked, qs_is_locked.to_value())
+	}
+}
+
+/// A way for threads to wait until another thread notifies them, while holding a [`Mutex`].
+///
+/// As with any condition variable, waiting threads can wake up spuriously, so they should check
+/// whatever condition they're waiting on in a loop.
+pub struct Condvar {
+	// Incremented on every notification, so waiters can tell whether they've been notified.
+	generation: StdMutex<u64>,
+	condvar: StdCondvar,
+}
+
+impl Default for Condvar {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl Condvar {
+	/// Creates a new condition variable.
+	#[must_use]
+	pub fn new() -> Self {
+		Self { generation: StdMutex::new(0), condvar: StdCondvar::new() }
+	}
+
+	/// Releases `mutex`, waits until notified, and then reacquires `mutex`.
+	///
+	/// # Errors
+	/// Returns an error if the current thread doesn't hold `mutex`.
+	pub fn wait(&self, mutex: &Mutex) -> Result<()> {
+		if !mutex.is_held_by_current_thread() {
+			return Err(error("can only wait on a condvar while holding the mutex"));
+		}
+
+		// We hold `generation` while releasing `mutex`, so we can't miss any notifications.
+		let generation = self.generation.lock().unwrap();
+		let start = *generation;
+		mutex.release();
+
+		drop(self.condvar.wait_while(generation, |generation| *generation == start).unwrap());
+
+		// The caller's guard still releases it, so we don't want to release it twice.
+		std::mem::forget(mutex.acquire()?);
+		Ok(())
+	}
+
+	/// Wakes up one thread that's waiting on `self`.
+	pub fn notify_one(&self) {
+		*self.generation.lock().unwrap() += 1;
+		self.condvar.notify_one();
+	}
+
+	/// Wakes up every thread that's waiting on `self`.
+	pub fn notify_all(&self) {
+		*self.generation.lock().unwrap() += 1;
+		self.condvar.notify_all();
+	}
+}
+
+/// Creates a new condition variable.
+#[quest_fn(name = "new")]
+fn condvar_new(#[this] _class: Value) -> Result<Gc<Native<Condvar>>> {
+	Native::new(Condvar::new())
+}
+
+/// Releases `mutex` (which must be held by the current thread), waits until notified, and then
+/// reacquires it.
+#[quest_fn]
+fn wait(#[this] condvar: Gc<Native<Condvar>>, mutex: Gc<Native<Mutex>>) -> Result<Value> {
+	let mutex = mutex.as_ref()?;
+	condvar.as_ref()?.wait(&mutex)?;
+	Ok(Value::default())
+}
+
+/// Wakes up one thread that's waiting.
+#[quest_fn]
+fn notify_one(#[this] condvar: Gc<Native<Condvar>>) -> Result<Value> {
+	condvar.as_ref()?.notify_one();
+	Ok(Value::default())
+}
+
+/// Wakes up every thread that's waiting.
+#[quest_fn]
+fn notify_all(#[this] condvar: Gc<Native<Condvar>>) -> Result<Value> {
+	condvar.as_ref()?.notify_all();
+	Ok(Value::default())
+}
+
+impl NativeType for Condvar {
+	const TYPENAME: crate::value::Typename = "Condvar";
+
+	fn init_class(class: &mut class::Builder) -> Result<()> {
+		class.set_attr(Intern::new_, qs_condvar_new.to_value())?;
+		class.set_attr(Intern::wait, qs_wait.to_value())?;
+		class.set_attr(Intern::notify_one, qs_notify_one.to_value())?;
+		class.set_attr(Intern::notify_all, qs_notify_all.to_value())
+	}
+}
+
+/// Gets the classes for [`Thread`], [`Channel`], [`Mutex`] and [`Condvar`], along with their names.
+pub(crate) fn classes() -> Result<[(Intern, Value); 4]> {
+	Ok([
+		(Intern::Thread, Native::<Thread>::class()?),
+		(Intern::Channel, Native::<Channel>::class()?),
+		(Intern::Mutex, Native::<Mutex>::class()?),
+		(Intern::Condvar, Native::<Condvar>::class()?),
+	])
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::time::Duration;
+
+	#[test]
+	fn bounded_channels_wait_for_room() {
+		let channel = Arc::new(Channel::new(Some(1)));
+		channel.send(Value::ONE.to_value()).unwrap();
+
+		let sender = {
+			let channel = channel.clone();
+			thread::spawn(move || channel.send(Value::ZERO.to_value()))
+		};
+
+		thread::sleep(Duration::from_millis(10));
+		assert_eq!(channel.len(), 1);
+
+		assert!(channel.recv().unwrap().is_identical(Value::ONE.to_value()));
+		sender.join().unwrap().unwrap();
+		assert!(channel.recv().unwrap().is_identical(Value::ZERO.to_value()));
+
+		channel.close();
+		assert!(channel.recv().is_none());
+		assert!(channel.send(Value::ONE.to_value()).is_err());
+	}
+
+	#[test]
+	fn select_waits_for_any_channel() {
+		let channels = Arc::new([Channel::new(None), Channel::new(None)]);
+
+		let sender = {
+			let channels = channels.clone();
+			thread::spawn(move || {
+				thread::sleep(Duration::from_millis(10));
+				channels[1].send(Value::ONE.to_value())
+			})
+		};
+
+		let (index, value) = select(&[&channels[0], &channels[1]]).unwrap();
+		assert_eq!(index, 1);
+		assert!(value.is_identical(Value::ONE.to_value()));
+		sender.join().unwrap().unwrap();
+
+		channels[0].close();
+		channels[1].close();
+		assert!(select(&[&channels[0], &channels[1]]).is_none());
+	}
+
+	#[test]
+	fn spawned_threads_share_their_parents_budget() {
+		use crate::vm::Limits;
+		use std::time::Instant;
+
+		let forever = crate::run_code("{ while({ true }, {}) }").unwrap();
+
+		limits::set_limits(Limits { fuel: Some(10_000), ..Limits::default() });
+		let err = Thread::spawn(forever, vec![]).join().unwrap_err();
+		assert_matches!(err.kind, ErrorKind::OutOfFuel);
+		assert_eq!(limits::remaining_fuel(), Some(0));
+
+		let timeout = Duration::from_millis(300);
+		limits::set_limits(Limits { timeout: Some(timeout), ..Limits::default() });
+		thread::sleep(timeout);
+
+		let start = Instant::now();
+		let err = Thread::spawn(forever, vec![]).join().unwrap_err();
+		assert_matches!(err.kind, ErrorKind::DeadlineExceeded);
+		assert!(start.elapsed() < timeout / 2, "the deadline was restarted");
+
+		limits::set_limits(Limits::default());
+	}
+}
@@ -1,10 +1,10 @@
 use crate::value::base::{Attribute, HasDefaultParent};
 use crate::value::ty::{
-	AttrConversionDefined, Boolean, BoundFn, Float, Integer, List, RustFn, Text, Wrap,
+	AttrConversionDefined, Boolean, BoundFn, Float, Integer, List, Null, RustFn, Text, Wrap,
 };
 use crate::value::{
-	Attributed, AttributedMut, Callable, Convertible, Gc, HasParents, NamedType, ToValue,
-	TryAttributed,
+	compare, Attributed, AttributedMut, Callable, Convertible, Gc, HasAttributes, HasParents,
+	NamedType, ToValue, TryAttributed,
 };
 use crate::vm::{Args, Block};
 use crate::{ErrorKind, Intern, Result};
@@ -192,6 +192,14 @@ impl Value {
 	pub fn typename(self) -> crate::value::Typename {
 		use crate::value::ty::*;
 
+		if let Some(typename) = native::typename_of(self) {
+			return typename;
+		}
+
+		if let Some(instance) = self.downcast::<Gc<Instance>>() {
+			return Instance::class_name_of(instance);
+		}
+
 		match () {
 			_ if self.is_a::<Integer>() || self.is_a::<Gc<Wrap<Integer>>>() => Integer::TYPENAME,
 			_ if self.is_a::<Float>() || self.is_a::<Gc<Wrap<Float>>>() => Float::TYPENAME,
@@ -200,19 +208,27 @@ impl Value {
 			_ if self.is_a::<RustFn>() || self.is_a::<Gc<Wrap<RustFn>>>() => RustFn::TYPENAME,
 			_ if self.is_a::<Gc<Text>>() => Gc::<Text>::TYPENAME,
 			_ if self.is_a::<Gc<List>>() => Gc::<List>::TYPENAME,
+			_ if self.is_a::<Gc<Map>>() => Gc::<Map>::TYPENAME,
+			_ if self.is_a::<Gc<Set>>() => Gc::<Set>::TYPENAME,
+			_ if self.is_a::<Gc<Range>>() => Gc::<Range>::TYPENAME,
 			_ if self.is_a::<Gc<Class>>() => Gc::<Class>::TYPENAME,
 			_ if self.is_a::<Gc<Scope>>() => Gc::<Scope>::TYPENAME,
 			_ if self.is_a::<Gc<BoundFn>>() => Gc::<BoundFn>::TYPENAME,
 			_ if self.is_a::<Gc<BigNum>>() => Gc::<BigNum>::TYPENAME,
 			_ if self.is_a::<Gc<crate::vm::Block>>() => Gc::<crate::vm::Block>::TYPENAME,
 			_ if self.is_a::<Gc<crate::vm::Frame>>() => Gc::<crate::vm::Frame>::TYPENAME,
+			_ if self.is_a::<Gc<Pristine>>() => Gc::<Pristine>::TYPENAME,
+			_ if self.is_a::<Gc<Callable>>() => Gc::<Callable>::TYPENAME,
+			_ if self.is_a::<Gc<Iterator>>() => Gc::<Iterator>::TYPENAME,
+			_ if self.is_a::<Gc<scope::ScopeClass>>() => Gc::<scope::ScopeClass>::TYPENAME,
+			_ if self.is_a::<Gc<boundfn::BoundFnClass>>() => Gc::<boundfn::BoundFnClass>::TYPENAME,
 			_ if cfg!(debug_assertions) => panic!("todo: typename for {:?}", self),
 			_ => "(Unknown)",
 		}
 	}
 
 	// SAFETY: must be called with an unallocated type.
-	unsafe fn parents_for_unallocated(self) -> Self {
+	pub(crate) unsafe fn parents_for_unallocated(self) -> Self {
 		use crate::value::ty::*;
 
 		debug_assert!(!self.is_allocated());
@@ -253,7 +269,7 @@ impl Value {
 		}
 	}
 
-	unsafe fn get_gc_any_unchecked(self) -> Gc<Wrap<Any>> {
+	pub(crate) unsafe fn get_gc_any_unchecked(self) -> Gc<Wrap<Any>> {
 		debug_assert!(self.is_allocated());
 
 		Gc::new_unchecked(self.bits() as usize as *mut _)
@@ -271,6 +287,48 @@ impl Value {
 		Ok(())
 	}
 
+	/// Freezes `self` and everything reachable from it: its attributes, its parents, and the
+	/// contents of containers (such as [`List`]s and [`Map`](crate::value::ty::Map)s).
+	///
+	/// Classes aren't frozen, as they're shared between all of their instances. Neither are frames
+	/// (unless they've been converted to objects and are done running) or lexical scopes.
+	///
+	/// # Errors
+	/// Will return an error if anything being frozen is currently mutably borrowed.
+	pub fn deep_freeze(self) -> Result<()> {
+		super::freeze::deep_freeze(self)
+	}
+
+	/// Checks whether `self` is frozen. Unallocated values can never be modified, and so are
+	/// always frozen.
+	#[must_use]
+	pub fn is_frozen(self) -> bool {
+		!self.is_allocated() || unsafe { self.get_gc_any_unchecked() }.is_frozen()
+	}
+
+	/// Returns an unfrozen copy of `self`, with the same attributes, parents and contents.
+	///
+	/// Only [`Text`]s, [`List`]s, [`Map`](crate::value::ty::Map)s, [`Set`](crate::value::ty::Set)s
+	/// and [`Instance`](crate::value::ty::Instance)s can be copied.
+	///
+	/// # Errors
+	/// Returns an error if `self` can't be copied, or is currently mutably borrowed.
+	pub fn dup(self) -> Result<Self> {
+		super::freeze::dup(self)
+	}
+
+	/// Returns an unfrozen copy of `self`, like [`dup`](Self::dup), except its attributes and
+	/// contents are copied too.
+	///
+	/// Values that can't be copied (eg classes and functions) are shared with `self` instead.
+	///
+	/// # Errors
+	/// Returns an error if `self` can't be copied, or if anything being copied is currently
+	/// mutably borrowed.
+	pub fn deep_dup(self) -> Result<Self> {
+		super::freeze::deep_dup(self)
+	}
+
 	/// Gets the list of parents associated with `self`
 	///
 	/// This takes a mutable reference in case `self` is not allocated
@@ -284,6 +342,144 @@ impl Value {
 
 		Ok(unsafe { self.get_gc_any_unchecked() }.as_mut()?.parents_list())
 	}
+
+	/// Gets the attributes defined directly on `self`, ie not including those from its parents.
+	///
+	/// # Errors
+	/// Returns an error if `self` is currently mutably borrowed.
+	pub fn own_attributes(self) -> Result<Vec<(Self, Self)>> {
+		if !self.is_allocated() {
+			return Ok(Vec::new()); // unallocated values cant have attributes of their own.
+		}
+
+		Ok(unsafe { self.get_gc_any_unchecked() }.as_ref()?.attributes().iter().collect())
+	}
+
+	/// Gets the attribute `attr` if it's defined directly on `self`, without looking at parents.
+	///
+	/// Note that `attr` should not be a [special attribute](Attribute::is_special).
+	///
+	/// # Errors
+	/// Returns an error if `self` is currently mutably borrowed, or if comparing `attr` fails.
+	pub fn get_own_unbound_attr<A: Attribute>(self, attr: A) -> Result<Option<Self>> {
+		if !self.is_allocated() {
+			return Ok(None);
+		}
+
+		unsafe { self.get_gc_any_unchecked() }.as_ref()?.attributes().get_unbound_attr(attr)
+	}
+
+	/// Gets the unbound attribute `attr` by searching the ancestors of `self` that come _after_
+	/// `parent` in its [`mro`](Self::mro), like `super` in other languages.
+	///
+	/// This allows for an overridden attribute to access the one it overrides.
+	///
+	/// # Errors
+	/// Returns an error if `parent` isn't in `self`'s [`mro`](Self::mro), or any errors from
+	/// computing it.
+	pub fn get_super_unbound_attr<A: Attribute>(
+		self,
+		parent: Self,
+		attr: A,
+	) -> Result<Option<Self>> {
+		let mro = self.mro()?;
+		let position = mro
+			.iter()
+			.position(|ancestor| ancestor.is_identical(parent))
+			.ok_or_else(|| ErrorKind::Message(format!("{parent:?} is not an ancestor of {self:?}")))?;
+
+		for &ancestor in &mro[position + 1..] {
+			if let Some(value) = ancestor.get_own_unbound_attr(attr)? {
+				return Ok(Some(value));
+			}
+		}
+
+		Ok(None)
+	}
+
+	/// Whether `self`'s parents are lexical scopes (eg of frames and blocks), which are searched
+	/// depth-first instead of in their method resolution order.
+	pub(crate) fn has_lexical_parents(self) -> Result<bool> {
+		if !self.is_allocated() {
+			return Ok(false);
+		}
+
+		Ok(unsafe { self.get_gc_any_unchecked() }.as_ref()?.parents().is_lexical())
+	}
+
+	/// Gets the direct parents of `self`, in the order they're listed in.
+	///
+	/// # Errors
+	/// Returns an error if `self`, or its list of parents, is currently mutably borrowed.
+	pub fn parents(self) -> Result<Vec<Self>> {
+		if !self.is_allocated() {
+			// SAFETY: `self` is unallocated, as we just verified
+			return Ok(vec![unsafe { self.parents_for_unallocated() }]);
+		}
+
+		unsafe { self.get_gc_any_unchecked() }.as_ref()?.parents().to_vec()
+	}
+
+	/// Gets the method resolution order of `self`: `self` followed by every ancestor (ie its
+	/// parents, their parents, and so on) in the order attributes are looked up in.
+	///
+	/// This is determined by [C3 linearization](https://en.wikipedia.org/wiki/C3_linearization),
+	/// so a value is always searched before its parents, parents are searched in the order
+	/// they're listed in, and ancestors shared between parents are searched only once. (The
+	/// exception is frames and blocks, whose parents are lexical scopes that are searched
+	/// depth-first.)
+	///
+	/// # Errors
+	/// Returns an error if any ancestor is currently mutably borrowed, if `self` is its own
+	/// ancestor ([`ErrorKind::CyclicParents`]), or if there's no consistent order ([`ErrorKind::
+	/// InconsistentParents`]).
+	pub fn mro(self) -> Result<Vec<Self>> {
+		super::mro::linearize(self)
+	}
+
+	/// Gets every ancestor of `self`, in the order that attributes are looked up in.
+	///
+	/// This is the same as [`mro`](Self::mro), except without `self`.
+	///
+	/// # Errors
+	/// Returns any errors from [`mro`](Self::mro).
+	pub fn ancestors(self) -> Result<Vec<Self>> {
+		Ok(self.mro()?.split_off(1))
+	}
+
+	/// Gets every attribute that's accessible on `self`, including those from its ancestors.
+	///
+	/// Attributes are returned in lookup order, and attributes which are shadowed by an earlier
+	/// one with the same name are omitted.
+	///
+	/// # Errors
+	/// Returns any errors from [`own_attributes`](Self::own_attributes) or [`mro`](Self::mro), or
+	/// from comparing attribute names.
+	pub fn all_attributes(self) -> Result<Vec<(Self, Self)>> {
+		let mut attributes = Vec::<(Self, Self)>::new();
+
+		for value in self.mro()? {
+			'next: for (key, attr) in value.own_attributes()? {
+				for &(existing, _) in &attributes {
+					if existing.try_eq(key)? {
+						continue 'next;
+					}
+				}
+
+				attributes.push((key, attr));
+			}
+		}
+
+		Ok(attributes)
+	}
+
+	/// Checks to see if `parent` is one of `self`'s [`ancestors`](Self::ancestors).
+	///
+	/// # Errors
+	/// Returns any errors from [`ancestors`](Self::ancestors).
+	pub fn inherits_from(self, parent: Self) -> Result<bool> {
+		Ok(self.ancestors()?.iter().any(|ancestor| ancestor.is_identical(parent)))
+	}
 }
 
 impl Attributed for Value {
@@ -305,6 +501,12 @@ impl AttributedMut for Value {
 	fn set_attr<A: Attribute>(&mut self, attr: A, value: Value) -> Result<()> {
 		self.assert_isnt_an_objectified_frame();
 
+		if attr.is_parents() {
+			let parents = value.try_downcast::<Gc<List>>()?;
+
+			super::mro::check_parents(*self, parents.as_ref()?.as_slice())?;
+		}
+
 		if !self.is_allocated() {
 			// SAFETY: `self` is unallocated, as we just verified
 			unsafe {
@@ -500,7 +702,13 @@ impl Value {
 	}
 
 	/// Attempts to hash `self`, returning an `Err` if unable to.
+	///
+	/// See the [comparison protocol](crate::value::compare) for how this relates to `hash`.
 	pub fn try_hash(self) -> Result<u64> {
+		if let Some(hash) = compare::hash_number(self)? {
+			return Ok(hash);
+		}
+
 		if self.is_allocated() {
 			if let Some(text) = self.downcast::<Gc<Text>>() {
 				Ok(text.as_ref()?.fast_hash())
@@ -517,11 +725,17 @@ impl Value {
 	}
 
 	/// Attempts to comapre `self` and `rhs`, returning an `Err` if unable to.
+	///
+	/// See the [comparison protocol](crate::value::compare) for how this relates to `==`.
 	pub fn try_eq(self, rhs: Self) -> Result<bool> {
 		if self.is_identical(rhs) {
 			return Ok(true);
 		}
 
+		if compare::is_number(self) {
+			return Ok(compare::cmp_numbers(self, rhs)? == Some(std::cmp::Ordering::Equal));
+		}
+
 		if self.is_allocated() {
 			if let (Some(lhs), Some(rhs)) = (self.downcast::<Gc<Text>>(), rhs.downcast::<Gc<Text>>()) {
 				Ok(*lhs.as_ref()? == *rhs.as_ref()?)
@@ -532,6 +746,34 @@ impl Value {
 			Ok(false)
 		}
 	}
+
+	/// Attempts to order `self` relative to `rhs` via `<=>`, returning `None` if they're unordered.
+	///
+	/// See the [comparison protocol](crate::value::compare) for details.
+	pub fn try_partial_cmp(self, rhs: Self) -> Result<Option<std::cmp::Ordering>> {
+		// Integers are by far the most common thing to compare, so skip the method call for them.
+		if let (Some(lhs), Some(rhs)) = (self.downcast::<Integer>(), rhs.downcast::<Integer>()) {
+			return Ok(Some(lhs.get().cmp(&rhs.get())));
+		}
+
+		if compare::is_number(self) {
+			return compare::cmp_numbers(self, rhs);
+		}
+
+		let cmp = self.call_attr(Intern::op_cmp, Args::new(&[rhs], &[]))?;
+
+		if cmp.is_a::<Null>() {
+			Ok(None)
+		} else {
+			Ok(Some(cmp.try_downcast::<Integer>()?.get().cmp(&0)))
+		}
+	}
+
+	/// Attempts to order `self` relative to `rhs` via `<=>`, returning an `Err` if unable to or if
+	/// they're unordered.
+	pub fn try_cmp(self, rhs: Self) -> Result<std::cmp::Ordering> {
+		self.try_partial_cmp(rhs)?.ok_or_else(|| compare::unordered(self, rhs))
+	}
 }
 
 impl<T: Convertible + Debug> Debug for Value<T> {
@@ -573,10 +815,18 @@ impl Debug for Value {
 			Debug::fmt(&t, fmt)
 		} else if let Some(l) = self.downcast::<Gc<List>>() {
 			Debug::fmt(&l, fmt)
+		} else if let Some(m) = self.downcast::<Gc<Map>>() {
+			Debug::fmt(&m, fmt)
+		} else if let Some(s) = self.downcast::<Gc<Set>>() {
+			Debug::fmt(&s, fmt)
+		} else if let Some(r) = self.downcast::<Gc<Range>>() {
+			Debug::fmt(&r, fmt)
 		} else if let Some(l) = self.downcast::<Gc<BigNum>>() {
 			Debug::fmt(&l, fmt)
 		} else if let Some(l) = self.downcast::<Gc<Class>>() {
 			Debug::fmt(&l, fmt)
+		} else if let Some(i) = self.downcast::<Gc<Instance>>() {
+			Debug::fmt(&i, fmt)
 		} else if let Some(l) = self.downcast::<Gc<Scope>>() {
 			Debug::fmt(&l, fmt)
 		} else if let Some(l) = self.downcast::<Gc<BoundFn>>() {
@@ -595,6 +845,8 @@ impl Debug for Value {
 			Debug::fmt(&StructDebug(n, "Null"), fmt)
 		} else if let Some(f) = self.downcast::<Gc<Wrap<RustFn>>>() {
 			Debug::fmt(&f, fmt)
+		} else if let Some(typename) = native::typename_of(*self) {
+			write!(fmt, "{typename}(<native:{:p}>)", self.0.get() as *const ())
 		} else {
 			write!(fmt, "Value(<unknown:{:p}>)", self.0.get() as *const ())
 		}
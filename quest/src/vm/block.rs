@@ -1,9 +1,10 @@
 //! Types relating to Quest [`Block`]s.
+use super::inline_cache::InlineCache;
 use super::{Frame, SourceLocation};
-use crate::value::base::Base;
+use crate::value::base::{Base, NoParents};
 use crate::value::gc::Gc;
 use crate::value::ty::{List, Text};
-use crate::value::{AttributedMut, Callable, HasAttributes, HasDefaultParent, ToValue};
+use crate::value::{AttributedMut, Callable, HasAttributes, HasDefaultParent, HasParents, ToValue};
 use crate::vm::Args;
 use crate::{Intern, Result, Value};
 use std::fmt::{self, Debug, Display, Formatter};
@@ -27,6 +28,26 @@ pub struct BlockInner {
 	pub(super) code: Vec<u8>,
 	pub(super) constants: Vec<Value>,
 	pub(super) num_of_unnamed_locals: NonZeroUsize,
+	// One for each opcode that looks up attributes via an inline cache, indexed by the opcode's
+	// cache index.
+	pub(super) inline_caches: Box<[InlineCache]>,
+	// `(code offset, line)` pairs, in ascending order, for the start of each line's code.
+	pub(super) lines: Vec<(usize, usize)>,
+}
+
+impl BlockInner {
+	/// Gets the line that the code at `pos` originated from.
+	pub(crate) fn line_at(&self, pos: usize) -> usize {
+		match self.lines.partition_point(|&(start, _)| start <= pos) {
+			0 => self.location.line,
+			idx => self.lines[idx - 1].1,
+		}
+	}
+
+	/// Gets the line that starts at `pos`, if any.
+	pub(crate) fn line_starting_at(&self, pos: usize) -> Option<usize> {
+		self.lines.binary_search_by_key(&pos, |&(start, _)| start).ok().map(|idx| self.lines[idx].1)
+	}
 }
 
 impl Debug for Block {
@@ -45,6 +66,7 @@ impl Debug for Block {
 }
 
 impl Block {
+	#[allow(clippy::too_many_arguments)]
 	fn _new(
 		arity: usize,
 		code: Vec<u8>,
@@ -52,6 +74,8 @@ impl Block {
 		constants: Vec<Value>,
 		num_of_unnamed_locals: NonZeroUsize,
 		named_locals: Vec<Intern>,
+		num_of_inline_caches: usize,
+		lines: Vec<(usize, usize)>,
 	) -> Gc<Self> {
 		let inner = Arc::new(BlockInner {
 			arity,
@@ -60,6 +84,8 @@ impl Block {
 			constants,
 			num_of_unnamed_locals,
 			named_locals,
+			inline_caches: (0..num_of_inline_caches).map(|_| InlineCache::default()).collect(),
+			lines,
 		});
 
 		Base::new(inner, Gc::<Self>::parent())
@@ -136,10 +162,10 @@ impl Block {
 		// TODO: optimize me, eg maybe have shared attributes pointer or something
 		let inner = self.inner();
 		let parents = List::from_slice(&[Gc::<Self>::parent(), parent_scope.to_value()]);
-		// this
-		let cloned = Base::<Self>::new(inner, parents);
+		let cloned = Base::<Self>::new(inner, NoParents);
 
 		let mut clonedmut = cloned.as_mut().unwrap();
+		clonedmut.parents_mut().set_lexical(parents);
 		for (attr, value) in self.attributes().iter() {
 			clonedmut.set_attr(attr, value)?;
 		}
@@ -176,6 +202,11 @@ impl Gc<Block> {
 
 		Ok(frame)
 	}
+
+	/// Creates a frame for `self` and [wraps it as a generator](Gc::<Frame>::into_generator).
+	pub fn generator(self, args: Args<'_>) -> Result<Gc<crate::value::ty::Iterator>> {
+		Frame::new(self, args)?.into_generator()
+	}
 }
 
 /// Quest functions defined for [`Block`].
@@ -194,6 +225,11 @@ pub mod funcs {
 		block.create_frame(args).map(ToValue::to_value)
 	}
 
+	/// Creates a generator which runs `block` with the given arguments.
+	pub fn generator(block: Gc<Block>, args: Args<'_>) -> Result<Value> {
+		block.generator(args).map(ToValue::to_value)
+	}
+
 	/// Returns a debug representation of `block`.
 	pub fn dbg(block: Gc<Block>, args: Args<'_>) -> Result<Value> {
 		args.assert_no_keyword()?;
@@ -218,6 +254,7 @@ pub mod funcs {
 quest_type_attrs! { for Gc<Block>, parent Object;
 	op_call => meth funcs::call,
 	create_frame => meth funcs::create_frame,
+	generator => meth funcs::generator,
 	dbg => meth funcs::dbg,
 	// "+" => meth qs_add,
 	// "@text" => meth qs_at_text,
@@ -344,6 +381,14 @@ impl Debug for CodeDebugger<'_> {
 					}
 					writeln_len!(f, "CreateListSimple: dst={dst}, list={list:?}")?;
 				}
+				Opcode::CreateMap => {
+					let count = count!();
+					let mut map = Vec::with_capacity(count / 2);
+					for _ in 0..count / 2 {
+						map.push((local!(), local!()));
+					}
+					writeln_len!(f, "CreateMap: dst={dst}, map={map:?}")?;
+				}
 
 				Opcode::ConstLoad => {
 					let idx = count!();
@@ -407,7 +452,12 @@ impl Debug for CodeDebugger<'_> {
 				| Opcode::DelAttrIntern => {
 					let obj = local!();
 					let attr = intern!();
-					writeln_len!(f, "{op:?}: dst={dst}, obj={obj}, attr={attr}")?;
+					if op.has_inline_cache() {
+						let cache = count!();
+						writeln_len!(f, "{op:?}: dst={dst}, obj={obj}, attr={attr}, cache={cache}")?;
+					} else {
+						writeln_len!(f, "{op:?}: dst={dst}, obj={obj}, attr={attr}")?;
+					}
 				}
 				Opcode::SetAttr => {
 					let value = local!();
@@ -436,12 +486,16 @@ impl Debug for CodeDebugger<'_> {
 				Opcode::CallAttrSimpleIntern => {
 					let obj = local!();
 					let attr = intern!();
+					let cache = count!();
 					let count = count!();
 					let mut args = Vec::with_capacity(count as usize);
 					for _ in 0..count {
 						args.push(local!());
 					}
-					writeln_len!(f, "{op:?}: dst={dst}, obj={obj}, attr={attr}, args={args:?}")?;
+					writeln_len!(
+						f,
+						"{op:?}: dst={dst}, obj={obj}, attr={attr}, cache={cache}, args={args:?}"
+					)?;
 				}
 				Opcode::Add
 				| Opcode::Subtract
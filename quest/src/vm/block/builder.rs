@@ -1,6 +1,8 @@
 use super::Block;
 use crate::value::{ty::Text, Gc, Value};
-use crate::vm::{Opcode, SourceLocation, COUNT_IS_NOT_ONE_BYTE_BUT_USIZE, NUM_ARGUMENT_REGISTERS};
+use crate::vm::{
+	coverage, Opcode, SourceLocation, COUNT_IS_NOT_ONE_BYTE_BUT_USIZE, NUM_ARGUMENT_REGISTERS,
+};
 use crate::{Intern, ToValue};
 use std::num::NonZeroUsize;
 
@@ -16,6 +18,8 @@ pub struct Builder {
 	constants: Vec<Value>,
 	num_of_unnamed_locals: NonZeroUsize,
 	named_locals: Vec<Intern>,
+	num_of_inline_caches: usize,
+	lines: Vec<(usize, usize)>,
 }
 
 impl Default for Builder {
@@ -49,12 +53,25 @@ impl Builder {
 			constants: Vec::default(),
 			num_of_unnamed_locals: NonZeroUsize::new(1).unwrap(), // The first register is `Scratch`.
 			named_locals,
+			num_of_inline_caches: 0,
+			lines: Vec::default(),
+		}
+	}
+
+	/// Marks the code that's built after this as originating from `line`.
+	///
+	/// This is what the debugger uses to determine where statements begin.
+	pub fn line(&mut self, line: usize) {
+		if self.lines.last().is_none_or(|&(_, last)| last != line) {
+			self.lines.push((self.code.len(), line));
 		}
 	}
 
 	/// Finish creating the [`Block`].
 	#[must_use]
 	pub fn build(self) -> Gc<Block> {
+		coverage::register(&self.source_location, self.lines.iter().map(|&(_, line)| line));
+
 		Block::_new(
 			self.arity,
 			self.code,
@@ -62,6 +79,8 @@ impl Builder {
 			self.constants,
 			self.num_of_unnamed_locals,
 			self.named_locals,
+			self.num_of_inline_caches,
+			self.lines,
 		)
 	}
 
@@ -139,6 +158,14 @@ impl Builder {
 		}
 	}
 
+	// SAFETY: You need to make sure this is only used right after the intern of an opcode with
+	// `has_inline_cache`.
+	unsafe fn inline_cache(&mut self) {
+		let index = self.num_of_inline_caches;
+		self.num_of_inline_caches += 1;
+		self.count(index);
+	}
+
 	/// SAFETY: requires `args` and `dst` to correctly correspond to `op`, per `op`'s definition
 	unsafe fn simple_opcode<A, I>(&mut self, op: Opcode, dst: Local, args: A)
 	where
@@ -316,6 +343,19 @@ impl Builder {
 		}
 	}
 
+	/// Creates a new map from `pairs`, where the first local of each pair is the key.
+	pub fn create_map(&mut self, pairs: &[(Local, Local)], dst: Local) {
+		// SAFETY: This is the definition of the `CreateMap` opcode.
+		unsafe {
+			self.opcode(Opcode::CreateMap, dst);
+			self.count(pairs.len() * 2);
+			for &(key, value) in pairs {
+				self.local(key);
+				self.local(value);
+			}
+		}
+	}
+
 	/// <under construction, come back later>
 	#[allow(clippy::missing_panics_doc)] // TODO
 	pub fn call(&mut self, _dst: Local) {
@@ -392,6 +432,7 @@ impl Builder {
 			self.opcode(Opcode::GetAttrIntern, dst);
 			self.local(obj);
 			self.intern(attr);
+			self.inline_cache();
 		}
 	}
 
@@ -509,6 +550,7 @@ impl Builder {
 			self.opcode(Opcode::CallAttrSimpleIntern, dst);
 			self.local(obj);
 			self.intern(attr);
+			self.inline_cache();
 			self.locals(args);
 		}
 	}
@@ -0,0 +1,348 @@
+//! Coroutines, which let generators and tasks suspend from anywhere within them.
+//!
+//! Each coroutine runs on its own stack. So, when it's suspended, everything that's running within
+//! it stays intact: not just the frame it was started with, but also any nested blocks, and Rust
+//! functions (such as `while` or `List::each`) that called them. Resuming it continues right where
+//! it left off.
+//!
+//! Suspending always applies to the innermost coroutine that's running, and callers check its
+//! [`Kind`] first, so that only the expected kind of coroutine is ever suspended.
+//!
+//! # Stacks
+//! Stacks are [`coroutine_stack_size`](crate::vm::Limits::coroutine_stack_size) bytes, which is
+//! much smaller than a thread's. Since overflowing one would abort the process, frames running
+//! within a coroutine are limited to however many fit on its stack (as estimated by
+//! [`STACK_BYTES_PER_FRAME`]), and running any more is a
+//! [`StackOverflow`](crate::ErrorKind::StackOverflow).
+//!
+//! # Threads
+//! Coroutines can only be resumed on the thread that created them, as their stacks may refer to
+//! thread-local state. Likewise, a suspended coroutine can only be dropped (which unwinds its
+//! stack) on that thread; if it's dropped on any other, it's sent back to be dropped the next time
+//! its thread creates or resumes a coroutine.
+
+use crate::vm::{frame, limits};
+use crate::{Result, Value};
+use corosensei::stack::{DefaultStack, Stack};
+use corosensei::{CoroutineResult, Yielder};
+use std::cell::{Cell, RefCell};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread::{self, ThreadId};
+
+/// What a coroutine is used for, which determines what can suspend it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum Kind {
+	/// Suspended by `yield`, handing a value to whoever resumed it.
+	Generator,
+
+	/// Suspended by `await`, until the task it's waiting for has finished.
+	Task,
+}
+
+/// What happened after a coroutine was [resumed](Coroutine::resume).
+pub(crate) enum Resumed {
+	/// The coroutine suspended itself with the given value.
+	Suspended(Value),
+
+	/// The coroutine finished with the given result, and can't be resumed again.
+	Finished(Result<Value>),
+}
+
+/// The default [`coroutine_stack_size`](crate::vm::Limits::coroutine_stack_size).
+///
+/// Stacks are only reserved up front, so the parts that aren't used don't take up any memory.
+pub(crate) const DEFAULT_STACK_SIZE: usize = if cfg!(debug_assertions) { 4 << 20 } else { 1 << 20 };
+
+/// A (generous) estimate of how much of a coroutine's stack each frame running within it uses.
+pub(crate) const STACK_BYTES_PER_FRAME: usize =
+	if cfg!(debug_assertions) { 64 << 10 } else { 4 << 10 };
+
+// Allocating stacks is relatively expensive, so stacks of finished coroutines are reused.
+const MAX_CACHED_STACKS: usize = 16;
+
+type Inner = corosensei::Coroutine<Value, Value, Result<Value>, DefaultStack>;
+
+/// A function running on its own stack, which can suspend itself via [`suspend`].
+pub(crate) struct Coroutine {
+	inner: Option<Inner>,
+	thread: ThreadId,
+}
+
+// A coroutine that's currently running, along with what's needed to suspend it.
+struct Active {
+	kind: Kind,
+	// Only dereferenced while running on the coroutine's own stack, where it's always valid.
+	yielder: *const Yielder<Value, Value>,
+	// The length of `STACKFRAMES` when the coroutine was (re)entered; any frames past it belong to
+	// the coroutine.
+	base: usize,
+	max_frames: usize,
+}
+
+thread_local! {
+	static ACTIVE: RefCell<Vec<Active>> = const { RefCell::new(Vec::new()) };
+	static STACKS: RefCell<Vec<DefaultStack>> = const { RefCell::new(Vec::new()) };
+
+	// How many stackframes can be running before the innermost coroutine's stack could overflow.
+	static MAX_STACKFRAMES: Cell<usize> = const { Cell::new(usize::MAX) };
+}
+
+fn push_active(active: Active) {
+	MAX_STACKFRAMES.with(|max| max.set(active.base + active.max_frames));
+	ACTIVE.with(|stack| stack.borrow_mut().push(active));
+}
+
+fn pop_active() -> Option<Active> {
+	ACTIVE.with(|active| {
+		let mut active = active.borrow_mut();
+		let popped = active.pop();
+		let max = active.last().map_or(usize::MAX, |outer| outer.base + outer.max_frames);
+		MAX_STACKFRAMES.with(|cell| cell.set(max));
+		popped
+	})
+}
+
+/// The most stackframes that can be running on the current thread without overflowing the stack
+/// of the coroutine that's running, or `usize::MAX` if there isn't one.
+pub(crate) fn max_stackframes() -> usize {
+	MAX_STACKFRAMES.with(Cell::get)
+}
+
+fn stack_size(stack: &DefaultStack) -> usize {
+	stack.base().get() - stack.limit().get()
+}
+
+impl Coroutine {
+	/// Creates a coroutine which runs `body` the first time it's resumed.
+	///
+	/// # Errors
+	/// Returns an error if the [stack size](crate::vm::Limits::coroutine_stack_size) is too small
+	/// to run any frames, or if the stack can't be allocated.
+	pub(crate) fn new(kind: Kind, body: impl FnOnce() -> Result<Value> + 'static) -> Result<Self> {
+		drop_orphans();
+
+		let size = limits::coroutine_stack_size();
+		let max_frames = size / STACK_BYTES_PER_FRAME;
+
+		if max_frames == 0 {
+			return Err(
+				format!("coroutine stacks must be at least {STACK_BYTES_PER_FRAME} bytes").into(),
+			);
+		}
+
+		let cached = STACKS.with(|stacks| {
+			let mut stacks = stacks.borrow_mut();
+			let index = stacks.iter().position(|stack| stack_size(stack) >= size)?;
+			Some(stacks.swap_remove(index))
+		});
+
+		let stack = match cached {
+			Some(stack) => stack,
+			None => DefaultStack::new(size)
+				.map_err(|err| format!("unable to allocate a coroutine stack: {err}"))?,
+		};
+
+		let inner = corosensei::Coroutine::with_stack(stack, move |yielder, _: Value| {
+			let base = frame::stackframes_len();
+			push_active(Active { kind, yielder, base, max_frames });
+
+			let result = body();
+
+			pop_active();
+			result
+		});
+
+		Ok(Self { inner: Some(inner), thread: thread::current().id() })
+	}
+
+	/// Runs `self` until it either suspends itself or finishes. `input` is returned from the
+	/// [`suspend`] call that `self` is resuming from, and is ignored the first time it's resumed.
+	///
+	/// # Errors
+	/// Returns an error if `self` was created on another thread, or if it's already finished.
+	pub(crate) fn resume(&mut self, input: Value) -> Result<Resumed> {
+		if self.thread != thread::current().id() {
+			return Err(
+				"coroutines can only be resumed on the thread that created them".to_string().into(),
+			);
+		}
+
+		drop_orphans();
+
+		let inner = self.inner.as_mut().ok_or("coroutine has already finished".to_string())?;
+
+		match inner.resume(input) {
+			CoroutineResult::Yield(value) => Ok(Resumed::Suspended(value)),
+			CoroutineResult::Return(result) => {
+				let stack = self.inner.take().expect("it was just resumed").into_stack();
+
+				STACKS.with(|stacks| {
+					let mut stacks = stacks.borrow_mut();
+					if stacks.len() < MAX_CACHED_STACKS {
+						stacks.push(stack);
+					}
+				});
+
+				Ok(Resumed::Finished(result))
+			}
+		}
+	}
+}
+
+// A suspended coroutine that was dropped on another thread, and so has to be dropped on its own.
+struct Orphan(Inner);
+
+// SAFETY: An orphan's stack is only ever unwound (by dropping it) on the thread that created it,
+// in `drop_orphans`. Until then, it's only moved around, which doesn't touch its stack.
+unsafe impl Send for Orphan {}
+
+static ORPHANS: Mutex<Vec<(ThreadId, Orphan)>> = Mutex::new(Vec::new());
+
+// How many orphans there are, so that looking for them doesn't need to lock `ORPHANS`.
+static ORPHAN_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+/// Drops any coroutines which were created on the current thread, but dropped on another one.
+///
+/// This is called whenever a coroutine's created or resumed, and should also be called before a
+/// thread that's used coroutines exits: a thread's orphans can't be dropped once it's gone, and so
+/// are leaked instead.
+pub(crate) fn drop_orphans() {
+	if ORPHAN_COUNT.load(Ordering::Acquire) == 0 {
+		return;
+	}
+
+	let current = thread::current().id();
+	let orphans: Vec<(ThreadId, Orphan)> = {
+		let mut orphans = ORPHANS.lock().unwrap();
+		let (mine, others) = orphans.drain(..).partition(|(thread, _)| *thread == current);
+		*orphans = others;
+		ORPHAN_COUNT.store(orphans.len(), Ordering::Release);
+		mine
+	};
+
+	// Dropping them runs arbitrary code, which may drop coroutines itself, so `ORPHANS` isn't
+	// locked while doing so.
+	for (_, Orphan(inner)) in orphans {
+		drop(inner);
+	}
+}
+
+impl Drop for Coroutine {
+	fn drop(&mut self) {
+		let Some(inner) = self.inner.take() else {
+			return;
+		};
+
+		// Coroutines which haven't started have nothing on their stack to unwind, so they're fine
+		// to drop anywhere.
+		if self.thread == thread::current().id() || !inner.started() {
+			return;
+		}
+
+		let mut orphans = ORPHANS.lock().unwrap();
+		orphans.push((self.thread, Orphan(inner)));
+		ORPHAN_COUNT.store(orphans.len(), Ordering::Release);
+	}
+}
+
+// SAFETY: A coroutine's stack is what may refer to thread-local state, and it's only ever accessed
+// on the thread that created it: `resume` returns an error on any other thread, and `drop` sends
+// suspended coroutines back to their own thread to be unwound. Everything else in a `Coroutine`
+// (its thread id) is `Send`, and the `Yielder` pointers in `ACTIVE` are never part
+// of it. So, moving one between threads never touches its stack from the wrong thread.
+unsafe impl Send for Coroutine {}
+
+/// Returns the kind of the innermost coroutine that's running on the current thread, if any.
+pub(crate) fn current() -> Option<Kind> {
+	ACTIVE.with(|active| active.borrow().last().map(|active| active.kind))
+}
+
+/// Suspends the innermost coroutine, handing `value` to whoever resumed it. Once it's resumed, the
+/// input it was resumed with is returned.
+///
+/// # Panics
+/// Panics if there's no coroutine running; callers should check [`current`] first.
+pub(crate) fn suspend(value: Value) -> Value {
+	let active = pop_active().expect("`suspend` was called outside of a coroutine");
+
+	// The coroutine's frames stay with it while it's suspended, so whoever resumed it doesn't see
+	// them on the stack.
+	let frames = frame::take_stackframes(active.base);
+
+	// SAFETY: We're running on the coroutine's stack, as it's the innermost one that's running, so
+	// its yielder is still alive.
+	let input = unsafe { &*active.yielder }.suspend(value);
+
+	let base = frame::restore_stackframes(frames);
+	push_active(Active { base, ..active });
+
+	input
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::value::ty::Integer;
+	use crate::ToValue;
+	use std::sync::atomic::AtomicBool;
+	use std::sync::Arc;
+
+	#[test]
+	fn suspends_and_resumes() {
+		let mut coroutine = Coroutine::new(Kind::Generator, || {
+			assert_eq!(current(), Some(Kind::Generator));
+			let input = suspend(1.to_value());
+			Ok(input)
+		})
+		.unwrap();
+
+		assert_eq!(current(), None);
+		assert!(matches!(
+			coroutine.resume(Value::default()).unwrap(),
+			Resumed::Suspended(value) if value.is_identical(1.to_value())
+		));
+
+		let Resumed::Finished(result) = coroutine.resume(2.to_value()).unwrap() else {
+			panic!("coroutine didn't finish");
+		};
+		assert_eq!(result.unwrap().try_downcast::<Integer>().unwrap(), 2);
+		assert!(coroutine.resume(Value::default()).is_err());
+	}
+
+	#[test]
+	fn only_resumes_on_its_own_thread() {
+		let mut coroutine = Coroutine::new(Kind::Generator, || Ok(Value::default())).unwrap();
+
+		thread::scope(|scope| {
+			scope.spawn(|| assert!(coroutine.resume(Value::default()).is_err()));
+		});
+	}
+
+	#[test]
+	fn dropping_on_another_thread_unwinds_on_its_own() {
+		struct SetOnDrop(Arc<AtomicBool>);
+
+		impl Drop for SetOnDrop {
+			fn drop(&mut self) {
+				self.0.store(true, Ordering::SeqCst);
+			}
+		}
+
+		let dropped = Arc::new(AtomicBool::new(false));
+		let on_stack = SetOnDrop(dropped.clone());
+		let mut coroutine = Coroutine::new(Kind::Generator, move || {
+			let _on_stack = on_stack;
+			suspend(Value::default());
+			Ok(Value::default())
+		})
+		.unwrap();
+		assert!(matches!(coroutine.resume(Value::default()).unwrap(), Resumed::Suspended(_)));
+
+		thread::spawn(move || drop(coroutine)).join().unwrap();
+		assert!(!dropped.load(Ordering::SeqCst));
+
+		drop_orphans();
+		assert!(dropped.load(Ordering::SeqCst));
+	}
+}
@@ -0,0 +1,152 @@
+//! Line coverage for Quest code.
+//!
+//! While coverage is [started](start), every [`Block`](crate::vm::Block) that's built registers the
+//! lines it contains, and every [`Frame`](crate::vm::Frame) records each line it starts executing.
+//! Once [stopped](stop), the results are returned as a [`Coverage`] report, which can be written in
+//! the LCOV format that most coverage tools understand.
+//!
+//! Code expanded from `$syntax`es is attributed to where the syntax was used, not to where it was
+//! defined.
+
+use crate::vm::SourceLocation;
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+// Checked before every line, so we don't have to lock `COVERAGE` when it's not running.
+static IS_ENABLED: AtomicBool = AtomicBool::new(false);
+
+static COVERAGE: Mutex<Option<Coverage>> = Mutex::new(None);
+
+/// The amount of times each line of each source was executed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Coverage {
+	files: BTreeMap<Option<PathBuf>, BTreeMap<usize, u64>>,
+}
+
+impl Coverage {
+	/// Gets the hit counts for each executable line of `file`, or `None` if it was never loaded.
+	///
+	/// Code that wasn't loaded from a file (eg `quest -e`) is under the `None` file.
+	#[must_use]
+	pub fn lines(&self, file: Option<&Path>) -> Option<&BTreeMap<usize, u64>> {
+		self.files.get(&file.map(Path::to_path_buf))
+	}
+
+	/// Writes `self` in the LCOV format to `out`.
+	///
+	/// Since LCOV needs a file for each record, code not loaded from a file is omitted.
+	///
+	/// # Errors
+	/// Returns any errors that occur when writing to `out`.
+	pub fn write_lcov<W: Write>(&self, mut out: W) -> io::Result<()> {
+		for (file, lines) in &self.files {
+			let file = match file {
+				Some(file) => file,
+				None => continue,
+			};
+
+			writeln!(out, "TN:")?;
+			writeln!(out, "SF:{}", file.display())?;
+
+			for (line, hits) in lines {
+				writeln!(out, "DA:{line},{hits}")?;
+			}
+
+			writeln!(out, "LF:{}", lines.len())?;
+			writeln!(out, "LH:{}", lines.values().filter(|&&hits| hits != 0).count())?;
+			writeln!(out, "end_of_record")?;
+		}
+
+		Ok(())
+	}
+}
+
+/// Starts recording coverage, discarding any previous (unfinished) results.
+///
+/// Only blocks built after this is called are known about, so this should be called before any
+/// code is parsed.
+pub fn start() {
+	*COVERAGE.lock().unwrap() = Some(Coverage::default());
+
+	IS_ENABLED.store(true, Ordering::Relaxed);
+}
+
+/// Stops recording coverage, returning the results if it was being recorded.
+pub fn stop() -> Option<Coverage> {
+	IS_ENABLED.store(false, Ordering::Relaxed);
+
+	COVERAGE.lock().unwrap().take()
+}
+
+/// Returns whether coverage is being recorded.
+#[inline]
+pub fn is_enabled() -> bool {
+	IS_ENABLED.load(Ordering::Relaxed)
+}
+
+fn with_lines(location: &SourceLocation, func: impl FnOnce(&mut BTreeMap<usize, u64>)) {
+	if let Some(coverage) = COVERAGE.lock().unwrap().as_mut() {
+		func(coverage.files.entry(location.file.clone()).or_default());
+	}
+}
+
+/// Registers `lines` as executable within the block defined at `location`.
+pub(crate) fn register(location: &SourceLocation, lines: impl IntoIterator<Item = usize>) {
+	if is_enabled() {
+		with_lines(location, |hits| {
+			for line in lines {
+				hits.entry(line).or_default();
+			}
+		});
+	}
+}
+
+/// Records that `line` within the block defined at `location` was executed.
+pub(crate) fn on_line(location: &SourceLocation, line: usize) {
+	if is_enabled() {
+		with_lines(location, |hits| *hits.entry(line).or_default() += 1);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::parse::{ast::Compile, ast::Group, Parser};
+	use crate::vm::block::{Builder, Local};
+
+	fn run_file(code: &str, file: &str) {
+		let mut parser = Parser::new(code, Some(Path::new(file)));
+		let mut builder = Builder::new(0, parser.location().into());
+
+		Group::parse_all(&mut parser).unwrap().compile(&mut builder, Local::Scratch);
+		builder.build().run(Default::default()).unwrap();
+	}
+
+	#[test]
+	fn records_executed_lines() {
+		start();
+		run_file(
+			"x = 1;\nf = { x + 1 };\ng = {\n\tx + 2\n};\nf();\nf();\n$syntax { twice $e:token } = { ($e) * 2 };\ntwice 3;",
+			"coverage.qs",
+		);
+		let coverage = stop().unwrap();
+		let lines = coverage.lines(Some(Path::new("coverage.qs"))).unwrap();
+
+		assert_eq!(lines.get(&1), Some(&1));
+		assert_eq!(lines.get(&2), Some(&3));
+		assert_eq!(lines.get(&4), Some(&0));
+		assert_eq!(lines.get(&6), Some(&1));
+		assert_eq!(lines.get(&9), Some(&1));
+		assert_eq!(lines.get(&8), None);
+
+		let mut lcov = Vec::new();
+		coverage.write_lcov(&mut lcov).unwrap();
+		let lcov = String::from_utf8(lcov).unwrap();
+
+		assert!(lcov.starts_with("TN:\nSF:coverage.qs\nDA:1,1\nDA:2,3\n"), "{lcov}");
+		assert!(lcov.ends_with("LF:7\nLH:6\nend_of_record\n"), "{lcov}");
+	}
+}
@@ -0,0 +1,456 @@
+//! An interactive debugger for Quest code.
+//!
+//! When a debugger is [attached](attach), every [`Frame`] checks in with it at the start of each
+//! line. If a [`Breakpoint`] matches, or we're stepping, execution is paused and the attached
+//! [`Frontend`] is handed a [`Session`], through which it can inspect (and evaluate code within)
+//! the paused frame. The frontend then decides how execution should [`Resume`].
+//!
+//! Quest code can also pause explicitly via `Kernel::breakpoint()`, which does nothing if no
+//! debugger is attached.
+
+use crate::value::Gc;
+use crate::vm::frame::with_stackframes;
+use crate::vm::{Args, Frame, SourceLocation};
+use crate::{ErrorKind, Intern, Result, Value};
+use std::cell::RefCell;
+use std::fmt::{self, Display, Formatter};
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+mod console;
+pub use console::Console;
+
+// How many threads have a debugger attached. It's checked before every line, so we don't have to
+// touch `DEBUGGER` when no debugger's attached.
+static ATTACHED: AtomicUsize = AtomicUsize::new(0);
+
+thread_local! {
+	static DEBUGGER: RefCell<Option<Debugger>> = const { RefCell::new(None) };
+}
+
+struct Debugger {
+	frontend: Box<dyn Frontend>,
+	breakpoints: Vec<Breakpoint>,
+	step: Step,
+}
+
+/// How a paused program should continue.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Resume {
+	/// Run until the next breakpoint.
+	Continue,
+	/// Pause at the next line, even if it's within a different frame.
+	StepInto,
+	/// Pause at the next line within the current frame (or its callers).
+	StepOver,
+	/// Pause once the current frame has returned.
+	StepOut,
+	/// Stop executing entirely.
+	Quit,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Step {
+	Continue,
+	Into,
+	Over { depth: usize },
+	Out { depth: usize },
+}
+
+impl Debugger {
+	fn new(frontend: Box<dyn Frontend>) -> Self {
+		ATTACHED.fetch_add(1, Ordering::Relaxed);
+		Self { frontend, breakpoints: Vec::new(), step: Step::Continue }
+	}
+}
+
+// Debuggers are dropped when they're detached or replaced, as well as when their thread exits.
+impl Drop for Debugger {
+	fn drop(&mut self) {
+		ATTACHED.fetch_sub(1, Ordering::Relaxed);
+	}
+}
+
+/// Something that can drive the debugger, such as the [`Console`].
+pub trait Frontend {
+	/// Called whenever execution is paused, returning how execution should resume.
+	fn paused(&mut self, session: &mut Session<'_>) -> Result<Resume>;
+}
+
+/// A location at which execution should be paused.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Breakpoint {
+	/// The file the breakpoint is in. If `None`, it matches every file.
+	pub file: Option<PathBuf>,
+	/// The line the breakpoint is on.
+	pub line: usize,
+}
+
+impl Breakpoint {
+	/// Creates a new breakpoint for `line` within `file`.
+	#[must_use]
+	pub fn new(file: Option<PathBuf>, line: usize) -> Self {
+		Self { file, line }
+	}
+
+	/// Checks to see whether `self` is for the `line` in the block defined at `location`.
+	///
+	/// Files are matched by suffix, so a breakpoint for `foo.qs` matches `examples/foo.qs`.
+	#[must_use]
+	pub fn matches(&self, location: &SourceLocation, line: usize) -> bool {
+		if self.line != line {
+			return false;
+		}
+
+		match (&self.file, &location.file) {
+			(None, _) => true,
+			(Some(file), Some(location_file)) => location_file.ends_with(file),
+			(Some(_), None) => false,
+		}
+	}
+}
+
+impl FromStr for Breakpoint {
+	type Err = String;
+
+	/// Parses a breakpoint in the form `file:line` or just `line`.
+	fn from_str(source: &str) -> std::result::Result<Self, Self::Err> {
+		let (file, line) = match source.rsplit_once(':') {
+			Some((file, line)) => (Some(PathBuf::from(file)), line),
+			None => (None, source),
+		};
+
+		match line.trim().parse() {
+			Ok(line) => Ok(Self { file, line }),
+			Err(_) => Err(format!("invalid breakpoint {source:?}: expected `[file:]line`")),
+		}
+	}
+}
+
+impl Display for Breakpoint {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		if let Some(file) = &self.file {
+			write!(f, "{}:", file.display())?;
+		}
+
+		write!(f, "{}", self.line)
+	}
+}
+
+/// Attaches `frontend` to the current thread, replacing any previous one (and its breakpoints).
+pub fn attach(frontend: Box<dyn Frontend>) {
+	let previous = DEBUGGER.with(|debugger| debugger.borrow_mut().replace(Debugger::new(frontend)));
+	drop(previous);
+}
+
+/// Detaches the current thread's frontend, if any.
+pub fn detach() {
+	let previous = DEBUGGER.with(|debugger| debugger.borrow_mut().take());
+	drop(previous);
+}
+
+/// Returns whether a debugger may be attached.
+#[inline]
+pub fn is_enabled() -> bool {
+	ATTACHED.load(Ordering::Relaxed) != 0
+}
+
+/// Adds `breakpoint` to the attached debugger, returning `false` if there's no debugger attached.
+pub fn add_breakpoint(breakpoint: Breakpoint) -> bool {
+	with_debugger(|debugger| debugger.breakpoints.push(breakpoint)).is_some()
+}
+
+/// Makes the attached debugger pause at the very next line that's executed.
+pub fn pause_at_next_line() {
+	with_debugger(|debugger| debugger.step = Step::Into);
+}
+
+fn with_debugger<T>(func: impl FnOnce(&mut Debugger) -> T) -> Option<T> {
+	DEBUGGER.with(|debugger| debugger.borrow_mut().as_mut().map(func))
+}
+
+fn stack_depth() -> usize {
+	with_stackframes(<[Gc<Frame>]>::len)
+}
+
+/// Called by frames whenever a new line is started.
+pub(crate) fn on_line(frame: Gc<Frame>, line: usize) -> Result<()> {
+	let depth = stack_depth();
+	let frameref = frame.as_ref()?;
+	let location = frameref.source_location();
+
+	let should_pause = with_debugger(|debugger| match debugger.step {
+		Step::Into => true,
+		Step::Over { depth: start } if depth <= start => true,
+		Step::Out { depth: start } if depth < start => true,
+		_ => debugger.breakpoints.iter().any(|bp| bp.matches(location, line)),
+	});
+
+	drop(frameref);
+
+	if should_pause == Some(true) {
+		pause(frame, line, depth)
+	} else {
+		Ok(())
+	}
+}
+
+/// Pauses within the currently executing frame, if a debugger is attached.
+///
+/// This is what `Kernel::breakpoint()` calls. It never attaches a debugger itself, so scripts
+/// can't block on a console that nobody asked for.
+pub fn breakpoint() -> Result<()> {
+	if with_debugger(|_| ()).is_none() {
+		return Ok(());
+	}
+
+	let frame = match with_stackframes(|frames| frames.last().copied()) {
+		Some(frame) => frame,
+		None => return Ok(()), // not within Quest code, so nothing to pause.
+	};
+
+	let line = frame.as_ref()?.current_line();
+	pause(frame, line, stack_depth())
+}
+
+fn pause(frame: Gc<Frame>, line: usize, depth: usize) -> Result<()> {
+	// We take the debugger out while paused, so code that's evaluated within the session doesn't
+	// trigger breakpoints itself.
+	let mut debugger = match DEBUGGER.with(|debugger| debugger.borrow_mut().take()) {
+		Some(debugger) => debugger,
+		None => return Ok(()),
+	};
+
+	let Debugger { frontend, breakpoints, step } = &mut debugger;
+	let resume = frontend.paused(&mut Session { frame, line, breakpoints });
+
+	*step = match resume {
+		Ok(Resume::Continue) | Ok(Resume::Quit) | Err(_) => Step::Continue,
+		Ok(Resume::StepInto) => Step::Into,
+		Ok(Resume::StepOver) => Step::Over { depth },
+		Ok(Resume::StepOut) => Step::Out { depth },
+	};
+
+	DEBUGGER.with(|slot| {
+		let mut slot = slot.borrow_mut();
+
+		// Only restore it if the session didn't attach a different debugger.
+		if slot.is_none() {
+			*slot = Some(debugger);
+		}
+	});
+
+	match resume? {
+		Resume::Quit => {
+			Err(ErrorKind::Message("execution stopped by the debugger".to_string()).into())
+		}
+		_ => Ok(()),
+	}
+}
+
+/// The state of a paused program, given to [`Frontend`]s.
+pub struct Session<'a> {
+	frame: Gc<Frame>,
+	line: usize,
+	breakpoints: &'a mut Vec<Breakpoint>,
+}
+
+impl Session<'_> {
+	/// The frame that's paused.
+	#[must_use]
+	pub fn frame(&self) -> Gc<Frame> {
+		self.frame
+	}
+
+	/// The line that's about to be executed.
+	#[must_use]
+	pub fn line(&self) -> usize {
+		self.line
+	}
+
+	/// Gets where the paused frame's block was defined.
+	pub fn location(&self) -> Result<SourceLocation> {
+		Ok(self.frame.as_ref()?.source_location().clone())
+	}
+
+	/// Gets every running frame, from outermost to innermost (ie the paused frame).
+	#[must_use]
+	pub fn backtrace(&self) -> Vec<Gc<Frame>> {
+		with_stackframes(<[Gc<Frame>]>::to_vec)
+	}
+
+	/// Gets all the named locals of the paused frame.
+	pub fn locals(&self) -> Result<Vec<(Intern, Value)>> {
+		self.frame.as_ref()?.named_locals()
+	}
+
+	/// Gets the named local `name` of the paused frame, if it's been assigned.
+	pub fn local(&self, name: &str) -> Result<Option<Value>> {
+		Ok(self
+			.locals()?
+			.into_iter()
+			.find(|(local, _)| local.as_str() == name)
+			.map(|(_, value)| value))
+	}
+
+	/// Evaluates `source` as if it were a block defined within the paused frame.
+	pub fn eval(&self, source: &str) -> Result<Value> {
+		use crate::parse::{ast, Parser};
+		use crate::vm::block::{Builder, Local};
+		use ast::Compile;
+
+		let mut parser = Parser::new(source, None);
+		let group = ast::Group::parse_all(&mut parser).map_err(|err| {
+			ErrorKind::Message(format!("unable to parse: {:?} at {}", err.kind, err.location))
+		})?;
+
+		let mut builder = Builder::default();
+		group.compile(&mut builder, Local::Scratch);
+
+		// Just like blocks defined within the frame, we need the frame to be an object so its
+		// locals are visible.
+		self.frame.as_mut()?.convert_to_object()?;
+		let block = builder.build().as_ref()?.deep_clone_from(self.frame)?;
+
+		block.run(Args::default())
+	}
+
+	/// The breakpoints that are currently set.
+	#[must_use]
+	pub fn breakpoints(&self) -> &[Breakpoint] {
+		self.breakpoints
+	}
+
+	/// Adds a new breakpoint.
+	pub fn add_breakpoint(&mut self, breakpoint: Breakpoint) {
+		self.breakpoints.push(breakpoint);
+	}
+
+	/// Removes `breakpoint`, returning whether it existed.
+	pub fn remove_breakpoint(&mut self, breakpoint: &Breakpoint) -> bool {
+		let len = self.breakpoints.len();
+		self.breakpoints.retain(|bp| bp != breakpoint);
+		len != self.breakpoints.len()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::value::ty::Integer;
+	use std::rc::Rc;
+	use std::sync::{Mutex, MutexGuard, PoisonError};
+
+	// Held by tests that attach a debugger, as whether one's enabled is shared between threads.
+	static ATTACHING: Mutex<()> = Mutex::new(());
+
+	pub(super) fn lock_attaching() -> MutexGuard<'static, ()> {
+		ATTACHING.lock().unwrap_or_else(PoisonError::into_inner)
+	}
+
+	type Log = Rc<RefCell<Vec<(usize, Option<i64>)>>>;
+
+	// Records the line and value of `x` at every pause, and then resumes with the next command.
+	struct Scripted {
+		log: Log,
+		commands: Vec<Resume>,
+	}
+
+	impl Frontend for Scripted {
+		fn paused(&mut self, session: &mut Session<'_>) -> Result<Resume> {
+			let x = session.local("x")?.and_then(|x| x.downcast::<Integer>()).map(|x| x.get());
+			self.log.borrow_mut().push((session.line(), x));
+
+			Ok(if self.commands.is_empty() { Resume::Continue } else { self.commands.remove(0) })
+		}
+	}
+
+	fn run_with(
+		code: &str,
+		commands: Vec<Resume>,
+		breakpoints: &[usize],
+	) -> Vec<(usize, Option<i64>)> {
+		let _guard = lock_attaching();
+		let log = Log::default();
+		attach(Box::new(Scripted { log: log.clone(), commands }));
+
+		for &line in breakpoints {
+			add_breakpoint(Breakpoint::new(None, line));
+		}
+
+		crate::run_code(code).unwrap();
+		detach();
+
+		let log = log.borrow().clone();
+		log
+	}
+
+	#[test]
+	fn breakpoints_and_stepping() {
+		let code = "x = 1;\nx = x + 1;\nx = x + 1;\nx = x + 1;";
+
+		assert_eq!(run_with(code, vec![], &[2]), [(2, Some(1))]);
+		assert_eq!(
+			run_with(code, vec![Resume::StepOver, Resume::Continue], &[2]),
+			[(2, Some(1)), (3, Some(2))]
+		);
+	}
+
+	#[test]
+	fn step_over_skips_nested_blocks() {
+		let code = "f = { \n  x = 10 \n };\nf();\nx = 1;";
+
+		assert_eq!(run_with(code, vec![Resume::StepOver], &[4]), [(4, None), (5, None)]);
+		assert_eq!(run_with(code, vec![Resume::StepInto], &[4]), [(4, None), (2, None)]);
+	}
+
+	#[test]
+	fn kernel_breakpoint_pauses() {
+		assert_eq!(run_with("x = 3;\nbreakpoint();\nx = 4;", vec![], &[]), [(2, Some(3))]);
+	}
+
+	#[test]
+	fn kernel_breakpoint_without_a_debugger_does_nothing() {
+		let _guard = lock_attaching();
+		assert!(crate::run_code("x = 3;\nbreakpoint();\nx = 4;").is_ok());
+		assert!(!is_enabled());
+	}
+
+	#[test]
+	fn eval_in_paused_frame() {
+		struct Eval(Rc<RefCell<Option<Value>>>);
+
+		impl Frontend for Eval {
+			fn paused(&mut self, session: &mut Session<'_>) -> Result<Resume> {
+				*self.0.borrow_mut() = Some(session.eval("x * 2")?);
+				Ok(Resume::Continue)
+			}
+		}
+
+		let _guard = lock_attaching();
+		let result = Rc::default();
+		attach(Box::new(Eval(Rc::clone(&result))));
+		crate::run_code("x = 21;\nbreakpoint();").unwrap();
+		detach();
+
+		let result = result.borrow().and_then(|x| x.downcast::<Integer>());
+		assert_eq!(result.map(|x| x.get()), Some(42));
+	}
+
+	#[test]
+	fn detaching_disables_the_debugger() {
+		let _guard = lock_attaching();
+		assert!(!is_enabled());
+
+		attach(Box::new(Console::stdio()));
+		attach(Box::new(Console::stdio()));
+		assert!(is_enabled());
+
+		detach();
+		assert!(!is_enabled());
+
+		std::thread::spawn(|| attach(Box::new(Console::stdio()))).join().unwrap();
+		assert!(!is_enabled(), "debuggers are detached when their thread exits");
+	}
+}
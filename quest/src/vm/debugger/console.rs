@@ -0,0 +1,188 @@
+use super::{Breakpoint, Frontend, Resume, Session};
+use crate::Result;
+use std::io::{self, BufRead, Write};
+
+const HELP: &str = "\
+commands:
+  c, continue          resume execution until the next breakpoint
+  s, step              step into the next line
+  n, next              step over the next line
+  o, out               step out of the current frame
+  b, break [FILE:]LINE add a breakpoint
+  d, delete [FILE:]LINE remove a breakpoint
+  breakpoints          list all breakpoints
+  l, locals            print all locals of the current frame
+  p, print NAME        print the local NAME
+  e, eval CODE         evaluate CODE within the current frame
+  bt, backtrace        print all running frames
+  q, quit              stop executing
+  h, help              print this message";
+
+/// A [`Frontend`] that reads commands from an input (usually stdin) and writes to an output
+/// (usually stderr).
+///
+/// This is what `quest --debug` uses.
+pub struct Console<R, W> {
+	input: R,
+	output: W,
+}
+
+impl Console<io::BufReader<io::Stdin>, io::Stderr> {
+	/// Creates a new [`Console`] that uses stdin and stderr.
+	#[must_use]
+	pub fn stdio() -> Self {
+		Self::new(io::BufReader::new(io::stdin()), io::stderr())
+	}
+}
+
+impl<R: BufRead, W: Write> Console<R, W> {
+	/// Creates a new [`Console`] that reads commands from `input` and writes to `output`.
+	pub fn new(input: R, output: W) -> Self {
+		Self { input, output }
+	}
+
+	fn run_command(&mut self, session: &mut Session<'_>, line: &str) -> io::Result<Option<Resume>> {
+		let (command, rest) = line.trim().split_once(' ').unwrap_or((line.trim(), ""));
+		let rest = rest.trim();
+
+		match command {
+			"c" | "continue" => return Ok(Some(Resume::Continue)),
+			"s" | "step" => return Ok(Some(Resume::StepInto)),
+			"n" | "next" => return Ok(Some(Resume::StepOver)),
+			"o" | "out" => return Ok(Some(Resume::StepOut)),
+			"q" | "quit" => return Ok(Some(Resume::Quit)),
+			"" => {}
+			"h" | "help" => writeln!(self.output, "{HELP}")?,
+
+			"b" | "break" => match rest.parse::<Breakpoint>() {
+				Ok(breakpoint) => {
+					writeln!(self.output, "breakpoint added at {breakpoint}")?;
+					session.add_breakpoint(breakpoint);
+				}
+				Err(err) => writeln!(self.output, "{err}")?,
+			},
+			"d" | "delete" => match rest.parse::<Breakpoint>() {
+				Ok(breakpoint) if session.remove_breakpoint(&breakpoint) => {
+					writeln!(self.output, "breakpoint removed at {breakpoint}")?;
+				}
+				Ok(breakpoint) => writeln!(self.output, "no breakpoint at {breakpoint}")?,
+				Err(err) => writeln!(self.output, "{err}")?,
+			},
+			"breakpoints" => {
+				for breakpoint in session.breakpoints() {
+					writeln!(self.output, "{breakpoint}")?;
+				}
+			}
+
+			"l" | "locals" => match session.locals() {
+				Ok(locals) => {
+					for (name, value) in locals {
+						writeln!(self.output, "{name} = {}", display(value))?;
+					}
+				}
+				Err(err) => writeln!(self.output, "error: {err}")?,
+			},
+			"p" | "print" => match session.local(rest) {
+				Ok(Some(value)) => writeln!(self.output, "{rest} = {}", display(value))?,
+				Ok(None) => writeln!(self.output, "no local named {rest:?}")?,
+				Err(err) => writeln!(self.output, "error: {err}")?,
+			},
+			"e" | "eval" => match session.eval(rest) {
+				Ok(value) => writeln!(self.output, "{}", display(value))?,
+				Err(err) => writeln!(self.output, "error: {err}")?,
+			},
+			"bt" | "backtrace" => {
+				for (depth, frame) in session.backtrace().into_iter().rev().enumerate() {
+					match frame.as_ref() {
+						Ok(frame) => writeln!(
+							self.output,
+							"#{depth} {} (line {})",
+							frame.source_location(),
+							frame.current_line()
+						)?,
+						Err(err) => writeln!(self.output, "#{depth} <{err}>")?,
+					}
+				}
+			}
+
+			other => writeln!(self.output, "unknown command {other:?}; try `help`")?,
+		}
+
+		Ok(None)
+	}
+}
+
+fn display(value: crate::Value) -> String {
+	match value.dbg_text() {
+		Ok(text) => {
+			text.as_ref().map_or_else(|err| format!("<{err}>"), |text| text.as_str().to_string())
+		}
+		Err(err) => format!("<{err}>"),
+	}
+}
+
+impl<R: BufRead, W: Write> Frontend for Console<R, W> {
+	fn paused(&mut self, session: &mut Session<'_>) -> Result<Resume> {
+		let location = session.location()?;
+		let io_error =
+			|err: io::Error| crate::ErrorKind::Message(format!("debugger io error: {err}"));
+
+		writeln!(self.output, "paused at line {} (in block at {location})", session.line())
+			.map_err(io_error)?;
+
+		let mut line = String::new();
+
+		loop {
+			write!(self.output, "(qdb) ").and_then(|()| self.output.flush()).map_err(io_error)?;
+
+			line.clear();
+			if self.input.read_line(&mut line).map_err(io_error)? == 0 {
+				// If we run out of input, there's no way to get more commands, so just keep going.
+				return Ok(Resume::Continue);
+			}
+
+			if let Some(resume) = self.run_command(session, &line).map_err(io_error)? {
+				return Ok(resume);
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::vm::debugger::tests::lock_attaching;
+	use crate::vm::debugger::{attach, detach};
+	use std::cell::RefCell;
+	use std::rc::Rc;
+
+	#[derive(Clone, Default)]
+	struct SharedOutput(Rc<RefCell<Vec<u8>>>);
+
+	impl Write for SharedOutput {
+		fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+			self.0.borrow_mut().write(buf)
+		}
+
+		fn flush(&mut self) -> io::Result<()> {
+			Ok(())
+		}
+	}
+
+	#[test]
+	fn console_commands() {
+		let _guard = lock_attaching();
+		let output = SharedOutput::default();
+		let commands = "p x\ne x + 1\nb 3\nc\nl\nc\n";
+
+		attach(Box::new(Console::new(commands.as_bytes(), output.clone())));
+		crate::run_code("x = 1;\nbreakpoint();\ny = 2;").unwrap();
+		detach();
+
+		let output = String::from_utf8(output.0.borrow().clone()).unwrap();
+		assert!(output.contains("x = 1\n"), "{output}");
+		assert!(output.contains("(qdb) 2\n"), "{output}");
+		assert!(output.contains("breakpoint added at 3\n"), "{output}");
+		assert!(output.contains("paused at line 3"), "{output}");
+	}
+}
@@ -0,0 +1,533 @@
+//! The event loop that runs [`Task`]s.
+//!
+//! Each thread has its own event loop, which runs the tasks created on that thread one at a time.
+//! Tasks are cooperative: a task only stops running once it finishes, or once it `await`s a task
+//! that hasn't finished yet, at which point the loop moves on to the next task that's ready.
+//!
+//! Quest code drives the loop by `await`ing a task outside of any task: the loop then runs until
+//! that task's finished. Embedders can do the same via [`block_on`], or run every task to
+//! completion via [`run_until_idle`].
+//!
+//! # Embedding in an async runtime
+//! Hosts that have their own event loop can drive Quest's a step at a time via [`turn`], whose
+//! [`Turn`] says when it's worth calling again. Alternatively, [`TaskFuture`] wraps a task in a
+//! [`Future`], which can be `await`ed from Rust (on the thread that created the task).
+//!
+//! Work done outside of Quest (such as IO performed by the host) can be represented as an
+//! [external task](Task::external), which is finished by its [`Completer`]. Completers can be used
+//! from any thread, and wake up the event loop that's waiting for them.
+
+use crate::value::ty::native::Native;
+use crate::value::ty::task::{self, Task};
+use crate::value::{Gc, ToValue};
+use crate::{Result, Value};
+use std::cell::RefCell;
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BinaryHeap, HashMap, VecDeque};
+use std::future::Future;
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::sync::{Arc, Condvar, Mutex, Weak};
+use std::task::{Context, Poll, Waker};
+use std::thread::{self, Thread};
+use std::time::Instant;
+
+/// What's left to do after a [`turn`] of the event loop.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Turn {
+	/// Some tasks are ready to run, so `turn` should be called again.
+	Ready,
+
+	/// No tasks are ready, but a timer finishes at the given time.
+	///
+	/// External tasks may also finish before then.
+	Sleeping(Instant),
+
+	/// No tasks are ready and there aren't any timers, but [external tasks](Task::external) are
+	/// still running.
+	Waiting,
+
+	/// There aren't any tasks that could ever run again: either they've all finished, or the
+	/// remaining ones are waiting on each other.
+	Idle,
+}
+
+// A task which should be run once `at` has passed.
+struct Timer {
+	at: Instant,
+	id: u64,
+	task: Gc<Native<Task>>,
+}
+
+impl PartialEq for Timer {
+	fn eq(&self, rhs: &Self) -> bool {
+		self.cmp(rhs) == Ordering::Equal
+	}
+}
+
+impl Eq for Timer {}
+
+impl PartialOrd for Timer {
+	fn partial_cmp(&self, rhs: &Self) -> Option<Ordering> {
+		Some(self.cmp(rhs))
+	}
+}
+
+impl Ord for Timer {
+	fn cmp(&self, rhs: &Self) -> Ordering {
+		// Timers that finish at the same time run in the order they were created.
+		(self.at, self.id).cmp(&(rhs.at, rhs.id))
+	}
+}
+
+// Results of external tasks, which can be sent from other threads.
+#[derive(Default)]
+struct Inbox {
+	completed: Mutex<Vec<(Value, Result<Value>)>>,
+	condvar: Condvar,
+	futures: Mutex<Futures>,
+}
+
+// The `TaskFuture`s that are driving the loop.
+#[derive(Default)]
+struct Futures {
+	// Each future's task and waker, by the future's id.
+	wakers: HashMap<u64, (Value, Waker)>,
+	next_id: u64,
+	// When the timer thread should next wake up every future, as the loop has a timer finishing.
+	deadline: Option<Instant>,
+	timer_thread: Option<Thread>,
+}
+
+impl Inbox {
+	fn send(&self, task: Value, result: Result<Value>) {
+		self.completed.lock().unwrap().push((task, result));
+		self.condvar.notify_all();
+		self.wake_futures(|_| true);
+	}
+
+	// Wakes up the futures whose tasks match `should_wake`.
+	fn wake_futures(&self, should_wake: impl Fn(Value) -> bool) {
+		let wakers = self
+			.futures
+			.lock()
+			.unwrap()
+			.wakers
+			.values()
+			.filter(|(task, _)| should_wake(*task))
+			.map(|(_, waker)| waker.clone())
+			.collect::<Vec<_>>();
+
+		wakers.into_iter().for_each(Waker::wake);
+	}
+
+	// Has the timer thread wake up every future at `at`, starting it if it's not running yet.
+	fn wake_futures_at(self: &Arc<Self>, at: Instant) {
+		let mut futures = self.futures.lock().unwrap();
+		futures.deadline = Some(futures.deadline.map_or(at, |deadline| deadline.min(at)));
+
+		if let Some(timer_thread) = &futures.timer_thread {
+			timer_thread.unpark();
+		} else {
+			let inbox = Arc::downgrade(self);
+			futures.timer_thread =
+				Some(thread::spawn(move || run_timer_thread(&inbox)).thread().clone());
+		}
+	}
+
+	// Blocks until something's in the inbox, or until `deadline` if it's given.
+	fn wait(&self, deadline: Option<Instant>) {
+		let completed = self.completed.lock().unwrap();
+
+		if let Some(deadline) = deadline {
+			let timeout = deadline.saturating_duration_since(Instant::now());
+			drop(self.condvar.wait_timeout_while(completed, timeout, |c| c.is_empty()).unwrap());
+		} else {
+			drop(self.condvar.wait_while(completed, |c| c.is_empty()).unwrap());
+		}
+	}
+}
+
+impl Drop for Inbox {
+	fn drop(&mut self) {
+		// Let the timer thread notice that it's no longer needed.
+		if let Some(timer_thread) = self.futures.get_mut().unwrap().timer_thread.take() {
+			timer_thread.unpark();
+		}
+	}
+}
+
+// Parks until the next deadline, and then wakes up every future. There's at most one of these per
+// event loop, and it exits once the loop's gone.
+fn run_timer_thread(inbox: &Weak<Inbox>) {
+	loop {
+		let Some(inbox) = inbox.upgrade() else {
+			return;
+		};
+
+		let mut futures = inbox.futures.lock().unwrap();
+		let deadline = futures.deadline;
+
+		if deadline.is_some_and(|deadline| deadline <= Instant::now()) {
+			futures.deadline = None;
+			drop(futures);
+			inbox.wake_futures(|_| true);
+			continue;
+		}
+
+		drop(futures);
+		drop(inbox);
+
+		match deadline {
+			Some(deadline) => thread::park_timeout(deadline.saturating_duration_since(Instant::now())),
+			None => thread::park(),
+		}
+	}
+}
+
+#[derive(Default)]
+struct EventLoop {
+	ready: VecDeque<Gc<Native<Task>>>,
+	timers: BinaryHeap<Reverse<Timer>>,
+	next_timer_id: u64,
+	external_tasks: usize,
+	running: Option<Gc<Native<Task>>>,
+	inbox: Arc<Inbox>,
+}
+
+thread_local! {
+	static EVENT_LOOP: RefCell<EventLoop> = RefCell::new(EventLoop::default());
+}
+
+fn with_loop<T>(func: impl FnOnce(&mut EventLoop) -> T) -> T {
+	EVENT_LOOP.with(|event_loop| func(&mut event_loop.borrow_mut()))
+}
+
+/// Schedules `task` to be run on the current thread's event loop.
+pub(crate) fn wake(task: Gc<Native<Task>>) {
+	with_loop(|event_loop| event_loop.ready.push_back(task));
+}
+
+/// Schedules `task` to be run on the current thread's event loop once `at` has passed.
+pub(crate) fn wake_at(at: Instant, task: Gc<Native<Task>>) {
+	with_loop(|event_loop| {
+		let id = event_loop.next_timer_id;
+		event_loop.next_timer_id += 1;
+		event_loop.timers.push(Reverse(Timer { at, id, task }));
+	});
+}
+
+/// Whether the current thread's event loop is in the middle of running a task.
+pub(crate) fn is_running_task() -> bool {
+	current_task().is_some()
+}
+
+/// The task that the current thread's event loop is running, if any.
+pub(crate) fn current_task() -> Option<Gc<Native<Task>>> {
+	with_loop(|event_loop| event_loop.running)
+}
+
+/// A handle used to finish an [external task](Task::external).
+///
+/// If it's dropped without calling [`complete`](Self::complete), the task fails.
+pub struct Completer {
+	task: Option<Value>,
+	inbox: Arc<Inbox>,
+}
+
+impl Completer {
+	/// Finishes the task with `result`, waking up its event loop if it's waiting.
+	pub fn complete(mut self, result: Result<Value>) {
+		let task = self.task.take().expect("the task is only taken when completing");
+		self.inbox.send(task, result);
+	}
+}
+
+impl Drop for Completer {
+	fn drop(&mut self) {
+		if let Some(task) = self.task.take() {
+			self.inbox.send(task, Err("external task was never completed".to_string().into()));
+		}
+	}
+}
+
+/// Registers the external `task` with the current thread's event loop.
+pub(crate) fn register_external(task: Gc<Native<Task>>) -> Completer {
+	with_loop(|event_loop| {
+		event_loop.external_tasks += 1;
+		Completer { task: Some(task.to_value()), inbox: event_loop.inbox.clone() }
+	})
+}
+
+/// Notes that an external task of the current thread's event loop has finished.
+pub(crate) fn external_finished() {
+	with_loop(|event_loop| event_loop.external_tasks -= 1);
+}
+
+/// Runs every task that's currently ready on the current thread's event loop, as well as any
+/// whose timers have finished or that were completed externally.
+///
+/// Tasks which become ready while this is running are run on the next turn.
+pub fn turn() -> Turn {
+	let (completed, ready) = with_loop(|event_loop| {
+		let now = Instant::now();
+
+		while event_loop.timers.peek().is_some_and(|Reverse(timer)| timer.at <= now) {
+			let Reverse(timer) = event_loop.timers.pop().unwrap();
+			event_loop.ready.push_back(timer.task);
+		}
+
+		let completed = std::mem::take(&mut *event_loop.inbox.completed.lock().unwrap());
+		(completed, std::mem::take(&mut event_loop.ready))
+	});
+
+	for (value, result) in completed {
+		let task = value.downcast::<Gc<Native<Task>>>().expect("completers are only made for tasks");
+
+		match task.as_ref() {
+			Ok(task) => task.complete(result),
+			// If it's in use elsewhere, it's tried again next turn; dropping it would mean the task
+			// never finishes, and the loop waits for it forever.
+			Err(_) => with_loop(|event_loop| {
+				event_loop.inbox.completed.lock().unwrap().push((value, result));
+			}),
+		}
+	}
+
+	for task in ready {
+		with_loop(|event_loop| event_loop.running = Some(task));
+		task::poll(task);
+		with_loop(|event_loop| event_loop.running = None);
+	}
+
+	// Futures whose tasks finished during this turn won't be polled again unless they're woken up.
+	with_loop(|event_loop| event_loop.inbox.clone())
+		.wake_futures(|task| task.downcast::<Gc<Native<Task>>>().is_some_and(is_finished));
+
+	with_loop(EventLoop::status)
+}
+
+impl EventLoop {
+	fn status(&mut self) -> Turn {
+		if !self.ready.is_empty() || !self.inbox.completed.lock().unwrap().is_empty() {
+			return Turn::Ready;
+		}
+
+		// Timers for tasks that were cancelled don't need to wait.
+		while self.timers.peek().is_some_and(|Reverse(timer)| is_finished(timer.task)) {
+			self.timers.pop();
+		}
+
+		if let Some(Reverse(timer)) = self.timers.peek() {
+			Turn::Sleeping(timer.at)
+		} else if self.external_tasks != 0 {
+			Turn::Waiting
+		} else {
+			Turn::Idle
+		}
+	}
+}
+
+fn is_finished(task: Gc<Native<Task>>) -> bool {
+	task.as_ref().is_ok_and(|task| task.is_finished())
+}
+
+fn never_finishes() -> crate::Error {
+	"awaited task will never finish, as every task is waiting on another".to_string().into()
+}
+
+// Blocks the current thread until `turn` has something to do.
+fn wait_for(turn: Turn) {
+	let inbox = with_loop(|event_loop| event_loop.inbox.clone());
+
+	match turn {
+		Turn::Ready | Turn::Idle => {}
+		Turn::Sleeping(until) => inbox.wait(Some(until)),
+		Turn::Waiting => inbox.wait(None),
+	}
+}
+
+/// Runs the current thread's event loop until `task` finishes, returning its result.
+///
+/// # Errors
+/// Returns whatever error `task` finished with. Additionally, since the event loop can't be run
+/// from within itself, this returns an error if called while a task is running, or if `task` can
+/// never finish.
+pub fn block_on(task: Gc<Native<Task>>) -> Result<Value> {
+	if is_running_task() {
+		return Err("the event loop can't be run while a task is running".to_string().into());
+	}
+
+	loop {
+		if let Some(result) = task.as_ref()?.result() {
+			return result;
+		}
+
+		let turn = turn();
+
+		// The task may have finished during the turn, in which case there's no need to wait.
+		if is_finished(task) {
+			continue;
+		}
+
+		match turn {
+			Turn::Idle => return Err(never_finishes()),
+			turn => wait_for(turn),
+		}
+	}
+}
+
+/// Runs the current thread's event loop until there are no tasks left to run.
+///
+/// Unlike [`block_on`], errors from the tasks aren't returned, as they're kept in the tasks
+/// themselves.
+pub fn run_until_idle() {
+	if is_running_task() {
+		return;
+	}
+
+	loop {
+		match turn() {
+			Turn::Idle => return,
+			turn => wait_for(turn),
+		}
+	}
+}
+
+/// A [`Future`] that drives the current thread's event loop until a task has finished.
+///
+/// Since event loops are per-thread, this must be created and polled on the thread that created
+/// the task. While the loop's waiting on a timer, a single helper thread per loop parks until the
+/// timer finishes and then wakes the futures up; hosts that have their own timers may prefer to
+/// use [`turn`] directly.
+#[must_use = "futures do nothing unless polled"]
+pub struct TaskFuture {
+	task: Gc<Native<Task>>,
+	id: u64,
+	inbox: Arc<Inbox>,
+	_not_send: PhantomData<*const ()>,
+}
+
+impl TaskFuture {
+	/// Creates a future which resolves to the result of `task`.
+	pub fn new(task: Gc<Native<Task>>) -> Self {
+		let inbox = with_loop(|event_loop| event_loop.inbox.clone());
+		let id = {
+			let mut futures = inbox.futures.lock().unwrap();
+			futures.next_id += 1;
+			futures.next_id
+		};
+
+		Self { task, id, inbox, _not_send: PhantomData }
+	}
+
+	fn finish(&self, result: Result<Value>) -> Poll<Result<Value>> {
+		self.inbox.futures.lock().unwrap().wakers.remove(&self.id);
+		Poll::Ready(result)
+	}
+}
+
+impl Future for TaskFuture {
+	type Output = Result<Value>;
+
+	fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+		// Set the waker before running, so external tasks that finish afterwards wake us up.
+		self
+			.inbox
+			.futures
+			.lock()
+			.unwrap()
+			.wakers
+			.insert(self.id, (self.task.to_value(), cx.waker().clone()));
+
+		let turn = turn();
+
+		match self.task.as_ref().map(|task| task.result()) {
+			Ok(Some(result)) => return self.finish(result),
+			Ok(None) => {}
+			Err(err) => return self.finish(Err(err)),
+		}
+
+		match turn {
+			Turn::Ready => cx.waker().wake_by_ref(),
+			Turn::Sleeping(until) => self.inbox.wake_futures_at(until),
+			Turn::Waiting => {}
+			Turn::Idle => return self.finish(Err(never_finishes())),
+		}
+
+		Poll::Pending
+	}
+}
+
+impl Drop for TaskFuture {
+	fn drop(&mut self) {
+		self.inbox.futures.lock().unwrap().wakers.remove(&self.id);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::sync::atomic::{AtomicBool, Ordering as AtomicOrdering};
+	use std::task::Wake;
+	use std::time::Duration;
+
+	// A waker that records whether it's been woken, and unparks the thread that created it.
+	struct Flag(AtomicBool, Thread);
+
+	impl Flag {
+		fn new() -> Arc<Self> {
+			Arc::new(Self(AtomicBool::new(false), thread::current()))
+		}
+
+		fn was_woken(&self) -> bool {
+			self.0.load(AtomicOrdering::SeqCst)
+		}
+	}
+
+	impl Wake for Flag {
+		fn wake(self: Arc<Self>) {
+			self.0.store(true, AtomicOrdering::SeqCst);
+			self.1.unpark();
+		}
+	}
+
+	fn poll(future: &mut TaskFuture, flag: &Arc<Flag>) -> Poll<Result<Value>> {
+		Pin::new(future).poll(&mut Context::from_waker(&Waker::from(flag.clone())))
+	}
+
+	#[test]
+	fn futures_are_woken_by_timers() {
+		let mut future = TaskFuture::new(Task::sleep(Duration::from_millis(10)).unwrap());
+		let flag = Flag::new();
+
+		// Each poll re-arms the loop's one timer thread, rather than starting another.
+		for _ in 0..100 {
+			assert!(poll(&mut future, &flag).is_pending());
+		}
+
+		while !flag.was_woken() {
+			thread::park();
+		}
+
+		assert!(poll(&mut future, &flag).is_ready());
+	}
+
+	#[test]
+	fn external_tasks_wake_every_future() {
+		let (first, first_completer) = Task::external().unwrap();
+		let (second, _second_completer) = Task::external().unwrap();
+		let mut first_future = TaskFuture::new(first);
+		let mut second_future = TaskFuture::new(second);
+		let first_flag = Flag::new();
+		let second_flag = Flag::new();
+
+		assert!(poll(&mut first_future, &first_flag).is_pending());
+		assert!(poll(&mut second_future, &second_flag).is_pending());
+
+		// The second future's waker mustn't replace the first's.
+		thread::spawn(move || first_completer.complete(Ok(Value::default()))).join().unwrap();
+		assert!(first_flag.was_woken());
+		assert!(poll(&mut first_future, &first_flag).is_ready());
+	}
+}
@@ -1,16 +1,20 @@
 //! Types associated with the [`Frame`] type.
 
 use crate::value::base::{Base, Flags};
-use crate::value::ty::{List, Text};
+use crate::value::ty::{List, Map, Text};
 use crate::value::{
 	Attributed, AttributedMut, Callable, Gc, HasAttributes, HasDefaultParent, HasFlags, HasParents,
 	ToValue, TryAttributed,
 };
 use crate::vm::block::BlockInner;
-use crate::vm::{Args, Block, Opcode, COUNT_IS_NOT_ONE_BYTE_BUT_USIZE, NUM_ARGUMENT_REGISTERS};
+use crate::vm::inline_cache::InlineCache;
+use crate::vm::{
+	coroutine, coverage, debugger, limits, profiler, Args, Block, Opcode, SourceLocation,
+	COUNT_IS_NOT_ONE_BYTE_BUT_USIZE, NUM_ARGUMENT_REGISTERS,
+};
 use crate::{Error, ErrorKind, Intern, Result, Value};
 use std::alloc::Layout;
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::fmt::{self, Debug, Formatter};
 use std::mem::MaybeUninit;
 use std::num::NonZeroUsize;
@@ -58,7 +62,7 @@ impl Drop for Inner {
 		// SAFETY:
 		// `self.unnamed_locals` was allocated by `crate::alloc`, and the layout is the same.
 		unsafe {
-			std::alloc::dealloc(self.unnamed_locals.cast::<u8>(), layout);
+			crate::dealloc(self.unnamed_locals.cast::<u8>(), layout);
 		}
 	}
 }
@@ -175,10 +179,44 @@ impl Frame {
 		self.block
 	}
 
+	/// Gets where the block for this stackframe was defined.
+	pub fn source_location(&self) -> &SourceLocation {
+		&self.inner_block.location
+	}
+
+	/// Gets the line of the code that was most recently executed.
+	pub fn current_line(&self) -> usize {
+		self.inner_block.line_at(self.pos.saturating_sub(1))
+	}
+
+	/// Gets all the named locals which have been assigned, in the order they were declared.
+	pub fn named_locals(&self) -> Result<Vec<(Intern, Value)>> {
+		let mut locals = Vec::with_capacity(self.inner_block.named_locals.len());
+
+		for (idx, &name) in self.inner_block.named_locals.iter().enumerate() {
+			let value = if self.is_object() {
+				self.attributes().get_unbound_attr(name)?
+			} else {
+				// SAFETY: `idx` is in bounds, as we're iterating over `named_locals`.
+				unsafe { *self.named_locals.add(idx) }
+			};
+
+			if let Some(value) = value {
+				locals.push((name, value));
+			}
+		}
+
+		Ok(locals)
+	}
+
 	pub(crate) fn is_object(&self) -> bool {
 		self.flags().contains(FLAG_IS_OBJECT)
 	}
 
+	pub(crate) fn is_running(&self) -> bool {
+		self.flags().contains(FLAG_CURRENTLY_RUNNING)
+	}
+
 	pub(crate) fn convert_to_object(&mut self) -> Result<()> {
 		// If we're already an object, nothing else needed to be done.
 		if !self.flags().try_acquire_all_user(FLAG_IS_OBJECT) {
@@ -189,7 +227,7 @@ impl Frame {
 
 		// Once we start referencing the frame as an object, we no longer can longer use the "block is
 		// our only parent" optimization.
-		self.set_parents(List::from_slice(&[Gc::<Self>::parent(), block]));
+		self.parents_mut().set_lexical(List::from_slice(&[Gc::<Self>::parent(), root(), block]));
 
 		let (data, mut attrs, _) = self.0.deconstruct_mut();
 
@@ -261,10 +299,14 @@ impl Frame {
 			return Ok(attr);
 		}
 
-		// When we're not an object, we first check the parent block and then the frame.
-		// when we are an object, the frame and block (in that order) are checked in the previous
-		// function.
+		// When we're not an object, we first check the parent block and then the root and frame.
+		// when we are an object, the frame, root, and block (in that order) are checked in the
+		// previous function.
 		if !self.is_object() {
+			if let Some(attr) = root().get_unbound_attr(attr_name)? {
+				return Ok(attr);
+			}
+
 			if let Some(attr) = Gc::<Self>::parent().get_unbound_attr(attr_name)? {
 				return Ok(attr);
 			}
@@ -475,18 +517,66 @@ thread_local! {
 	);
 }
 
+thread_local! {
+	static ROOT: Cell<Option<Value>> = const { Cell::new(None) };
+}
+
+/// Gets the object that frames look up variables in when they aren't defined locally, such as
+/// `print` or `Integer`.
+///
+/// This is [`Kernel::instance`](crate::value::ty::Kernel::instance) unless [`set_root`] was
+/// called on the current thread.
+#[must_use]
+pub fn root() -> Value {
+	ROOT.with(Cell::get).unwrap_or_else(crate::value::ty::Kernel::instance)
+}
+
+/// Sets the [root](root) for the current thread.
+///
+/// This is how embedders can choose what scripts can access; see [`Kernel::with_capabilities`](
+/// crate::value::ty::Kernel::with_capabilities).
+pub fn set_root(root: Value) {
+	ROOT.with(|cell| cell.set(Some(root)));
+}
+
 /// Provides access to the stackframe.
 pub fn with_stackframes<F: FnOnce(&[Gc<Frame>]) -> T, T>(func: F) -> T {
 	STACKFRAMES.with(|sf| func(&sf.borrow()))
 }
 
+pub(crate) fn stackframes_len() -> usize {
+	STACKFRAMES.with(|sf| sf.borrow().len())
+}
+
+/// Removes every stackframe from `base` onwards, so they can be [restored](restore_stackframes)
+/// later on; used when suspending coroutines.
+pub(crate) fn take_stackframes(base: usize) -> Vec<Gc<Frame>> {
+	STACKFRAMES.with(|sf| sf.borrow_mut().split_off(base))
+}
+
+/// Pushes `frames` back onto the stackframe, returning where they start.
+pub(crate) fn restore_stackframes(frames: Vec<Gc<Frame>>) -> usize {
+	STACKFRAMES.with(|sf| {
+		let mut sf = sf.borrow_mut();
+		let base = sf.len();
+		sf.extend(frames);
+		base
+	})
+}
+
 impl Gc<Frame> {
+	/// Whether the frame has been [converted into an object](Frame::convert_to_object), so its
+	/// locals are stored as attributes. Unlike [`Frame::is_object`], this doesn't borrow it.
+	pub(crate) fn has_escaped(self) -> bool {
+		self.flags().contains(FLAG_IS_OBJECT)
+	}
+
 	/// Enters the given `frame`, executes `func`, then returns the result of `func`.
 	pub fn enter_stackframe<F: FnOnce() -> Result<T>, T>(self, func: F) -> Result<T> {
 		STACKFRAMES.with(|stackframes| {
 			let mut sf = stackframes.borrow_mut();
 
-			if MAX_STACKFRAME_LEN < sf.len() {
+			if limits::max_stackframes() < sf.len() || coroutine::max_stackframes() <= sf.len() {
 				drop(sf); // so we dont have a mutable borrow
 				return Err(ErrorKind::StackOverflow.into());
 			}
@@ -522,7 +612,7 @@ impl Gc<Frame> {
 			return Err(ErrorKind::StackframeIsCurrentlyRunning(self).into());
 		}
 
-		let result = self.enter_stackframe(|| self.run_inner());
+		let result = profiler::profile_frame(self, || self.enter_stackframe(|| self.run_inner()));
 
 		if !self
 			.as_ref()
@@ -548,11 +638,41 @@ impl Gc<Frame> {
 		}
 	}
 
+	/// Wraps `self` in an [`Iterator`](crate::value::ty::Iterator) which runs `self` until it
+	/// `yield`s, returning the yielded value.
+	///
+	/// `self` is run on its own stack, so `yield` can be called from anywhere within it (such as from
+	/// within a `while` loop), and each call to `next` picks up right where the previous one left
+	/// off. Once `self` finishes, `StopIteration` is returned. That stack is
+	/// [`coroutine_stack_size`](crate::vm::Limits::coroutine_stack_size) bytes, which limits how
+	/// deeply code within `self` can recurse.
+	pub fn into_generator(self) -> Result<Gc<crate::value::ty::Iterator>> {
+		use crate::vm::coroutine::{Coroutine, Kind, Resumed};
+
+		let mut coroutine = Some(Coroutine::new(Kind::Generator, move || self.run())?);
+
+		Ok(crate::iterator! { "Frame::generator";
+			let Some(running) = coroutine.as_mut() else {
+				return Err(StopIteration.into());
+			};
+
+			match running.resume(Value::default())? {
+				Resumed::Suspended(value) => Ok(value),
+				Resumed::Finished(result) => {
+					coroutine = None;
+					result?;
+					Err(StopIteration.into())
+				}
+			}
+		})
+	}
+
 	fn run_inner(self) -> Result<()> {
 		let mut args = [MaybeUninit::<Value>::uninit(); NUM_ARGUMENT_REGISTERS];
 		let mut this = self.as_mut()?;
 		let mut variable_args_count = MaybeUninit::<usize>::uninit();
 		let mut interned = MaybeUninit::<Intern>::uninit();
+		let mut inline_cache = MaybeUninit::<*const InlineCache>::uninit();
 
 		macro_rules! without_this {
 			($($code:tt)*) => {{
@@ -575,9 +695,26 @@ impl Gc<Frame> {
 			};
 		}
 
+		// Every call uses up some fuel, even if there aren't any opcodes to run.
+		if this.is_done() {
+			limits::tick()?;
+		}
+
 		// SAFETY: we're guaranteed the next byte, if it exists, is valid, because `Frame`s can only
 		// be created with valid bytecode.
 		while let Some(op) = unsafe { this.next_op()? } {
+			limits::tick()?;
+
+			if debugger::is_enabled() || coverage::is_enabled() {
+				if let Some(line) = this.inner_block.line_starting_at(this.pos - 1) {
+					coverage::on_line(&this.inner_block.location, line);
+
+					if debugger::is_enabled() {
+						without_this! { debugger::on_line(self, line)? };
+					}
+				}
+			}
+
 			if cfg!(debug_assertions) {
 				for position in args.iter_mut().take(NUM_ARGUMENT_REGISTERS) {
 					*position = MaybeUninit::uninit();
@@ -585,6 +722,7 @@ impl Gc<Frame> {
 
 				variable_args_count = MaybeUninit::uninit();
 				interned = MaybeUninit::uninit();
+				inline_cache = MaybeUninit::uninit();
 			}
 
 			// SAFETY: we're guaranteed the next byte, if it exists, is valid, because `Frame`s can
@@ -610,6 +748,13 @@ impl Gc<Frame> {
 					interned.write(unsafe { Intern::from_bits_unchecked(this.next_u64()) });
 				}
 
+				if op.has_inline_cache() {
+					// SAFETY: `self` is well-formed, so opcodes with inline caches are followed by the
+					// index of one.
+					let index = unsafe { this.next_count() };
+					inline_cache.write(&this.inner_block.inline_caches[index]);
+				}
+
 				if op.is_variable_simple() {
 					// SAFETY: we're guaranteed the next byte exists, because `Frame`s can only be
 					// created with valid bytecode.
@@ -671,6 +816,27 @@ impl Gc<Frame> {
 					List::from_slice(slice).to_value()
 				}
 
+				Opcode::CreateMap => {
+					// SAFETY: `self` is well-formed, so after `CreateMap` and `dst` follows a count,
+					// and then that many locals.
+					let elements = unsafe {
+						let amnt = this.next_count();
+						let mut elements = Vec::with_capacity(amnt);
+
+						for _ in 0..amnt {
+							elements.push(this.next_local()?);
+						}
+
+						elements
+					};
+
+					// Hashing keys can call arbitrary functions, so we cant hold onto `this`.
+					without_this! {
+						Map::from_pairs(elements.chunks_exact(2).map(|pair| (pair[0], pair[1])))?
+							.to_value()
+					}
+				}
+
 				// SAFETY: `self` is well-formed, so we know the first argument to `Mov` is present
 				Opcode::Mov => unsafe { args[0].assume_init() },
 
@@ -745,9 +911,22 @@ impl Gc<Frame> {
 					object.try_get_attr(attr)?
 				},
 				Opcode::GetAttrIntern => without_this! {
-					// SAFETY: `self` is well-formed, so we know that the first two arguments exist.
-					let (object, attr) = unsafe { (args[0].assume_init(), interned.assume_init()) };
-					object.try_get_attr(attr)?
+					// SAFETY: `self` is well-formed, so we know that the first two arguments and the
+					// cache exist. The cache lives as long as our block, which we keep alive.
+					let (object, attr, cache) = unsafe {
+						(args[0].assume_init(), interned.assume_init(), &*inline_cache.assume_init())
+					};
+
+					match cache.get_unbound_attr(object, attr)? {
+						Some(value) => crate::value::bind(object, value)?,
+						None => {
+							return Err(ErrorKind::UnknownAttribute {
+								object,
+								attribute: attr.to_value(),
+							}
+							.into())
+						}
+					}
 				},
 				Opcode::GetUnboundAttr => without_this! {
 					// SAFETY: `self` is well-formed, so we know that the first two arguments exist.
@@ -800,21 +979,31 @@ impl Gc<Frame> {
 								debug_assert!(this.is_object());
 							}
 
-							object.set_attr(attr, value)?;
+							without_this! {
+								object.set_attr(attr, value)?;
+							}
 						}
 						LocalTarget::Named(index) => {
 							// SAFETY: `self` is well-formed, so we we're guaranteed `index` is a
 							// valid local target.
 							let name = unsafe { *this.inner_block.named_locals.get_unchecked(index) };
-							let object = this.get_unbound_attr_mut(name)?;
+							let mut object = *this.get_unbound_attr_mut(name)?;
 
-							if self.to_value().is_identical(*object) {
+							if self.to_value().is_identical(object) {
 								this.convert_to_object()?;
 								without_this! {
 									self.to_value().set_attr(attr, value)?;
 								}
 							} else {
-								object.set_attr(attr, value)?;
+								// We're unlocked while setting it, as its new parents may include us.
+								let original = object;
+								without_this! {
+									object.set_attr(attr, value)?;
+								}
+
+								if !object.is_identical(original) {
+									*this.get_unbound_attr_mut(name)? = object;
+								}
 							}
 						}
 					}
@@ -854,22 +1043,32 @@ impl Gc<Frame> {
 									self.to_value().set_attr(attr, value)?;
 								}
 							} else {
-								object.set_attr(attr, value)?;
+								without_this! {
+									object.set_attr(attr, value)?;
+								}
 							}
 						}
 						LocalTarget::Named(index) => {
 							// SAFETY: `self` is well-formed, so we we're guaranteed `index` is a
 							// valid local target.
 							let name = unsafe { *this.inner_block.named_locals.get_unchecked(index) };
-							let object = this.get_unbound_attr_mut(name)?;
+							let mut object = *this.get_unbound_attr_mut(name)?;
 
-							if self.to_value().is_identical(*object) {
+							if self.to_value().is_identical(object) {
 								this.convert_to_object()?;
 								without_this! {
 									self.to_value().set_attr(attr, value)?;
 								}
 							} else {
-								object.set_attr(attr, value)?;
+								// We're unlocked while setting it, as its new parents may include us.
+								let original = object;
+								without_this! {
+									object.set_attr(attr, value)?;
+								}
+
+								if !object.is_identical(original) {
+									*this.get_unbound_attr_mut(name)? = object;
+								}
 							}
 						}
 					}
@@ -898,12 +1097,26 @@ impl Gc<Frame> {
 				},
 				Opcode::CallAttrSimpleIntern => without_this! {
 					// SAFETY: `self` is well-formed, so we know that the first two arguments exist, and
-					// are followed by an argument slice
-					let (object, attr, args_slice) = unsafe {
-						(args[0].assume_init(), interned.assume_init(), args_slice!(start=1))
+					// are followed by an argument slice. See `GetAttrIntern` for the cache's safety.
+					let (object, attr, args_slice, cache) = unsafe {
+						(
+							args[0].assume_init(),
+							interned.assume_init(),
+							args_slice!(start=1),
+							&*inline_cache.assume_init(),
+						)
 					};
 
-					object.call_attr(attr, args_slice)?
+					match cache.get_unbound_attr(object, attr)? {
+						Some(func) => func.call(args_slice.with_this(object))?,
+						None => {
+							return Err(ErrorKind::UnknownAttribute {
+								object,
+								attribute: attr.to_value(),
+							}
+							.into())
+						}
+					}
 				},
 
 				Opcode::Add
@@ -1004,6 +1217,13 @@ pub mod funcs {
 		frame.run()
 	}
 
+	/// Wraps `frame` as a generator; see [`Gc<Frame>::into_generator`].
+	pub fn iter(frame: Gc<Frame>, args: Args<'_>) -> Result<Value> {
+		args.assert_no_arguments()?;
+
+		frame.into_generator().map(ToValue::to_value)
+	}
+
 	/// Returns a debug representation of `frame`.
 	pub fn dbg(frame: Gc<Frame>, args: Args<'_>) -> Result<Value> {
 		args.assert_no_arguments()?;
@@ -1020,9 +1240,10 @@ pub mod funcs {
 	}
 }
 
-quest_type_attrs! { for Gc<Frame>, parents [Kernel, Callable];
+quest_type_attrs! { for Gc<Frame>, parent Callable;
 	resume => meth funcs::resume,
 	restart => meth funcs::restart,
+	iter => meth funcs::iter,
 	dbg => meth funcs::dbg,
 	// "+" => meth qs_add,
 	// "@text" => meth qs_at_text,
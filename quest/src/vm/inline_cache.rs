@@ -0,0 +1,350 @@
+//! Inline caches for attribute lookups.
+//!
+//! Every `GetAttrIntern` and `CallAttrSimpleIntern` opcode has its own [`InlineCache`], which
+//! remembers where the attribute was found the last time the opcode ran. The cache is used as long as
+//! the object has the same [shape](Shape) and parents as last time, and nothing has been modified
+//! since. Since the shape determines whether the object defines the attribute itself (and in which
+//! slot), this doesn't need to search the object's own attributes either.
+//!
+//! Caches are shared by every thread running the opcode, but never block: if another thread is
+//! updating a cache at the same time, it's simply ignored.
+//!
+//! Instead of tracking which caches depend on which values, there's a single global version. Each
+//! ancestor that's used to fill a cache is flagged, and setting or deleting attributes on (or
+//! changing the parents of) a flagged value bumps the version, invalidating every cache. As methods
+//! are usually defined up front, this happens rarely in practice.
+//!
+//! Lists of parents can also be modified in place (eg via `__parents__.push(...)`), so they're
+//! flagged too, and releasing a mutable borrow of one bumps the version.
+//!
+//! The amount of hits and misses are tracked, and can be retrieved via [`stats`].
+
+use crate::value::base::{Attribute, Flags, Shape};
+use crate::value::{Attributed, Gc, HasAttributes, HasFlags, HasParents};
+use crate::vm::Frame;
+use crate::{Intern, Result, Value};
+use std::sync::atomic::{self, AtomicU64, AtomicUsize, Ordering};
+
+static VERSION: AtomicU64 = AtomicU64::new(0);
+static HITS: AtomicU64 = AtomicU64::new(0);
+static MISSES: AtomicU64 = AtomicU64::new(0);
+
+/// The amount of times inline caches were used.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Stats {
+	/// The amount of lookups that were answered by an inline cache.
+	pub hits: u64,
+
+	/// The amount of lookups that had to search an object's ancestors.
+	pub misses: u64,
+}
+
+/// Gets the amount of hits and misses since the program started, or [`reset_stats`] was called.
+#[must_use]
+pub fn stats() -> Stats {
+	Stats { hits: HITS.load(Ordering::Relaxed), misses: MISSES.load(Ordering::Relaxed) }
+}
+
+/// Resets the hit and miss counts to zero.
+pub fn reset_stats() {
+	HITS.store(0, Ordering::Relaxed);
+	MISSES.store(0, Ordering::Relaxed);
+}
+
+/// Gets the current version, which changes whenever the inline caches are invalidated.
+pub(crate) fn version() -> u64 {
+	VERSION.load(Ordering::Acquire)
+}
+
+/// Flags `value` as something a cache depends on, so that modifying it invalidates the caches.
+pub(crate) fn depend_on(value: Value) {
+	if value.is_allocated() {
+		unsafe { value.get_gc_any_unchecked() }.flags().insert_internal(Flags::INLINE_CACHED);
+	}
+}
+
+/// Invalidates every inline cache.
+pub(crate) fn invalidate() {
+	VERSION.fetch_add(1, Ordering::AcqRel);
+}
+
+/// Invalidates every inline cache if the value whose `flags` these are is used by any of them.
+///
+/// This must be called before a value's attributes or parents are modified.
+#[inline]
+pub(crate) fn on_modify(flags: &Flags) {
+	if flags.contains(Flags::INLINE_CACHED) {
+		invalidate();
+	}
+}
+
+/// The cache for a single opcode.
+///
+/// Its entry is guarded by a sequence lock, so lookups never wait on each other: `sequence` is odd
+/// while the entry is being written, and is bumped again once it's done. If it's odd, or changed
+/// while the entry was being read, the entry may be torn, and is ignored.
+#[derive(Debug, Default)]
+pub(crate) struct InlineCache {
+	sequence: AtomicU64,
+	version: AtomicU64,
+	shape: AtomicUsize,
+	parents: AtomicU64,
+	// One more than the slot the attribute is in, or zero if it's inherited.
+	slot: AtomicUsize,
+	inherited: AtomicU64,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Entry {
+	version: u64,
+	key: Key,
+	found: Found,
+}
+
+// Objects with the same key are guaranteed to have the attribute in the same place.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Key {
+	// The address of the object's shape, or zero if its attributes are stored in a map.
+	shape: usize,
+	parents: u64,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Found {
+	// The attribute is in this slot of the object's shape.
+	Own(usize),
+	// The attribute is defined by one of the object's ancestors.
+	Inherited(Value),
+}
+
+impl InlineCache {
+	// Gets the entry, unless it's empty or being written.
+	fn load(&self) -> Option<Entry> {
+		let sequence = self.sequence.load(Ordering::Acquire);
+
+		// It's zero before the first write.
+		if sequence == 0 || sequence % 2 == 1 {
+			return None;
+		}
+
+		let version = self.version.load(Ordering::Relaxed);
+		let key = Key {
+			shape: self.shape.load(Ordering::Relaxed),
+			parents: self.parents.load(Ordering::Relaxed),
+		};
+		let slot = self.slot.load(Ordering::Relaxed);
+		let inherited = self.inherited.load(Ordering::Relaxed);
+
+		// Pairs with the fence in `store`, so if any of the above saw a write from it, we'll see
+		// that the sequence has changed.
+		atomic::fence(Ordering::Acquire);
+
+		if self.sequence.load(Ordering::Relaxed) != sequence {
+			return None;
+		}
+
+		let found = match slot {
+			// SAFETY: The bits came from a `Value`, as we just checked they weren't torn.
+			0 => Found::Inherited(unsafe { Value::from_bits(inherited) }),
+			slot => Found::Own(slot - 1),
+		};
+
+		Some(Entry { version, key, found })
+	}
+
+	// Replaces the entry, unless another thread is already doing so.
+	fn store(&self, entry: Entry) {
+		let sequence = self.sequence.load(Ordering::Relaxed);
+
+		if sequence % 2 == 1
+			|| self
+				.sequence
+				.compare_exchange(sequence, sequence + 1, Ordering::Relaxed, Ordering::Relaxed)
+				.is_err()
+		{
+			return;
+		}
+
+		atomic::fence(Ordering::Release);
+
+		let (slot, inherited) = match entry.found {
+			Found::Own(slot) => (slot + 1, 0),
+			Found::Inherited(value) => (0, value.bits()),
+		};
+
+		self.version.store(entry.version, Ordering::Relaxed);
+		self.shape.store(entry.key.shape, Ordering::Relaxed);
+		self.parents.store(entry.key.parents, Ordering::Relaxed);
+		self.slot.store(slot, Ordering::Relaxed);
+		self.inherited.store(inherited, Ordering::Relaxed);
+
+		self.sequence.store(sequence + 2, Ordering::Release);
+	}
+
+	/// Gets the unbound attribute `attr` of `object`, like [`Value::get_unbound_attr`].
+	pub(crate) fn get_unbound_attr(&self, object: Value, attr: Intern) -> Result<Option<Value>> {
+		if attr.is_special() || !is_cacheable(object) {
+			return object.get_unbound_attr(attr);
+		}
+
+		let key = match Key::of(object, attr)? {
+			Ok(key) => key,
+			Err(own) => return Ok(Some(own)),
+		};
+
+		if let Some(cached) = self.load() {
+			if cached.key == key && cached.version == version() {
+				HITS.fetch_add(1, Ordering::Relaxed);
+
+				return match cached.found {
+					Found::Own(index) => own_slot(object, attr, key.shape, index),
+					Found::Inherited(value) => Ok(Some(value)),
+				};
+			}
+		}
+
+		MISSES.fetch_add(1, Ordering::Relaxed);
+
+		if key.shape != 0 {
+			// SAFETY: Shapes are never freed, and `key.shape` came from a reference to one.
+			let shape = unsafe { &*(key.shape as *const Shape) };
+
+			if let Some(index) = shape.index_of(attr) {
+				self.store(Entry { version: version(), key, found: Found::Own(index) });
+				return own_slot(object, attr, key.shape, index);
+			}
+		}
+
+		// The version has to be read before the ancestors are flagged: if one of them is modified
+		// in between, the entry will just be out of date.
+		let version = version();
+		let ancestors = object.mro()?;
+
+		if ancestors.iter().any(|&ancestor| !is_cacheable(ancestor)) {
+			return object.get_unbound_attr(attr);
+		}
+
+		// Only the ancestors up to (and including) the one that defines the attribute are flagged,
+		// as modifying any later ones can't change where it's found. This keeps frames that are
+		// far up the chain (and so have their locals assigned often) from invalidating the caches.
+		for &ancestor in &ancestors[1..] {
+			depend_on(ancestor);
+
+			if let Some(value) = ancestor.get_own_unbound_attr(attr)? {
+				self.store(Entry { version, key, found: Found::Inherited(value) });
+				return Ok(Some(value));
+			}
+		}
+
+		Ok(None)
+	}
+}
+
+impl Key {
+	// Gets the key for `object`. If its attributes are stored in a map, they're searched for `attr`
+	// first, which is returned if found.
+	fn of(object: Value, attr: Intern) -> Result<std::result::Result<Self, Value>> {
+		if !object.is_allocated() {
+			// SAFETY: We just checked that `object` isn't allocated.
+			let parents = unsafe { object.parents_for_unallocated() }.bits();
+			return Ok(Ok(Self { shape: Shape::root() as *const Shape as usize, parents }));
+		}
+
+		let gc = unsafe { object.get_gc_any_unchecked() };
+		let header = gc.as_ref()?;
+		let attributes = header.attributes();
+
+		let shape = match attributes.shape() {
+			Some(shape) => shape as *const Shape as usize,
+			None => match attributes.get_unbound_attr(attr)? {
+				Some(own) => return Ok(Err(own)),
+				None => 0,
+			},
+		};
+
+		Ok(Ok(Self { shape, parents: header.parents().identity() }))
+	}
+}
+
+// Gets the value in the slot `index` of `object`'s shape, which was `shape` when its key was made.
+// As another thread may have changed its shape since then, both are read under the same borrow, and
+// if its shape's changed it's just looked up normally.
+fn own_slot(object: Value, attr: Intern, shape: usize, index: usize) -> Result<Option<Value>> {
+	debug_assert!(object.is_allocated());
+
+	let gc = unsafe { object.get_gc_any_unchecked() };
+	let header = gc.as_ref()?;
+	let attributes = header.attributes();
+
+	if attributes.shape().map_or(0, |current| current as *const Shape as usize) == shape {
+		return Ok(attributes.slot(index));
+	}
+
+	drop(header);
+	object.get_unbound_attr(attr)
+}
+
+// Frames store their locals outside of their attributes until they escape (eg via `:0`, or by
+// being returned from `object`), so lookups involving them are only cached afterwards.
+fn is_cacheable(value: Value) -> bool {
+	match value.downcast::<Gc<Frame>>() {
+		Some(frame) => frame.has_escaped(),
+		None => true,
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::value::ty::{Object, Wrap};
+	use crate::value::{AttributedMut, ToValue};
+
+	#[test]
+	fn invalidated_by_modifying_ancestors() {
+		let mut class = Wrap::with_parent((), Object::instance()).to_value();
+		class.set_attr(Intern::len, 1_i64.to_value()).unwrap();
+		let instance = Wrap::with_parent((), class).to_value();
+		let other = Wrap::with_parent((), class).to_value();
+
+		let cache = InlineCache::default();
+		let get = |object: Value| cache.get_unbound_attr(object, Intern::len).unwrap().unwrap();
+
+		assert_eq!(get(instance).bits(), 1_i64.to_value().bits());
+		assert!(cache.load().is_some());
+
+		// Objects with the same parents share the entry, but their own attributes come first.
+		let mut other = other;
+		assert_eq!(get(other).bits(), 1_i64.to_value().bits());
+		other.set_attr(Intern::len, 2_i64.to_value()).unwrap();
+		assert_eq!(get(other).bits(), 2_i64.to_value().bits());
+
+		// Modifying an ancestor invalidates the entry.
+		class.set_attr(Intern::len, 3_i64.to_value()).unwrap();
+		assert_eq!(get(instance).bits(), 3_i64.to_value().bits());
+		class.del_attr(Intern::len).unwrap();
+		assert!(cache.get_unbound_attr(instance, Intern::len).unwrap().is_none());
+	}
+
+	#[test]
+	fn caches_escaped_frames() {
+		use crate::value::ty::List;
+
+		let objects = crate::run_code("base = object({ len = 1 }); [base, object([base], {})]")
+			.unwrap()
+			.try_downcast::<Gc<List>>()
+			.unwrap();
+		let (mut base, derived) = {
+			let objects = objects.as_ref().unwrap();
+			(objects.as_slice()[0], objects.as_slice()[1])
+		};
+
+		let cache = InlineCache::default();
+		let get = |object: Value| cache.get_unbound_attr(object, Intern::len).unwrap().unwrap();
+
+		assert_eq!(get(derived).bits(), 1_i64.to_value().bits());
+		assert!(cache.load().is_some());
+
+		base.set_attr(Intern::len, 2_i64.to_value()).unwrap();
+		assert_eq!(get(derived).bits(), 2_i64.to_value().bits());
+	}
+}
@@ -0,0 +1,374 @@
+//! Execution limits, for running untrusted code.
+//!
+//! [`Limits`] are set per-thread via [`set_limits`], and are enforced by [`Frame`](crate::vm::Frame)s
+//! as they execute. Each limit has its own [`ErrorKind`], so embedders can tell which was exceeded:
+//!
+//! | limit | error |
+//! |-------|-------|
+//! | [`fuel`](Limits::fuel) | [`ErrorKind::OutOfFuel`] |
+//! | [`timeout`](Limits::timeout) | [`ErrorKind::DeadlineExceeded`] |
+//! | [`max_heap_size`](Limits::max_heap_size) | [`ErrorKind::HeapLimitExceeded`] |
+//! | [`max_stackframes`](Limits::max_stackframes) | [`ErrorKind::StackOverflow`] |
+//! | [`coroutine_stack_size`](Limits::coroutine_stack_size) | [`ErrorKind::StackOverflow`] |
+//!
+//! Once a limit is exceeded, it stays exceeded until [`set_limits`] is called again, so scripts
+//! can't simply ignore the error and keep going.
+
+use crate::vm::coroutine::DEFAULT_STACK_SIZE;
+use crate::vm::frame::MAX_STACKFRAME_LEN;
+use crate::{ErrorKind, Result};
+use std::cell::{Cell, RefCell};
+use std::sync::atomic::{AtomicIsize, AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+// How many threads have limits set. It's checked before every instruction (and allocation), so we
+// don't have to touch `STATE` when no limits are set.
+static LIMITED_THREADS: AtomicUsize = AtomicUsize::new(0);
+
+// Counts towards `LIMITED_THREADS` while it's alive, so threads that exit with limits set don't
+// keep them enabled.
+struct Limited;
+
+impl Limited {
+	fn new() -> Self {
+		LIMITED_THREADS.fetch_add(1, Ordering::Relaxed);
+		Self
+	}
+}
+
+impl Drop for Limited {
+	fn drop(&mut self) {
+		LIMITED_THREADS.fetch_sub(1, Ordering::Relaxed);
+	}
+}
+
+fn is_enabled() -> bool {
+	LIMITED_THREADS.load(Ordering::Relaxed) != 0
+}
+
+// Checking the clock is comparatively slow, so we only do it every so often.
+const INSTRUCTIONS_PER_DEADLINE_CHECK: u32 = 1024;
+
+/// Budgets for executing Quest code.
+///
+/// The default has no limits, other than the default amount of stackframes and size of coroutine
+/// stacks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Limits {
+	/// The maximum amount of instructions that may be executed. Each opcode counts as an
+	/// instruction, as does calling a block without any, so that every call uses up some fuel.
+	pub fuel: Option<u64>,
+
+	/// The maximum amount of wall-clock time code may run for, starting from when the limits are
+	/// [set](set_limits).
+	pub timeout: Option<Duration>,
+
+	/// The maximum amount of bytes that may be [allocated](crate::alloc) (and not yet freed) while
+	/// the limits are set. Memory that was allocated beforehand, or by unrelated threads, doesn't
+	/// count towards it.
+	///
+	/// Since allocations themselves can't fail, this is checked before every instruction, so code
+	/// may exceed it by one instruction's worth of allocations.
+	pub max_heap_size: Option<usize>,
+
+	/// The maximum amount of frames that may be running at once.
+	pub max_stackframes: usize,
+
+	/// The size, in bytes, of the stacks that generators and tasks run on. Only as many frames as
+	/// fit on one may be running within a generator or task at once.
+	pub coroutine_stack_size: usize,
+}
+
+impl Default for Limits {
+	fn default() -> Self {
+		Self {
+			fuel: None,
+			timeout: None,
+			max_heap_size: None,
+			max_stackframes: MAX_STACKFRAME_LEN,
+			coroutine_stack_size: DEFAULT_STACK_SIZE,
+		}
+	}
+}
+
+#[derive(Debug, Clone, Copy)]
+struct State {
+	limits: Limits,
+	deadline: Option<Instant>,
+	until_deadline_check: u32,
+}
+
+// The parts of the budget that are shared with spawned threads, so spawning one can't be used to
+// get around the limits.
+#[derive(Debug, Clone, Default)]
+struct Shared {
+	fuel: Option<Arc<AtomicU64>>,
+	// Bytes allocated minus bytes freed since the limits were set. This can go negative when memory
+	// from beforehand is freed.
+	heap_size: Arc<AtomicIsize>,
+}
+
+thread_local! {
+	static STATE: Cell<State> = Cell::new(State::new(Limits::default()));
+	static SHARED: RefCell<Shared> = RefCell::default();
+	static LIMITED: RefCell<Option<Limited>> = const { RefCell::new(None) };
+}
+
+impl State {
+	fn new(limits: Limits) -> Self {
+		Self {
+			limits,
+			deadline: limits.timeout.map(|timeout| Instant::now() + timeout),
+			until_deadline_check: 0,
+		}
+	}
+}
+
+/// The current thread's limits, along with what's left of them.
+///
+/// [Spawned threads](crate::value::ty::thread::Thread) share their parent's budget, rather than
+/// starting with a fresh one: they use up the same fuel and heap, and have the same deadline.
+#[derive(Debug, Clone)]
+pub(crate) struct Budget {
+	state: State,
+	shared: Shared,
+}
+
+/// Gets the current thread's budget, so that it can be shared with another thread.
+pub(crate) fn budget() -> Budget {
+	Budget { state: STATE.with(Cell::get), shared: SHARED.with(|shared| shared.borrow().clone()) }
+}
+
+/// Sets the current thread's budget, sharing it with whichever thread it came from.
+pub(crate) fn set_budget(budget: Budget) {
+	let is_limited = budget.state.limits != Limits::default();
+
+	STATE.with(|state| state.set(budget.state));
+	let previous_shared = SHARED.with(|shared| shared.replace(budget.shared));
+	let previous_limited = LIMITED.with(|current| current.replace(is_limited.then(Limited::new)));
+	drop((previous_shared, previous_limited));
+}
+
+/// Sets the limits for the current thread, resetting any fuel, deadline, and heap usage.
+pub fn set_limits(limits: Limits) {
+	let shared =
+		Shared { fuel: limits.fuel.map(|fuel| Arc::new(AtomicU64::new(fuel))), ..Shared::default() };
+	set_budget(Budget { state: State::new(limits), shared });
+}
+
+/// Gets the limits for the current thread.
+#[must_use]
+pub fn limits() -> Limits {
+	STATE.with(|state| state.get().limits)
+}
+
+/// Gets the amount of fuel the current thread has left, or `None` if it's unlimited.
+#[must_use]
+pub fn remaining_fuel() -> Option<u64> {
+	SHARED.with(|shared| shared.borrow().fuel.as_ref().map(|fuel| fuel.load(Ordering::Relaxed)))
+}
+
+/// Records that `bytes` were allocated (or freed, if negative) on the current thread.
+#[inline]
+pub(crate) fn record_allocation(bytes: isize) {
+	if is_enabled() {
+		// Allocations can happen while the thread's exiting, after `SHARED` is gone.
+		let _ = SHARED.try_with(|shared| {
+			shared.borrow().heap_size.fetch_add(bytes, Ordering::Relaxed);
+		});
+	}
+}
+
+pub(crate) fn max_stackframes() -> usize {
+	if is_enabled() {
+		limits().max_stackframes
+	} else {
+		MAX_STACKFRAME_LEN
+	}
+}
+
+pub(crate) fn coroutine_stack_size() -> usize {
+	if is_enabled() {
+		limits().coroutine_stack_size
+	} else {
+		DEFAULT_STACK_SIZE
+	}
+}
+
+/// Consumes one instruction's worth of fuel, returning an error if any limit's been exceeded.
+#[inline]
+pub(crate) fn tick() -> Result<()> {
+	if is_enabled() {
+		let result = STATE.with(|state| {
+			let mut current = state.get();
+			let result = SHARED.with(|shared| current.tick(&shared.borrow()));
+			state.set(current);
+			result
+		});
+
+		result.map_err(Into::into)
+	} else {
+		Ok(())
+	}
+}
+
+impl State {
+	// Errors are only created once `SHARED` is no longer borrowed, as creating them may allocate.
+	fn tick(&mut self, shared: &Shared) -> std::result::Result<(), ErrorKind> {
+		if let Some(fuel) = &shared.fuel {
+			if fuel
+				.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |fuel| fuel.checked_sub(1))
+				.is_err()
+			{
+				return Err(ErrorKind::OutOfFuel);
+			}
+		}
+
+		if let Some(limit) = self.limits.max_heap_size {
+			let size = shared.heap_size.load(Ordering::Relaxed).max(0).unsigned_abs();
+
+			if limit < size {
+				return Err(ErrorKind::HeapLimitExceeded { size, limit });
+			}
+		}
+
+		if let Some(deadline) = self.deadline {
+			if self.until_deadline_check == 0 {
+				if deadline <= Instant::now() {
+					return Err(ErrorKind::DeadlineExceeded);
+				}
+
+				self.until_deadline_check = INSTRUCTIONS_PER_DEADLINE_CHECK;
+			}
+
+			self.until_deadline_check -= 1;
+		}
+
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn run_with(limits: Limits, code: &str) -> Result<crate::Value> {
+		set_limits(limits);
+		let result = crate::run_code(code);
+		set_limits(Limits::default());
+		result
+	}
+
+	#[test]
+	fn fuel() {
+		let limits = Limits { fuel: Some(10_000), ..Limits::default() };
+
+		assert_matches!(
+			run_with(limits, "while({ true }, {})").unwrap_err().kind,
+			ErrorKind::OutOfFuel
+		);
+		assert_eq!(
+			run_with(limits, "1 + 2").unwrap().downcast::<crate::value::ty::Integer>().unwrap().get(),
+			3
+		);
+	}
+
+	#[test]
+	fn each_opcode_uses_one_fuel() {
+		use crate::value::ToValue;
+		use crate::vm::block::{Builder, Local};
+		use crate::vm::Args;
+
+		let run_block = |fuel, opcodes| {
+			let mut builder = Builder::default();
+			for _ in 0..opcodes {
+				builder.immediate(crate::Value::ONE.to_value(), Local::Scratch);
+			}
+			let block = builder.build();
+
+			set_limits(Limits { fuel: Some(fuel), ..Limits::default() });
+			let result = block.run(Args::default());
+			set_limits(Limits::default());
+			result
+		};
+
+		assert!(run_block(2, 2).is_ok());
+		assert!(run_block(1, 2).is_err());
+
+		// Calls without any opcodes still use up fuel.
+		assert!(run_block(1, 0).is_ok());
+		assert!(run_block(0, 0).is_err());
+	}
+
+	#[test]
+	fn timeout() {
+		let limits = Limits { timeout: Some(Duration::from_millis(20)), ..Limits::default() };
+
+		assert_matches!(
+			run_with(limits, "while({ true }, {})").unwrap_err().kind,
+			ErrorKind::DeadlineExceeded
+		);
+	}
+
+	#[test]
+	fn heap_size() {
+		let limits = Limits { max_heap_size: Some(1_000_000), ..Limits::default() };
+
+		assert_matches!(
+			run_with(limits, "l = []; while({ true }, { l.push([1, 2, 3, 4, 5, 6, 7, 8]) })")
+				.unwrap_err()
+				.kind,
+			ErrorKind::HeapLimitExceeded { .. }
+		);
+
+		// Only what's allocated while the limits are set counts, not anything from beforehand.
+		let _before = crate::value::ty::Text::with_capacity(2_000_000);
+		assert!(run_with(limits, "[1, 2, 3]").is_ok());
+	}
+
+	#[test]
+	fn clearing_limits_disables_them() {
+		set_limits(Limits { fuel: Some(1), ..Limits::default() });
+		assert!(is_enabled());
+		set_limits(Limits::default());
+		assert!(LIMITED.with(|limited| limited.borrow().is_none()));
+	}
+
+	#[test]
+	fn stackframes() {
+		let limits = Limits { max_stackframes: 10, ..Limits::default() };
+
+		assert_matches!(
+			run_with(limits, "f = n -> { (n == 0).then(0.return); f(n - 1) }; f(20)")
+				.unwrap_err()
+				.kind,
+			ErrorKind::StackOverflow
+		);
+		assert!(run_with(limits, "f = n -> { (n == 0).then(0.return); f(n - 1) }; f(5)").is_ok());
+	}
+
+	#[test]
+	fn coroutine_stack_size() {
+		use crate::vm::coroutine::STACK_BYTES_PER_FRAME;
+
+		let code = |depth: usize| {
+			format!(
+				"f = n -> {{ (n == 0).then(0.return); f(n - 1) }}; {{ yield(f({depth})) }}.generator().next()"
+			)
+		};
+		let small = Limits {
+			max_stackframes: 10_000,
+			coroutine_stack_size: STACK_BYTES_PER_FRAME * 4,
+			..Limits::default()
+		};
+		let large = Limits { coroutine_stack_size: STACK_BYTES_PER_FRAME * 16, ..small };
+
+		// Recursing deeper than fits on the stack is an error, rather than overflowing it.
+		assert_matches!(run_with(small, &code(8)).unwrap_err().kind, ErrorKind::StackOverflow);
+		assert!(run_with(large, &code(8)).is_ok());
+
+		// Stacks must have room for at least one frame.
+		assert!(run_with(Limits { coroutine_stack_size: 1, ..small }, &code(0)).is_err());
+	}
+}
@@ -1,13 +1,21 @@
 //! Types relating to Quest's virtual machine.
 mod args;
 pub mod block;
+pub(crate) mod coroutine;
+pub mod coverage;
+pub mod debugger;
+pub mod event_loop;
 pub mod frame;
+pub mod inline_cache;
+pub mod limits;
 mod opcode;
+pub mod profiler;
 mod source_location;
 
 pub use args::Args;
 pub use block::Block;
 pub use frame::Frame;
+pub use limits::Limits;
 pub use opcode::Opcode;
 pub use source_location::SourceLocation;
 
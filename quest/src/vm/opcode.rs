@@ -45,6 +45,10 @@ pub enum Opcode {
 	/// stores it into `dst`. (For longer lists, use [`CreateList`](Self::CreateList))
 	CreateListSimple = opcode_fmt(Variable::Yes, Interned::No, 0, 0),
 
+	/// `CreateMap(dst, count, ...)` Creates a map out of `count` trailing locals, which alternate
+	/// between keys and values, and stores it into `dst`.
+	CreateMap = opcode_fmt(Variable::No, Interned::No, 0, 6),
+
 	/// `ConstLoad(dst, count)` Loads the constant at `count` into `dst`.
 	ConstLoad = opcode_fmt(Variable::No, Interned::No, 0, 1),
 
@@ -161,7 +165,8 @@ pub enum Opcode {
 	CallAttrSimple = opcode_fmt(Variable::Yes, Interned::No, 2, 3),
 
 	/* INTERNED ATTRIBUTES */
-	/// `GetAttr(dst, obj, <intern attr>)`
+	/// `GetAttrIntern(dst, obj, <intern attr>, cache)`. `cache` is the index of the block's inline
+	/// cache that's used for the lookup.
 	GetAttrIntern = opcode_fmt(Variable::No, Interned::Yes, 1, 11),
 
 	/// `GetUnboundAttrIntern(dst, obj, <intern attr>)`
@@ -176,8 +181,9 @@ pub enum Opcode {
 	// <TODO>
 	CallAttrIntern = opcode_fmt(Variable::Yes, Interned::Yes, 1, 15),
 
-	/// `CallAttrSimpleIntern(dst, obj, <intern>, count, ...)` Calls `obj`'s attribute `attr` with
-	/// `count` positional arguments, storing the result into `dst`.
+	/// `CallAttrSimpleIntern(dst, obj, <intern>, cache, count, ...)` Calls `obj`'s attribute `attr`
+	/// with `count` positional arguments, storing the result into `dst`. `cache` is the same as
+	/// for `GetAttrIntern`.
 	CallAttrSimpleIntern = opcode_fmt(Variable::Yes, Interned::Yes, 1, 3),
 
 	/// `DelAttrIntern(dst, obj, <intern>)` Deletes `<intern>` from `obj`. If you want to dynamically
@@ -197,6 +203,12 @@ impl Opcode {
 		((self as u8) & 0b01_0000_00) != 0
 	}
 
+	/// Gets whether `self` is followed by the index of an inline cache, after its intern.
+	#[inline]
+	pub const fn has_inline_cache(self) -> bool {
+		matches!(self, Self::GetAttrIntern | Self::CallAttrSimpleIntern)
+	}
+
 	/// Gets whether `self` takes a variable amount of arguments.
 	#[inline]
 	pub const fn is_variable_simple(self) -> bool {
@@ -257,6 +269,7 @@ impl Opcode {
 		match byte {
 			_ if byte == Self::CreateList as u8 => true,
 			_ if byte == Self::CreateListSimple as u8 => true,
+			_ if byte == Self::CreateMap as u8 => true,
 
 			_ if byte == Self::Mov as u8 => true,
 			_ if byte == Self::Call as u8 => true,
@@ -0,0 +1,319 @@
+//! A profiler for Quest code.
+//!
+//! When the profiler is [started](start), every call to a [`Block`](crate::vm::Block) (via
+//! [`Frame::run`]) and every call to a [`RustFn`] is timed. Each [`Function`] is attributed both
+//! its _inclusive_ time (the total time spent within it, including its callees) and its _exclusive_
+//! time (the time spent within it, excluding its callees). Once [stopped](stop), the results are
+//! returned as a [`Profile`], which can be written as a textual report or as folded stacks (which
+//! most flamegraph tools consume).
+
+use crate::value::ty::RustFn;
+use crate::value::Gc;
+use crate::vm::{Frame, SourceLocation};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt::{self, Display, Formatter};
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+// Checked before every call, so we don't have to touch `PROFILER` when it's not running.
+static IS_ENABLED: AtomicBool = AtomicBool::new(false);
+
+thread_local! {
+	static PROFILER: RefCell<Option<Profiler>> = const { RefCell::new(None) };
+}
+
+/// Something that can be profiled.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Function {
+	/// A block, identified by its `__name__` (if it has one) and where it was defined.
+	Block {
+		/// The `__name__` of the block, if it was set.
+		name: Option<String>,
+		/// Where the block was defined.
+		location: SourceLocation,
+	},
+	/// A function written in Rust.
+	RustFn(RustFn),
+}
+
+impl Function {
+	fn for_frame(frame: Gc<Frame>) -> Self {
+		let (name, location) = match frame.as_ref() {
+			Ok(frameref) => {
+				let name = frameref
+					.block()
+					.as_ref()
+					.ok()
+					.and_then(|block| block.name().ok().flatten())
+					.and_then(|name| name.as_ref().ok().map(|text| text.as_str().to_string()));
+
+				(name, frameref.source_location().clone())
+			}
+			Err(_) => (None, SourceLocation::default()),
+		};
+
+		Self::Block { name, location }
+	}
+}
+
+impl Display for Function {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		match self {
+			Self::Block { name: Some(name), location } => write!(f, "{name} ({location})"),
+			Self::Block { name: None, location } => write!(f, "<unnamed> ({location})"),
+			Self::RustFn(rustfn) => write!(f, "{} (rust)", rustfn.name()),
+		}
+	}
+}
+
+/// The timings for a single [`Function`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Entry {
+	/// What was called.
+	pub function: Function,
+	/// How many times `function` was called.
+	pub calls: u64,
+	/// The time spent within `function`, including the time spent in the functions it called.
+	///
+	/// Recursive calls are only counted once, so this never exceeds the total runtime.
+	pub inclusive: Duration,
+	/// The time spent within `function`, excluding the time spent in the functions it called.
+	pub exclusive: Duration,
+}
+
+#[derive(Default)]
+struct Profiler {
+	entries: Vec<Entry>,
+	indices: HashMap<Function, usize>,
+	stack: Vec<Active>,
+	folded: HashMap<Vec<usize>, Duration>,
+}
+
+struct Active {
+	index: usize,
+	start: Instant,
+	children: Duration,
+}
+
+impl Profiler {
+	fn enter(&mut self, function: Function) {
+		let entries = &mut self.entries;
+		let index = *self.indices.entry(function).or_insert_with_key(|function| {
+			entries.push(Entry {
+				function: function.clone(),
+				calls: 0,
+				inclusive: Duration::ZERO,
+				exclusive: Duration::ZERO,
+			});
+
+			entries.len() - 1
+		});
+
+		self.entries[index].calls += 1;
+		self.stack.push(Active { index, start: Instant::now(), children: Duration::ZERO });
+	}
+
+	fn exit(&mut self) {
+		let path = self.stack.iter().map(|active| active.index).collect::<Vec<_>>();
+		let active = self.stack.pop().expect("exited more functions than were entered");
+		let elapsed = active.start.elapsed();
+		let exclusive = elapsed.saturating_sub(active.children);
+		let entry = &mut self.entries[active.index];
+
+		entry.exclusive += exclusive;
+
+		// Only the outermost call of recursive functions counts towards inclusive time, otherwise
+		// we'd count the same time multiple times.
+		if self.stack.iter().all(|caller| caller.index != active.index) {
+			entry.inclusive += elapsed;
+		}
+
+		if let Some(caller) = self.stack.last_mut() {
+			caller.children += elapsed;
+		}
+
+		*self.folded.entry(path).or_default() += exclusive;
+	}
+
+	fn finish(self) -> Profile {
+		let mut folded = self
+			.folded
+			.into_iter()
+			.map(|(path, duration)| {
+				let names = path
+					.into_iter()
+					.map(|index| self.entries[index].function.to_string().replace(';', ":"))
+					.collect::<Vec<_>>();
+
+				(names.join(";"), duration)
+			})
+			.collect::<Vec<_>>();
+		folded.sort();
+
+		let mut entries = self.entries;
+		entries.sort_by_key(|entry| std::cmp::Reverse(entry.exclusive));
+
+		Profile { entries, folded }
+	}
+}
+
+/// The results of profiling, as returned from [`stop`].
+#[derive(Debug, Clone, Default)]
+pub struct Profile {
+	entries: Vec<Entry>,
+	folded: Vec<(String, Duration)>,
+}
+
+impl Profile {
+	/// Gets the timings for every function that was called, most exclusive time first.
+	#[must_use]
+	pub fn entries(&self) -> &[Entry] {
+		&self.entries
+	}
+
+	/// Gets the timings for `function`, if it was ever called.
+	#[must_use]
+	pub fn entry(&self, function: &Function) -> Option<&Entry> {
+		self.entries.iter().find(|entry| entry.function == *function)
+	}
+
+	/// Writes the `top` functions (by exclusive time) as a table to `out`.
+	///
+	/// # Errors
+	/// Returns any errors that occur when writing to `out`.
+	pub fn write_report<W: Write>(&self, mut out: W, top: usize) -> io::Result<()> {
+		writeln!(out, "{:>10} {:>14} {:>14}  function", "calls", "inclusive", "exclusive")?;
+
+		for entry in self.entries.iter().take(top) {
+			writeln!(
+				out,
+				"{:>10} {:>14} {:>14}  {}",
+				entry.calls,
+				format!("{:.3?}", entry.inclusive),
+				format!("{:.3?}", entry.exclusive),
+				entry.function
+			)?;
+		}
+
+		Ok(())
+	}
+
+	/// Writes the folded stacks to `out`, one stack per line, with the exclusive time spent in
+	/// that stack in microseconds.
+	///
+	/// This is the format that `flamegraph.pl` and `inferno` consume.
+	///
+	/// # Errors
+	/// Returns any errors that occur when writing to `out`.
+	pub fn write_folded<W: Write>(&self, mut out: W) -> io::Result<()> {
+		for (stack, duration) in &self.folded {
+			writeln!(out, "{stack} {}", duration.as_micros())?;
+		}
+
+		Ok(())
+	}
+}
+
+/// Starts profiling the current thread, discarding any previous (unfinished) profile.
+pub fn start() {
+	PROFILER.with(|profiler| *profiler.borrow_mut() = Some(Profiler::default()));
+
+	IS_ENABLED.store(true, Ordering::Relaxed);
+}
+
+/// Stops profiling the current thread, returning the results if it was being profiled.
+///
+/// Functions that are still running when this is called aren't included in the results.
+pub fn stop() -> Option<Profile> {
+	PROFILER.with(|profiler| profiler.borrow_mut().take()).map(Profiler::finish)
+}
+
+/// Returns whether a profiler is possibly running.
+#[inline]
+pub fn is_enabled() -> bool {
+	IS_ENABLED.load(Ordering::Relaxed)
+}
+
+/// Runs `body`, attributing the time it takes to `function` if the current thread is profiled.
+#[inline]
+pub(crate) fn profile<T>(function: impl FnOnce() -> Function, body: impl FnOnce() -> T) -> T {
+	if !is_enabled() {
+		return body();
+	}
+
+	let is_profiling = PROFILER.with(|profiler| match profiler.borrow_mut().as_mut() {
+		Some(profiler) => {
+			profiler.enter(function());
+			true
+		}
+		None => false,
+	});
+
+	let result = body();
+
+	if is_profiling {
+		PROFILER.with(|profiler| {
+			// If the profiler was stopped (or restarted) within `body`, there's nothing to exit.
+			if let Some(profiler) = profiler.borrow_mut().as_mut().filter(|p| !p.stack.is_empty()) {
+				profiler.exit();
+			}
+		});
+	}
+
+	result
+}
+
+pub(crate) fn profile_frame<T>(frame: Gc<Frame>, body: impl FnOnce() -> T) -> T {
+	profile(|| Function::for_frame(frame), body)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn counts_calls_and_time() {
+		start();
+		crate::run_code(
+			r#"
+			fib = n -> { (n <= 1).then(n.return); fib(n - 1) + fib(n - 2) };
+			fib(10)
+		"#,
+		)
+		.unwrap();
+		let profile = stop().unwrap();
+
+		let fib = profile
+			.entries()
+			.iter()
+			.find(
+				|entry| matches!(&entry.function, Function::Block { name: Some(name), .. } if name == "fib"),
+			)
+			.expect("no entry for fib");
+
+		assert_eq!(fib.calls, 177);
+		assert!(fib.exclusive <= fib.inclusive);
+
+		let rustfns = profile.entries().iter().filter(|e| matches!(e.function, Function::RustFn(_)));
+		assert!(rustfns.count() > 0);
+
+		let mut folded = Vec::new();
+		profile.write_folded(&mut folded).unwrap();
+		let folded = String::from_utf8(folded).unwrap();
+		assert!(
+			folded.lines().any(|line| line.contains("fib (") && line.contains(";fib (")),
+			"{folded}"
+		);
+
+		let mut report = Vec::new();
+		profile.write_report(&mut report, 3).unwrap();
+		assert_eq!(String::from_utf8(report).unwrap().lines().count(), 4);
+	}
+
+	#[test]
+	fn stop_without_start() {
+		assert!(stop().is_none());
+	}
+}
@@ -6,7 +6,7 @@ use std::path::PathBuf;
 /// This is distinct from [`parse::SourceLocation`](crate::parse::SourceLocation) in that the `parse`
 /// one doesn't own its `file`, whereas this one does. This is because the `parse` one simply uses
 /// a reference, where this one is expected to last (potentially) forever.
-#[derive(Default, Clone, PartialEq, Eq)]
+#[derive(Default, Clone, PartialEq, Eq, Hash)]
 pub struct SourceLocation {
 	/// The filename (if any).
 	pub file: Option<PathBuf>,
@@ -341,6 +341,20 @@ fn lists_containing_themselves() {
 
 	let result = result.downcast::<Gc<Text>>().unwrap().as_ref().unwrap();
 	assert_eq!(result.as_str(), "[[[...], 4], [[...], 4]]");
+
+	let result = run_code(
+		r#"
+			l = [];
+			l.push(l);
+			l.push(1);
+			assert(l.hash() == l.hash());
+			assert(l.hash() != [[], 1].hash());
+			[l => 2][l]
+		"#,
+	)
+	.unwrap();
+
+	assert_eq!(result.downcast::<Integer>().unwrap(), 2);
 }
 
 #[test]
@@ -532,3 +546,1181 @@ fn all_attr_methods() {
 		"#
 	}
 }
+
+#[test]
+fn map_literals_and_indexing() {
+	run! {
+		r#"
+			map = ["a" => 1, 2 => "two", true => null];
+			assert(3 == map.len());
+			assert(1 == map["a"]);
+			assert("two" == map[2]);
+			assert(null == map["missing"]);
+
+			map["a"] = 10;
+			map[3] = 4;
+			assert(10 == map["a"]);
+			assert(4 == map.len());
+
+			empty = [=>];
+			assert(empty.is_empty());
+			empty.set_default(0);
+			assert(0 == empty["x"]);
+			assert(0 == empty.default());
+		"#
+	}
+}
+
+#[test]
+fn map_methods() {
+	run! {
+		r#"
+			map = ["a" => 1, "b" => 2];
+			assert(map == ["b" => 2, "a" => 1]);
+			assert(map != ["a" => 1]);
+
+			assert(map.includes("a"));
+			assert(2 == map.delete("b"));
+			assert(!map.includes("b"));
+			assert(null == map.delete("b"));
+
+			merged = map.merge(["a" => 3, "c" => 4]);
+			assert(merged == ["a" => 3, "c" => 4]);
+			assert(1 == map["a"]);
+			assert(5 == map.get("z", 5));
+
+			assert(7 == merged.values().sum());
+			assert(2 == merged.keys().count());
+			assert(2 == merged.entries().map(e -> { e[0] }).count());
+		"#
+	}
+}
+
+#[test]
+fn map_keys_are_only_hashed_once() {
+	// Resizing the map mustn't hash `key` again, as it'd raise an error.
+	run! {
+		r#"
+			hashes = [];
+			key = object({ hash = self -> { hashes.push(1); assert(1 == hashes.len()); 1 }; });
+
+			map = [key => "key"];
+			i = 0;
+			while({ i < 100 }, { map[i] = i; :1.i = i + 1 });
+
+			assert(101 == map.len());
+			assert(99 == map[99]);
+			assert(1 == hashes.len());
+		"#
+	}
+
+	let err = run_code(
+		r#"
+			hashes = [];
+			key = object({ hash = self -> { hashes.push(1); assert(1 == hashes.len()); 1 }; });
+			map = [key => "key"];
+			map[key]
+		"#,
+	)
+	.unwrap_err();
+	assert_matches::assert_matches!(err.kind, quest::ErrorKind::AssertionFailed(_));
+}
+
+#[test]
+fn maps_hash_by_their_contents() {
+	run! {
+		r#"
+			a = ["x" => 1, "y" => [1, 2]];
+			b = ["y" => [1.0, 2], "x" => 1];
+			assert(a.hash() == b.hash());
+			assert(a.hash() != ["x" => 2, "y" => [1, 2]].hash());
+
+			maps = [a => "a"];
+			i = 0;
+			while({ i < 100 }, { maps[[i => i]] = i; :1.i = i + 1 });
+			assert("a" == maps[b]);
+			assert(50 == maps[[50 => 50]]);
+
+			recursive = ["x" => 1];
+			recursive["self"] = recursive;
+			assert(recursive.hash() == recursive.hash());
+		"#
+	}
+}
+
+#[test]
+fn set_algebra() {
+	run! {
+		r#"
+			a = [1, 2, 3, 3].to_set();
+			b = [3, 4].to_set();
+			assert(3 == a.len());
+
+			assert((a | b) == [1, 2, 3, 4].to_set());
+			assert((a & b) == [3].to_set());
+			assert((a - b) == [1, 2].to_set());
+			assert((a ^ b) == [1, 2, 4].to_set());
+			assert(a.union(b) == (a | b));
+
+			assert([1, 2].to_set().is_subset(a));
+			assert(!b.is_subset(a));
+			assert(a.is_superset([3].to_set()));
+		"#
+	}
+}
+
+#[test]
+fn set_elements_are_only_hashed_once() {
+	run! {
+		r#"
+			hashes = [];
+			element = object({ hash = self -> { hashes.push(1); assert(1 == hashes.len()); 1 }; });
+
+			set = [element].to_set();
+			i = 0;
+			while({ i < 100 }, { set.add(i); :1.i = i + 1 });
+
+			assert(101 == set.len());
+			assert(set.includes(99));
+			assert(1 == hashes.len());
+		"#
+	}
+}
+
+#[test]
+fn set_membership_and_iteration() {
+	run! {
+		r#"
+			set = [].to_set();
+			assert(set.is_empty());
+			set.add("a").add("b").add("a");
+			assert(2 == set.len());
+			assert(set.includes("a"));
+			assert(set.delete("a"));
+			assert(!set.delete("a"));
+			assert(!set.includes("a"));
+
+			assert(6 == [1, 2, 3].to_set().iter().sum());
+			assert([1, 1, 2].iter().to_set() == [2, 1].to_set());
+
+			# sets hash by their elements, so they can be used as elements themselves
+			assert([1, 2].to_set().hash() == [2.0, 1].to_set().hash());
+			assert([1, 2].to_set().hash() != [1, 3].to_set().hash());
+			sets = [[1, 2].to_set()].to_set();
+			i = 0;
+			while({ i < 100 }, { sets.add([i].to_set()); :1.i = i + 1 });
+			assert(sets.includes([2, 1].to_set()));
+			assert(sets.includes([50].to_set()));
+			assert(101 == sets.len());
+		"#
+	}
+}
+
+#[test]
+fn range_literals() {
+	run! {
+		r#"
+			exclusive = 1..10;
+			inclusive = 1..=10;
+
+			assert(9 == exclusive.len());
+			assert(10 == inclusive.len());
+			assert(!exclusive.includes(10));
+			assert(inclusive.includes(10));
+			assert(!inclusive.includes("1"));
+
+			# ranges can be iterated over more than once
+			assert(45 == exclusive.sum());
+			assert(45 == exclusive.sum());
+
+			assert([0, 3, 6, 9] == (0..10).step(3).to_list());
+			assert([10, 7, 4, 1] == (10..0).step(-3).to_list());
+			assert((10..0).step(-3).includes(4));
+			assert(0 == (5..1).len());
+			assert([3, 2, 1] == 3.downto(0).to_list());
+
+			# ranges hash by their bounds, step and inclusivity
+			assert((1..3).hash() == (1..3).hash());
+			assert((1..3).hash() != (1..=3).hash());
+			assert((1..3).hash() != (1..3).step(2).hash());
+			map = ["x" => 0];
+			i = 0;
+			while({ i < 200 }, { map[i..(i + 1)] = i; :1.i = i + 1 });
+			assert(5 == map[5..6]);
+			assert(150 == map[150..151]);
+		"#
+	}
+}
+
+#[test]
+fn full_width_ranges() {
+	use quest::value::ty::Range;
+
+	run! {
+		r#"
+			max = 4611686018427387903;
+			min = -max - 1;
+
+			assert((min..=max).take(3).to_list() == [min, min + 1, min + 2]);
+			assert(max == (0..max).len());
+			assert(!(min..=max).is_empty());
+		"#
+	}
+
+	let err = run_code("max = 4611686018427387903; min = -max - 1; (min..=max).len()").unwrap_err();
+	assert_matches::assert_matches!(err.kind, quest::ErrorKind::Message(_));
+
+	let range = Range::new(i64::MIN, i64::MAX, true);
+	let range = range.as_ref().unwrap();
+	assert!(range.len().is_err());
+	assert_eq!(range.iter().take(2).collect::<Vec<_>>(), [i64::MIN, i64::MIN + 1]);
+}
+
+#[test]
+fn range_slicing() {
+	run! {
+		r#"
+			list = [1, 2, 3, 4, 5];
+			assert([2, 3] == list[1..3]);
+			assert([2, 3, 4, 5] == list[1..=(-1)]);
+			assert([1, 3, 5] == list[(0..10).step(2)]);
+			assert([5, 4, 3, 2, 1] == list[(4..=0).step(-1)]);
+			assert([] == list[7..9]);
+
+			text = "hello";
+			assert("el" == text[1..3]);
+			assert("hlo" == text[(0..=(-1)).step(2)]);
+			assert("o" == text[-1]);
+		"#
+	}
+}
+
+#[test]
+fn list_sorting() {
+	run! {
+		r#"
+			list = [3, 1, 2];
+			assert([1, 2, 3] == list.sort());
+			assert([3, 1, 2] == list);
+			assert([1, 2, 3, 4, 5, 6, 7, 8, 9] == [9, 8, 7, 6, 5, 4, 3, 2, 1].sort());
+
+			# `sort_by` is stable, so equal keys keep their original order.
+			words = ["bb", "a", "cc", "d"];
+			assert(["a", "d", "bb", "cc"] == words.sort_by(w -> { w.len() }));
+
+			list.sort_in_place();
+			assert([1, 2, 3] == list);
+			list.sort_by_in_place(n -> { -n });
+			assert([3, 2, 1] == list);
+
+			assert(1 == [1, 3, 5, 7].binary_search(3));
+			assert(null == [1, 3, 5, 7].binary_search(4));
+			assert(1 == [3, 1, 2].min());
+			assert(3 == [3, 1, 2].max());
+			assert(null == [].max());
+		"#
+	}
+}
+
+#[test]
+fn list_algorithms() {
+	run! {
+		r#"
+			list = [1, 2, 3, 4, 5];
+			assert([5, 4, 3, 2, 1] == list.reverse());
+			assert(2 == list.index_of(3));
+			assert(null == list.index_of(9));
+			assert(list.includes(4));
+			assert([2, 3] == list.slice(1, 3));
+			assert([4, 5] == list.slice(-2));
+			assert([3, 4, 5, 1, 2] == list.rotate(2));
+			assert([1, 2, 3, 4, 5] == list);
+			assert([5, 1, 2, 3, 4] == list.rotate(-1));
+			assert([[1, 2], [3, 4], [5]] == list.chunks(2));
+			assert([[1, 2, 3], [2, 3, 4], [3, 4, 5]] == list.windows(3));
+
+			list.insert(0, 0).insert(-1, 9);
+			assert([0, 1, 2, 3, 4, 9, 5] == list);
+			assert(9 == list.remove_at(-2));
+			assert([0, 1, 2, 3, 4, 5] == list);
+
+			assert([1, 2, [3, [4]]] == [1, [2, [3, [4]]]].flatten(1));
+			assert([1, 2, 3, 4] == [1, [2, [3, [4]]]].flatten());
+			assert([1, 2, 3] == [1, 2, 1, 3, 2].uniq());
+			assert([[1, "a"], [2, "b"]] == [1, 2, 3].zip(["a", "b"]));
+			assert([7, 7] == [1, 2].fill(7));
+		"#
+	}
+}
+
+#[test]
+fn lazy_iterator_combinators() {
+	run! {
+		r#"
+			assert([1, 2] == [1, 2, 3, 4, 5].take(2).to_list());
+			assert([3, 4, 5] == [1, 2, 3, 4, 5].skip(2).to_list());
+			assert([1, 2] == [1, 2, 3, 1].take_while(x -> { x < 3 }).to_list());
+			assert([3, 1] == [1, 2, 3, 1].skip_while(x -> { x < 3 }).to_list());
+			assert([[1, "a"], [2, "b"]] == ["a", "b"].enumerate(1).to_list());
+			assert([[1, 4], [2, 5]] == [1, 2, 3].zip([4, 5]).to_list());
+			assert([1, 2, 3, 4] == [1].chain([2, 3], [4]).to_list());
+			assert([1, 1, 2, 2] == [1, 2].flat_map(x -> { [x, x] }).to_list());
+			assert([0, 3, 6, 9] == (0..10).step_by(3).to_list());
+			assert([1, 2, 1, 2, 1] == [1, 2].cycle().take(5).to_list());
+
+			# Combinators are lazy, so infinite iterators are fine as long as they're bounded later.
+			assert([0, 2, 4] == (0..1_000_000_000).map(x -> { x * 2 }).take(3).to_list());
+
+			peekable = [1, 2].peekable();
+			assert(1 == peekable.peek());
+			assert(1 == peekable.next());
+			assert(2 == peekable.next());
+			assert(null == peekable.peek());
+		"#
+	}
+}
+
+#[test]
+fn iterable_consumers() {
+	run! {
+		r#"
+			assert(4 == [4, 5].first());
+			assert(5 == [4, 5].last());
+			assert(null == [].first());
+			assert(5 == [4, 5, 6].find(x -> { x > 4 }));
+			assert("b" == ["aa", "b", "ccc"].min_by(x -> { x.len() }));
+			assert("ccc" == ["aa", "b", "ccc"].max_by(x -> { x.len() }));
+			assert([[2, 4], [1, 3]] == [1, 2, 3, 4].partition(x -> { (x % 2) == 0 }));
+
+			groups = [1, 2, 3, 4, 5].group_by(x -> { x % 2 });
+			assert([1, 3, 5] == groups[1]);
+			assert([2, 4] == groups[0]);
+		"#
+	}
+}
+
+#[test]
+fn generators() {
+	run! {
+		r#"
+			counter = (start) -> {
+				n = start;
+				yield(n);
+				n = n + 1;
+				yield(n);
+				n = n + 1;
+				yield(n);
+			}.generator(10);
+
+			assert(10 == counter.next());
+			assert([22, 24] == counter.map(x -> { x * 2 }).to_list());
+
+			# Returning early stops the generator.
+			early = { yield(1); 2.return(); yield(3) }.generator();
+			assert([1] == early.to_list());
+
+			assert([] == {}.generator().to_list());
+		"#
+	}
+}
+
+#[test]
+fn generators_yield_from_nested_blocks() {
+	run! {
+		r#"
+			counting = { i = 0; while({ i < 3 }, { yield(i); :1.i = i + 1 }); }.generator();
+			assert([0, 1, 2] == counting.to_list());
+
+			doubled = { [1, 2, 3].each(x -> { yield(x * 2) }) }.generator();
+			assert(2 == doubled.next());
+			assert([4, 6] == doubled.to_list());
+
+			# `yield` suspends the innermost generator.
+			outer = {
+				inner = { yield("a"); yield("b") }.generator();
+				inner.each(x -> { yield(x + "!") });
+			}.generator();
+			assert(["a!", "b!"] == outer.to_list());
+		"#
+	}
+}
+
+#[test]
+fn yield_outside_generator_fails() {
+	let err = run_code(
+		r#"
+			{ yield(1) }();
+		"#,
+	)
+	.unwrap_err();
+
+	assert!(matches!(err.kind, quest::ErrorKind::Message(_)), "yield didnt fail: {:?}", err);
+}
+
+#[test]
+fn exit_unwinds_to_the_host() {
+	assert_matches::assert_matches!(
+		run_code("x = 1; exit(x + 2); x = 4").unwrap_err().kind,
+		quest::ErrorKind::Exit(3)
+	);
+}
+
+#[test]
+fn restricted_kernel() {
+	use quest::value::ty::{kernel::Capabilities, Kernel};
+	use quest::vm::frame::{root, set_root};
+
+	let original = root();
+	set_root(Kernel::with_capabilities(Capabilities { threads: true, ..Capabilities::NONE }));
+
+	let denied = ["exit(1)", "abort(\"no\")", "print(1)", "{ print(1) }()", "object({ print })"];
+	let results = denied.map(run_code);
+	let allowed = run_code("spawn({ 1 + 2 }).join() + 4");
+
+	set_root(original);
+
+	for (code, result) in denied.iter().zip(results) {
+		assert_matches::assert_matches!(
+			result.unwrap_err().kind,
+			quest::ErrorKind::UnknownAttribute { .. },
+			"{code} should not have access to the kernel function"
+		);
+	}
+
+	assert_eq!(allowed.unwrap().downcast::<Integer>().unwrap().get(), 7);
+	run!("print(1)");
+}
+
+#[test]
+fn from_value_conversions() {
+	use quest::FromValue;
+	use std::collections::HashMap;
+
+	assert_eq!(i64::from_value(run_code("1 + 2").unwrap()).unwrap(), 3);
+	assert_eq!(f64::from_value(run_code("3").unwrap()).unwrap(), 3.0);
+	assert!(bool::from_value(run_code("[]").unwrap()).unwrap());
+	assert_eq!(String::from_value(run_code("12").unwrap()).unwrap(), "12");
+	assert_eq!(Option::<i64>::from_value(run_code("null").unwrap()).unwrap(), None);
+	assert_eq!(Vec::<i64>::from_value(run_code("[1, 2, 3]").unwrap()).unwrap(), [1, 2, 3]);
+	assert_eq!(
+		<(i64, String)>::from_value(run_code("[1, 'a']").unwrap()).unwrap(),
+		(1, "a".to_string())
+	);
+	assert!(<(i64, i64)>::from_value(run_code("[1]").unwrap()).is_err());
+
+	let map = HashMap::<String, i64>::from_value(run_code("['a' => 1, 'b' => 2]").unwrap()).unwrap();
+	assert_eq!(map, HashMap::from([("a".to_string(), 1), ("b".to_string(), 2)]));
+
+	let list = Gc::<List>::from_value(run_code("[1]").unwrap()).unwrap();
+	assert_eq!(list.as_ref().unwrap().len(), 1);
+	assert!(Gc::<List>::from_value(run_code("1").unwrap()).is_err());
+}
+
+#[test]
+fn derived_value_conversions() {
+	use quest::{FromValue, ToValue};
+
+	#[derive(Debug, PartialEq, FromValue, ToValue)]
+	struct Point {
+		x: i64,
+		r#y: i64,
+		label: Option<String>,
+	}
+
+	let point = Point::from_value(run_code("object({ x = 1; y = 2; label = 'origin'; })").unwrap());
+	assert_eq!(point.unwrap(), Point { x: 1, y: 2, label: Some("origin".to_string()) });
+	assert!(Point::from_value(run_code("object({ x = 1; })").unwrap()).is_err());
+
+	let point = Point { x: 3, y: 4, label: None }.to_value();
+	assert_eq!(Point::from_value(point).unwrap(), Point { x: 3, y: 4, label: None });
+
+	let mut interpreter = quest::Interpreter::new();
+	interpreter.set_global("point", point).unwrap();
+	let sum = interpreter.eval("point.x + point.y").unwrap();
+	assert_eq!(i64::from_value(sum).unwrap(), 7);
+}
+
+#[test]
+fn quest_fn_argument_parsing() {
+	use quest::value::ty::RustFn;
+	use quest::value::Callable;
+	use quest::{FromValue, ToValue};
+
+	/// Clamps `value` to be at most `max`, and at least `min` (if given).
+	#[quest::quest_fn]
+	fn clamp(
+		value: i64,
+		min: Option<i64>,
+		#[keyword] max: i64,
+		#[keyword] step: Option<i64>,
+	) -> i64 {
+		value.max(min.unwrap_or(i64::MIN)).min(max) / step.unwrap_or(1)
+	}
+
+	#[quest::quest_fn(name = "sum_all")]
+	fn sum(first: i64, #[rest] rest: Vec<i64>) -> Result<i64> {
+		Ok(first + rest.iter().sum::<i64>())
+	}
+
+	let call = |func: RustFn, positional: &[Value], keyword: &[(&str, Value)]| {
+		func.call(Args::new(positional, keyword)).map(|value| i64::from_value(value).unwrap())
+	};
+	let max = [("max", 10.to_value())];
+
+	assert_eq!(call(qs_clamp, &[3.to_value()], &max).unwrap(), 3);
+	assert_eq!(call(qs_clamp, &[30.to_value()], &max).unwrap(), 10);
+	assert_eq!(call(qs_clamp, &[3.to_value(), 5.to_value()], &max).unwrap(), 5);
+	assert_eq!(call(qs_clamp, &[30.to_value()], &[max[0], ("step", 2.to_value())]).unwrap(), 5);
+	assert!(call(qs_clamp, &[3.to_value()], &[]).is_err());
+	assert!(call(qs_clamp, &[3.to_value()], &[max[0], ("nope", 1.to_value())]).is_err());
+	assert!(call(qs_clamp, &[], &max).is_err());
+	assert!(call(qs_clamp, &[1.to_value(), 2.to_value(), 3.to_value()], &max).is_err());
+
+	assert_eq!(
+		qs_clamp.help(),
+		"clamp(value, [min], max:, [step:])\n\nClamps `value` to be at most `max`, and at least `min` (if given)."
+	);
+	assert_eq!(qs_clamp.signature().unwrap().arity(), (1, Some(2)));
+	assert_eq!(qs_sum.name(), "sum_all");
+	assert_eq!(qs_sum.signature().unwrap().arity(), (1, None));
+
+	let mut interpreter = quest::Interpreter::new();
+	interpreter.set_global("sum", qs_sum.to_value()).unwrap();
+	let result = interpreter.eval("sum(1) + sum(1, 2, 3)").unwrap();
+	assert_eq!(i64::from_value(result).unwrap(), 7);
+	assert!(interpreter.eval("sum()").is_err());
+	assert_eq!(i64::from_value(interpreter.eval("sum(2.5, true)").unwrap()).unwrap(), 3);
+	assert!(interpreter.eval("sum(1, [])").is_err());
+
+	run!("assert(3 == 1 + 2); assert(-1 == 1 - 2); assert(6 == 2 * 3);");
+	assert!(run_code("1 - 'a'").is_err());
+}
+
+#[test]
+fn native_types() {
+	use quest::value::ty::{class, native::NativeType, Native};
+	use quest::{FromValue, ToValue};
+
+	#[derive(Debug)]
+	struct Celsius(f64);
+	struct Fahrenheit(f64);
+
+	#[quest::quest_fn]
+	fn to_fahrenheit(#[this] celsius: Gc<Native<Celsius>>) -> Result<Value> {
+		let celsius = celsius.as_ref()?.0;
+		Ok(Native::new(Fahrenheit(celsius * 9.0 / 5.0 + 32.0))?.to_value())
+	}
+
+	#[quest::quest_fn]
+	fn degrees(#[this] fahrenheit: Gc<Native<Fahrenheit>>) -> Result<f64> {
+		Ok(fahrenheit.as_ref()?.0)
+	}
+
+	impl NativeType for Celsius {
+		const TYPENAME: &'static str = "Celsius";
+
+		fn init_class(class: &mut class::Builder) -> Result<()> {
+			class.set_attr("to_fahrenheit".to_value(), qs_to_fahrenheit.to_value())
+		}
+	}
+
+	impl NativeType for Fahrenheit {
+		const TYPENAME: &'static str = "Fahrenheit";
+
+		fn init_class(class: &mut class::Builder) -> Result<()> {
+			class.set_attr("degrees".to_value(), qs_degrees.to_value())
+		}
+	}
+
+	let celsius = Native::new(Celsius(100.0)).unwrap().to_value();
+	assert_eq!(celsius.typename(), "Celsius");
+	assert!(celsius.downcast::<Gc<Native<Celsius>>>().is_some());
+	assert!(celsius.downcast::<Gc<Native<Fahrenheit>>>().is_none());
+	assert!(celsius.downcast::<Gc<List>>().is_none());
+	assert_eq!(
+		Native::<Celsius>::class().unwrap().id(),
+		Native::<Celsius>::class().unwrap().id()
+	);
+
+	let mut interpreter = quest::Interpreter::new();
+	interpreter.set_global("boiling", celsius).unwrap();
+
+	let result = interpreter.eval("boiling.to_fahrenheit().degrees()").unwrap();
+	assert_eq!(f64::from_value(result).unwrap(), 212.0);
+
+	let err = interpreter.eval("boiling.to_fahrenheit().to_fahrenheit()").unwrap_err();
+	assert!(matches!(err.kind, quest::ErrorKind::UnknownAttribute { .. }), "{err:?}");
+
+	let err = quest::value::Callable::call(qs_degrees.to_value(), Args::new(&[celsius], &[]));
+	assert_matches::assert_matches!(
+		err.unwrap_err().kind,
+		quest::ErrorKind::InvalidTypeGiven { expected: "Fahrenheit", given: "Celsius" }
+	);
+}
+
+#[test]
+fn reflection() {
+	use quest::value::ty::Object;
+	use quest::ToValue;
+
+	let rex = run_code(
+		r#"
+			Animal = object({ speak = () -> { 'hi' }; legs = 4; });
+			Dog = object([Animal], { bark = () -> { 'woof' }; legs = 3; });
+			object([Dog], { name = 'rex'; })
+		"#,
+	)
+	.unwrap();
+
+	let names = |attrs: Vec<(Value, Value)>| {
+		attrs
+			.into_iter()
+			.map(|(name, _)| name.try_downcast::<Gc<Text>>().unwrap().as_ref().unwrap().to_string())
+			.collect::<Vec<_>>()
+	};
+
+	assert_eq!(names(rex.own_attributes().unwrap()), ["__block__", "__args__", "name"]);
+
+	// `legs` comes from `Dog`, and is only listed once despite `Animal` also defining it.
+	let all = names(rex.all_attributes().unwrap());
+	assert_eq!(all.iter().filter(|name| *name == "legs").count(), 1);
+	assert!(all.iter().position(|name| name == "name") < all.iter().position(|name| name == "bark"));
+	assert!(
+		all.iter().position(|name| name == "bark") < all.iter().position(|name| name == "speak")
+	);
+	assert!(all.iter().any(|name| name == "__get_attr__"));
+
+	let dog = rex.parents().unwrap()[0];
+	let animal = dog.parents().unwrap()[0];
+	let ancestors = rex.ancestors().unwrap();
+	assert!(ancestors[0].is_identical(dog));
+	assert!(ancestors[1].is_identical(animal));
+	assert!(rex.inherits_from(Object::instance()).unwrap());
+	assert!(!animal.inherits_from(dog).unwrap());
+
+	let three = 3_i64.to_value();
+	assert!(three.own_attributes().unwrap().is_empty());
+	assert!(three.parents().unwrap()[0].is_identical(run_code("Integer").unwrap()));
+
+	let result = run_code(
+		r#"
+			Animal = object({ speak = () -> { 'hi' }; });
+			rex = object([Animal], { name = 'rex'; });
+
+			assert(rex.__attrs__() == ['__block__', '__args__', 'name']);
+			assert(rex.__all_attrs__().includes('speak'));
+			assert(rex.__ancestors__()[0] == Animal);
+			assert(rex.is_a(Animal));
+			assert(3.is_a(Integer));
+			assert(!3.is_a(Animal));
+			assert(rex.responds_to('speak'));
+			assert(!rex.responds_to('bark'));
+			[3.typename(), 'a'.typename(), [].typename(), rex.typename()]
+		"#,
+	)
+	.unwrap();
+
+	let typenames = result.try_downcast::<Gc<List>>().unwrap();
+	let typenames = typenames.as_ref().unwrap();
+	let typenames = typenames
+		.as_slice()
+		.iter()
+		.map(|name| name.try_downcast::<Gc<Text>>().unwrap().as_ref().unwrap().to_string())
+		.collect::<Vec<_>>();
+	assert_eq!(typenames, ["Integer", "Text", "List", "Frame"]);
+}
+
+#[test]
+fn method_resolution_order() {
+	let result = run_code(
+		r#"
+			Base = object({ greet = self -> { 'base' }; });
+			Left = object([Base], { greet = self -> { 'left' }; });
+			Right = object([Base], {
+				greet = self -> { 'right:' + self.__super__(Right, 'greet')() };
+				who = self -> { 'right' };
+			});
+			Bottom = object([Left, Right], {});
+
+			mro = Bottom.__mro__();
+			assert(mro[0] == Bottom);
+			assert(mro[1] == Left);
+			assert(mro[2] == Right); # depth-first would've searched `Base` first
+			assert(mro[3] == Base);
+			assert(Bottom.__ancestors__()[0] == Left);
+
+			assert(Bottom.who() == 'right');
+			[Bottom.greet(), Bottom.__super__(Left, 'greet')()]
+		"#,
+	)
+	.unwrap();
+
+	let result = result.try_downcast::<Gc<List>>().unwrap();
+	let result = result.as_ref().unwrap();
+	assert_eq!(
+		result.as_slice()[0].try_downcast::<Gc<Text>>().unwrap().as_ref().unwrap().as_str(),
+		"left"
+	);
+	assert_eq!(
+		result.as_slice()[1].try_downcast::<Gc<Text>>().unwrap().as_ref().unwrap().as_str(),
+		"right:base"
+	);
+
+	let err = run_code(
+		r#"
+			Base = object({});
+			Derived = object([Base], {});
+			object([Base, Derived], {})
+		"#,
+	)
+	.unwrap_err();
+	assert_matches::assert_matches!(err.kind, quest::ErrorKind::InconsistentParents(_));
+
+	let err = run_code(
+		r#"
+			a = object({});
+			b = object([a], {});
+			a.__parents__ = [b];
+		"#,
+	)
+	.unwrap_err();
+	assert_matches::assert_matches!(err.kind, quest::ErrorKind::CyclicParents(_));
+
+	// Lookups see parents that are modified in place.
+	run_code(
+		r#"
+			Base = object({ who = self -> { 'base' }; });
+			Left = object([Base], { who = self -> { 'left' }; });
+			Right = object([Base], { who = self -> { 'right' }; });
+			Bottom = object([Left, Right], {});
+			assert(Bottom.who() == 'left');
+
+			Bottom.__parents__.shift();
+			Bottom.__parents__.push(Left);
+			assert(Bottom.who() == 'right');
+		"#,
+	)
+	.unwrap();
+
+	// Modifying them is checked the same way as assigning them.
+	let err = run_code(
+		r#"
+			a = object({});
+			b = object([a], {});
+			a.__parents__.push(b);
+		"#,
+	)
+	.unwrap_err();
+	assert_matches::assert_matches!(err.kind, quest::ErrorKind::CyclicParents(_));
+}
+
+#[test]
+fn classes() {
+	let result = run_code(
+		r#"
+			class Animal {
+				legs = 4;
+				init = (self, name) -> { self.name = name };
+				speak = self -> { self.name + ' makes a sound' };
+			};
+
+			class Dog(Animal) {
+				speak = self -> { self.__super__(Dog, 'speak')() + ': woof' };
+			};
+
+			rex = Dog.new('rex');
+			assert(rex.is_a(Dog));
+			assert(rex.is_a(Animal));
+			assert(rex.is_a(Class));
+			assert(rex.typename() == 'Dog');
+			assert(rex.legs == 4); # class attributes are visible from instances
+			assert(Dog.legs == 4);
+			assert(Animal.name() == 'Animal'); # but instance attributes aren't on the class
+
+			assert(Dog.name() == 'Dog');
+			assert(Dog.to_text() == 'Dog');
+			assert(Dog.dbg() == '<class Dog>');
+			assert(rex.to_text() == 'Dog(name: "rex")');
+
+			# `class` is only a keyword when it's followed by a name
+			class = 3;
+			assert(4 == class + 1);
+
+			# A class's own `to_text` takes precedence over the default, but the default takes
+			# precedence over `Object`'s.
+			class Point(Object) { to_text = self -> { 'point' } };
+			assert(Point.new().to_text() == 'point');
+			assert(Class.new('Empty', [Object]).new().to_text() == 'Empty()');
+
+			rex.speak()
+		"#,
+	)
+	.unwrap();
+
+	assert_eq!(
+		result.try_downcast::<Gc<Text>>().unwrap().as_ref().unwrap().as_str(),
+		"rex makes a sound: woof"
+	);
+
+	let err = run_code("class A {}; class B(A) {}; class C(A, B) {}").unwrap_err();
+	assert_matches::assert_matches!(err.kind, quest::ErrorKind::InconsistentParents(_));
+
+	// Without an `init`, there's nothing to pass arguments to.
+	let err = run_code("class A {}; A.new(1, 2)").unwrap_err();
+	assert_matches::assert_matches!(
+		err.kind,
+		quest::ErrorKind::PositionalArgumentMismatch { given: 2, expected: 0 }
+	);
+}
+
+#[test]
+fn inline_caches() {
+	use quest::vm::inline_cache;
+
+	let before = inline_cache::stats();
+	let result = run_code(
+		r#"
+			class Counter {
+				step = self -> { 1 };
+			};
+
+			counter = Counter.new();
+			total = 0;
+			i = 0;
+			while({ i < 10 }, {
+				:1.total = total + counter.step();
+				:1.i = i + 1;
+			});
+
+			# Redefining a method is seen by call sites that already cached the old one.
+			Counter.step = self -> { 100 };
+			total = total + counter.step();
+
+			# So are attributes set directly on the object.
+			counter.step = self -> { 1000 };
+			total + counter.step()
+		"#,
+	)
+	.unwrap();
+
+	assert_eq!(result.try_downcast::<Integer>().unwrap(), 1110);
+
+	let after = inline_cache::stats();
+	assert!(after.hits > before.hits);
+	assert!(after.misses > before.misses);
+}
+
+#[test]
+fn deep_freeze_and_copying() {
+	let result = run_code(
+		r#"
+			class Point {
+				init = (self, x, y) -> { self.x = x; self.y = y };
+			};
+
+			points = [Point.new(1, 2), [Point.new(0, 0)]];
+			assert(!points.is_frozen());
+			points.deep_freeze();
+
+			assert(points.is_frozen());
+			assert(points[0].is_frozen());
+			assert(points[1][0].is_frozen());
+			assert(!Point.is_frozen()); # classes aren't frozen
+			assert(3.is_frozen());
+
+			# `dup` makes an unfrozen copy that shares its contents...
+			shallow = points.dup();
+			assert(!shallow.is_frozen());
+			assert(shallow[0].is_frozen());
+			shallow.push(4);
+			assert(points.len() == 2);
+
+			# ...whereas `clone` copies them as well.
+			deep = points.clone();
+			assert(!deep[0].is_frozen());
+			assert(!deep[1][0].is_frozen());
+			deep[0].x = 10;
+			assert(points[0].x == 1);
+
+			copy = Point.new(3, 4).dup();
+			assert(copy.is_a(Point));
+			copy.y
+		"#,
+	)
+	.unwrap();
+
+	assert_eq!(result.try_downcast::<Integer>().unwrap(), 4);
+
+	let err = run_code("x = [1].freeze(); x.push(2)").unwrap_err();
+	assert_matches::assert_matches!(err.kind, quest::ErrorKind::ValueFrozen(_));
+
+	let err = run_code("object().dup()").unwrap_err();
+	assert_matches::assert_matches!(err.kind, quest::ErrorKind::Message(_));
+}
+
+#[test]
+fn comparison_protocol_conformance() {
+	use quest::value::ty::Null;
+	use quest::Intern;
+
+	let values = run_code(
+		r#"
+			big = 4611686018427387903 + 1;
+			[
+				0, 1, -1, 4611686018427387903, big, 4611686018427387903 * 4,
+				0.0, -0.0, 1.0, 1.5, -1.5, 2.0 ** 62.0, 0.0 / 0.0,
+				"", "a", "ab", "b", "1",
+				[], [1], [1, 2], [1.0, 2], [2], [1, "a"], ["a"],
+				true, false, null, object(),
+				["a" => 1], ["a" => 1.0], ["a" => 2], ["a" => 1, "b" => 2], ["b" => 2, "a" => 1],
+				[1, 2].to_set(), [2.0, 1].to_set(), [1].to_set(), [[1] => 2].keys().to_set(),
+				1..3, 1..3, 1..=3, (1..3).step(2), 1..4
+			]
+		"#,
+	)
+	.unwrap()
+	.try_downcast::<Gc<List>>()
+	.unwrap()
+	.as_ref()
+	.unwrap()
+	.as_slice()
+	.to_vec();
+
+	let call = |lhs: Value, attr, rhs: &[Value]| lhs.call_attr(attr, Args::new(rhs, &[])).unwrap();
+	let cmp = |lhs: Value, rhs: Value| {
+		let cmp = call(lhs, Intern::op_cmp, &[rhs]);
+		(!cmp.is_a::<Null>()).then(|| cmp.try_downcast::<Integer>().unwrap().get())
+	};
+	let hash = |value: Value| call(value, Intern::hash, &[]).try_downcast::<Integer>().unwrap();
+
+	for &lhs in &values {
+		for &rhs in &values {
+			let eql = call(lhs, Intern::op_eql, &[rhs]).try_downcast::<Boolean>().unwrap();
+			let ordering = cmp(lhs, rhs);
+			let context = format!("{lhs:?} and {rhs:?}");
+
+			assert_eq!(eql, ordering == Some(0), "`==` and `<=>` disagree for {context}");
+			assert_eq!(ordering.map(|o| -o), cmp(rhs, lhs), "`<=>` isn't antisymmetric for {context}");
+			assert!(matches!(ordering, None | Some(-1..=1)), "bad `<=>` for {context}");
+
+			if eql {
+				assert_eq!(hash(lhs), hash(rhs), "equal values hash differently for {context}");
+				assert_eq!(lhs.try_hash().unwrap(), rhs.try_hash().unwrap(), "for {context}");
+			}
+
+			if !lhs.is_identical(rhs) {
+				assert_eq!(lhs.try_eq(rhs).unwrap(), eql, "`try_eq` disagrees for {context}");
+			}
+
+			let partial_cmp = lhs.try_partial_cmp(rhs).unwrap();
+			assert_eq!(partial_cmp.map(|o| o as i64), ordering, "for {context}");
+
+			match (ordering, lhs.call_attr(Intern::op_lth, Args::new(&[rhs], &[]))) {
+				(Some(ordering), Ok(lth)) => assert_eq!(lth.downcast::<Boolean>(), Some(ordering < 0)),
+				(None, Ok(lth)) => assert_eq!(lth.downcast::<Boolean>(), Some(false), "for {context}"),
+				(None, Err(_)) => {}
+				(Some(_), Err(err)) => panic!("`<` failed for {context}: {err}"),
+			}
+		}
+	}
+
+	run!(
+		r#"
+			big = 4611686018427387903 + 1;
+
+			assert(1 == 1.0);
+			assert(1 < 1.5);
+			assert(-1.5 < -1);
+			assert(big > 4611686018427387903);
+			assert(big == 2.0 ** 62.0);
+			assert(big.hash() == (2.0 ** 62.0).hash());
+			nan = 0.0 / 0.0;
+			assert(!(nan < 1));
+			assert(nan != nan);
+
+			assert("a" < "ab");
+			assert("ab" < "b");
+			assert(("b" <=> "a") == 1);
+
+			assert([1] < [1, 2]);
+			assert([1, 2] < [2]);
+			assert([1, 2] == [1.0, 2]);
+			assert([1, 2].hash() == [1.0, 2].hash());
+
+			assert(false < true);
+			assert((null <=> null) == 0);
+			assert((1 <=> "1") == null);
+		"#
+	);
+
+	let err = run_code(r#"1 < "1""#).unwrap_err();
+	assert_matches::assert_matches!(err.kind, quest::ErrorKind::Message(_));
+}
+
+#[test]
+fn threads_channels_and_locks() {
+	let result = run_code(
+		r#"
+			# Workers send their results over a channel, which is iterable once closed. Values are
+			# passed as arguments, rather than looked up in this scope while it's being assigned to.
+			results = Channel.new(1);
+			send_result = (results, n) -> { results.send(n * 10) };
+			workers = [1, 2, 3].map(n -> { Thread.spawn(send_result, results, n) }).to_list();
+			close_after = (workers, results) -> { workers.each(w -> { w.join() }); results.close() };
+			Thread.spawn(close_after, workers, results);
+			assert(results.sum() == 60);
+			assert(results.is_closed());
+			assert(results.try_recv() == null);
+
+			# Mutexes serialize updates from multiple threads.
+			counter = Mutex.new(0);
+			increment = counter -> {
+				i = 0;
+				while({ i < 50 }, { counter.update(n -> { n + 1 }); :1.i = i + 1 });
+			};
+			threads = [1, 2, 3, 4].map(_ -> { Thread.spawn(increment, counter) }).to_list();
+			threads.each(thread -> { thread.join() });
+			assert(counter.lock(n -> { n }) == 200);
+
+			# Condvars wait until they're notified.
+			ready = Mutex.new(false);
+			condvar = Condvar.new();
+			wait_until_ready = (ready, condvar) -> {
+				ready.lock(_ -> {
+					while({ !ready.get() }, { condvar.wait(ready) });
+					"woke up"
+				})
+			};
+			waiter = Thread.spawn(wait_until_ready, ready, condvar);
+			ready.lock(_ -> { ready.set(true); condvar.notify_all() });
+			assert(waiter.join() == "woke up");
+
+			# `select` returns whichever channel has a value first.
+			idle = Channel.new();
+			busy = Channel.new();
+			Thread.spawn(channel -> { channel.send(5) }, busy);
+			chosen = Channel.select(idle, busy);
+			assert(chosen[1] == 5);
+
+			spawn({ 1 + 2 }).join()
+		"#,
+	)
+	.unwrap();
+
+	assert_eq!(result.downcast::<Integer>().unwrap().get(), 3);
+
+	let err = run_code("Mutex.new(1).get()").unwrap_err();
+	assert_matches::assert_matches!(err.kind, quest::ErrorKind::Message(_));
+
+	let err = run_code("Thread.spawn({ 1.nope() }).join()").unwrap_err();
+	assert_matches::assert_matches!(err.kind, quest::ErrorKind::UnknownAttribute { .. });
+}
+
+#[test]
+fn contended_values_wait_for_each_other() {
+	let result = run_code(
+		r#"
+			# Without a `Mutex`, each `push` still waits for the others to finish.
+			list = [];
+			push_all = list -> {
+				i = 0;
+				while({ i < 200 }, { list.push(i); :1.i = i + 1 });
+			};
+			threads = [1, 2, 3, 4].map(_ -> { Thread.spawn(push_all, list) }).to_list();
+			threads.each(thread -> { thread.join() });
+			list.len()
+		"#,
+	)
+	.unwrap();
+
+	assert_eq!(result.downcast::<Integer>().unwrap().get(), 800);
+}
+
+#[test]
+fn tasks_and_the_event_loop() {
+	let result = run_code(
+		r#"
+			# Tasks run one at a time, switching whenever one awaits something unfinished.
+			log = [];
+			worker = (log, name, seconds) -> {
+				log.push(name + " start");
+				sleep(seconds);
+				log.push(name + " end");
+				name
+			};
+			slow = Task.spawn(worker, log, "slow", 0.05);
+			fast = Task.spawn(worker, log, "fast", 0.01);
+			assert(!slow.is_done());
+			assert(await(fast) == "fast");
+			assert(slow.await() == "slow");
+			assert(log == ["slow start", "fast start", "fast end", "slow end"]);
+
+			# Results can be combined, and awaited from within other tasks.
+			double = n -> { n * 2 };
+			sum = (a, b, c) -> {
+				results = await(Task.all([a, b, c]));
+				results.sum()
+			};
+			doubles = [1, 2, 3].map(n -> { Task.spawn(double, n) }).to_list();
+			assert(await(Task.spawn(sum, doubles[0], doubles[1], doubles[2])) == 12);
+			assert(await(Task.all(doubles)) == [2, 4, 6]);
+			slowest = Task.sleep(1);
+			assert(await(Task.race([slowest, Task.after(0.01, double, 5)])) == 10);
+			slowest.cancel();
+			assert(await(3) == 3);
+
+			# Repeating timers run until they're cancelled.
+			ticks = [];
+			ticker = Task.every(0.01, ticks -> { ticks.push(1) }, ticks);
+			sleep(0.1);
+			assert(ticker.cancel());
+			assert(!ticker.cancel());
+			assert(ticks.len() >= 2);
+
+			# Errors propagate to whoever awaits the task.
+			failing = Task.spawn({ 1.nope() });
+			Task.spawn(failing -> { await(failing); "unreachable" }, failing)
+		"#,
+	)
+	.unwrap();
+
+	let err = quest::value::ty::task::await_value(result).unwrap_err();
+	assert_matches::assert_matches!(err.kind, quest::ErrorKind::UnknownAttribute { .. });
+
+	let err = run_code("await(Task.race([]))").unwrap_err();
+	assert_matches::assert_matches!(err.kind, quest::ErrorKind::Message(_));
+
+	// `Task.all` and `Task.race` take a single list, which must only contain tasks.
+	let err = run_code("Task.all([Task.sleep(0), 3])").unwrap_err();
+	assert_matches::assert_matches!(err.kind, quest::ErrorKind::InvalidTypeGiven { .. });
+	let err = run_code("Task.all(Task.sleep(0), Task.sleep(0))").unwrap_err();
+	assert_matches::assert_matches!(err.kind, quest::ErrorKind::PositionalArgumentMismatch { .. });
+
+	// Tasks' stacks are small, but recursing too deeply is still just an error.
+	let err = run_code("f = n -> { f(n + 1) }; Task.spawn(f, 0).await()").unwrap_err();
+	assert_matches::assert_matches!(err.kind, quest::ErrorKind::StackOverflow);
+}
+
+#[test]
+fn tasks_await_from_nested_blocks() {
+	run! {
+		r#"
+			log = [];
+			looping = Task.spawn({
+				i = 0;
+				while({ i < 3 }, { sleep(0.03); log.push(i); :1.i = i + 1 });
+				"looped"
+			});
+			iterating = Task.spawn({
+				["a", "b"].each(x -> { await(Task.sleep(0.005)); log.push(x) });
+				"iterated"
+			});
+			assert(await(Task.all([looping, iterating])) == ["looped", "iterated"]);
+			assert(log == ["a", "b", 0, 1, 2]);
+		"#
+	}
+
+	let err =
+		run_code("Task.spawn({ { sleep(0); yield(1) }.generator().next() }).await()").unwrap_err();
+	assert_matches::assert_matches!(err.kind, quest::ErrorKind::Message(_));
+}